@@ -0,0 +1,100 @@
+use std::{collections::HashSet, hash::Hash};
+
+use strum::IntoEnumIterator;
+
+use crate::*;
+
+/// Separates the loadout, mutators, and perks in a build code
+const BUILD_CODE_SEPARATOR: char = '-';
+
+/// The choices that made up a run, which can be shared as a short code and replicated in another run
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Build {
+    pub loadout: Loadout,
+    pub mutators: HashSet<Mutator>,
+    pub perks: HashSet<PerkType>,
+}
+
+impl Build {
+    /// Encodes this build as a short code, made up of the loadout's index followed by one bit per mutator and one bit per perk, all in hex.
+    /// Indexes and bits follow the order the variants are declared in, so new variants need to be added at the end to keep old codes working.
+    pub fn to_code(&self) -> String {
+        let loadout_index = Loadout::iter()
+            .position(|loadout| loadout == self.loadout)
+            .expect("loadout should be one of the loadouts");
+        format!(
+            "{:x}{BUILD_CODE_SEPARATOR}{:x}{BUILD_CODE_SEPARATOR}{:x}",
+            loadout_index,
+            to_bits(&self.mutators, Mutator::iter()),
+            to_bits(&self.perks, PerkType::iter()),
+        )
+    }
+
+    /// Decodes a build from a code made by `to_code`, or returns `None` if the code isn't valid
+    pub fn from_code(code: &str) -> Option<Build> {
+        let mut parts = code.trim().split(BUILD_CODE_SEPARATOR);
+        let loadout_index = usize::from_str_radix(parts.next()?, 16).ok()?;
+        let mutator_bits = u64::from_str_radix(parts.next()?, 16).ok()?;
+        let perk_bits = u64::from_str_radix(parts.next()?, 16).ok()?;
+        if parts.next().is_some() {
+            return None;
+        }
+
+        let mutators = from_bits(mutator_bits, Mutator::iter())?;
+        if mutators.len() > MAX_ACTIVE_MUTATORS {
+            return None;
+        }
+
+        Some(Build {
+            loadout: Loadout::iter().nth(loadout_index)?,
+            mutators,
+            perks: from_bits(perk_bits, PerkType::iter())?,
+        })
+    }
+
+    /// Gets a short user-facing description of this build
+    pub fn get_summary(&self) -> String {
+        let plural = |amount: usize| if amount == 1 { "" } else { "s" };
+        format!(
+            "{}, {} mutator{}, {} perk{}",
+            self.loadout.get_name(),
+            self.mutators.len(),
+            plural(self.mutators.len()),
+            self.perks.len(),
+            plural(self.perks.len()),
+        )
+    }
+}
+
+/// Packs the chosen values into a number with one bit per possible value
+fn to_bits<T: Eq + Hash>(chosen: &HashSet<T>, all: impl Iterator<Item = T>) -> u64 {
+    all.enumerate()
+        .filter(|(_, value)| chosen.contains(value))
+        .fold(0, |bits, (i, _)| bits | (1 << i))
+}
+
+/// Unpacks a number made by `to_bits`, or returns `None` if it has bits set that don't correspond to any value
+fn from_bits<T: Eq + Hash>(bits: u64, all: impl Iterator<Item = T>) -> Option<HashSet<T>> {
+    let mut chosen = HashSet::new();
+    let mut remaining_bits = bits;
+    for (i, value) in all.enumerate() {
+        if bits & (1 << i) != 0 {
+            chosen.insert(value);
+            remaining_bits &= !(1 << i);
+        }
+    }
+
+    if remaining_bits != 0 {
+        return None;
+    }
+
+    Some(chosen)
+}
+
+/// A build imported on the menu, to be replicated in the next run
+#[derive(Resource)]
+pub struct ImportedBuild(pub Option<Build>);
+
+/// The build from the most recently finished run
+#[derive(Resource)]
+pub struct LastRunBuild(pub Build);
@@ -0,0 +1,238 @@
+use strum::IntoEnumIterator;
+
+use crate::*;
+
+pub struct CodexPlugin;
+
+impl Plugin for CodexPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Codex), codex_setup)
+            .add_systems(
+                OnExit(GameState::Codex),
+                despawn_components_system::<CodexComponent>,
+            )
+            .add_systems(
+                Update,
+                (codex_back_button_system, practice_boss_button_system)
+                    .run_if(in_state(GameState::Codex)),
+            );
+    }
+}
+
+#[derive(Component)]
+struct CodexComponent;
+
+#[derive(Component)]
+struct CodexBackButton;
+
+/// Starts a run practicing against a boss
+#[derive(Component)]
+struct PracticeBossButton(BossPracticeSettings);
+
+/// Gets the user-facing summary of the stats of an enemy type
+fn get_enemy_stats_label(enemy_type: EnemyType) -> String {
+    let params = enemy_type.get_params();
+    format!(
+        "{} - damage {}, speed {:.0}-{:.0}, health {}, {} XP",
+        enemy_type.get_name(),
+        params.damage,
+        params.max_speed.start(),
+        params.max_speed.end(),
+        params.health,
+        params.xp_reward,
+    )
+}
+
+fn codex_setup(mut commands: Commands, theme: Res<Theme>, asset_server: Res<AssetServer>) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .insert(CodexComponent)
+        .with_children(|parent| {
+            // title
+            parent.spawn(
+                TextBundle::from_section(
+                    "Codex",
+                    TextStyle {
+                        font: asset_server.load(TITLE_FONT),
+                        font_size: 60.0,
+                        color: Color::WHITE,
+                    },
+                )
+                .with_text_alignment(TextAlignment::Center)
+                .with_style(Style {
+                    margin: UiRect::bottom(Val::Px(15.0)),
+                    ..default()
+                }),
+            );
+
+            // enemy entries
+            for enemy_type in EnemyType::iter() {
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            width: Val::Percent(70.0),
+                            flex_direction: FlexDirection::Row,
+                            align_items: AlignItems::Center,
+                            margin: UiRect::bottom(Val::Px(6.0)),
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        // color swatch, so the entry can be matched up with enemies in the arena
+                        parent.spawn(NodeBundle {
+                            style: Style {
+                                width: Val::Px(20.0),
+                                height: Val::Px(20.0),
+                                margin: UiRect::right(Val::Px(10.0)),
+                                ..default()
+                            },
+                            background_color: enemy_type.get_params().color.into(),
+                            ..default()
+                        });
+
+                        parent
+                            .spawn(NodeBundle {
+                                style: Style {
+                                    flex_direction: FlexDirection::Column,
+                                    ..default()
+                                },
+                                ..default()
+                            })
+                            .with_children(|parent| {
+                                parent.spawn(TextBundle::from_section(
+                                    get_enemy_stats_label(enemy_type),
+                                    TextStyle {
+                                        font: asset_server.load(MAIN_FONT),
+                                        font_size: 20.0,
+                                        color: Color::WHITE,
+                                    },
+                                ));
+                                parent.spawn(TextBundle::from_section(
+                                    enemy_type.get_description(),
+                                    TextStyle {
+                                        font: asset_server.load(MAIN_FONT),
+                                        font_size: 15.0,
+                                        color: Color::GRAY,
+                                    },
+                                ));
+                            });
+
+                        if enemy_type.is_boss() {
+                            for (text, god_mode) in
+                                [("Practice", false), ("Practice (invincible)", true)]
+                            {
+                                spawn_practice_boss_button(
+                                    text,
+                                    BossPracticeSettings {
+                                        boss_type: enemy_type,
+                                        god_mode,
+                                    },
+                                    parent,
+                                    &theme,
+                                    &asset_server,
+                                );
+                            }
+                        }
+                    });
+            }
+
+            // back button
+            parent
+                .spawn(ButtonBundle {
+                    style: Style {
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        padding: UiRect::all(Val::Px(10.0)),
+                        margin: UiRect::top(Val::Px(15.0)),
+                        ..default()
+                    },
+                    background_color: theme.normal_button.into(),
+                    ..default()
+                })
+                .insert(CodexBackButton)
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "Back",
+                        TextStyle {
+                            font: asset_server.load(MAIN_FONT),
+                            font_size: 40.0,
+                            color: theme.button_text,
+                        },
+                    ));
+                });
+        });
+}
+
+/// Spawns a button that starts a run practicing against a boss with the provided settings
+fn spawn_practice_boss_button(
+    text: &str,
+    practice: BossPracticeSettings,
+    parent: &mut ChildBuilder,
+    theme: &Theme,
+    asset_server: &Res<AssetServer>,
+) {
+    parent
+        .spawn(ButtonBundle {
+            style: Style {
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                padding: UiRect::all(Val::Px(4.0)),
+                margin: UiRect::left(Val::Px(10.0)),
+                ..default()
+            },
+            background_color: theme.normal_button.into(),
+            ..default()
+        })
+        .insert(PracticeBossButton(practice))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                text,
+                TextStyle {
+                    font: asset_server.load(MAIN_FONT),
+                    font_size: 20.0,
+                    color: theme.button_text,
+                },
+            ));
+        });
+}
+
+type InteractedCodexBackButtonTuple = (Changed<Interaction>, With<CodexBackButton>);
+
+/// Handles interactions with the back button on the codex screen.
+fn codex_back_button_system(
+    mut next_state: ResMut<NextState<GameState>>,
+    interaction_query: Query<&Interaction, InteractedCodexBackButtonTuple>,
+) {
+    for interaction in interaction_query.iter() {
+        if *interaction == Interaction::Pressed {
+            next_state.set(GameState::Menu);
+        }
+    }
+}
+
+/// Handles interactions with the buttons for practicing against a boss.
+fn practice_boss_button_system(
+    mut next_state: ResMut<NextState<GameState>>,
+    mut boss_practice: ResMut<BossPractice>,
+    interaction_query: Query<(&Interaction, &PracticeBossButton), Changed<Interaction>>,
+    image_assets: Option<Res<ImageAssets>>,
+    audio_assets: Option<Res<AudioAssets>>,
+) {
+    for (interaction, button) in interaction_query.iter() {
+        if *interaction == Interaction::Pressed {
+            boss_practice.0 = Some(button.0);
+            next_state.set(get_game_start_state(&image_assets, &audio_assets));
+        }
+    }
+}
@@ -0,0 +1,421 @@
+use std::marker::PhantomData;
+
+use serde::{Deserialize, Serialize};
+use strum::{EnumIter, IntoEnumIterator};
+
+use crate::*;
+
+const COSMETICS_KEY: &str = "cosmetics";
+
+pub struct CosmeticsPlugin;
+
+impl Plugin for CosmeticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(persistence::load::<Cosmetics>(COSMETICS_KEY).unwrap_or_default())
+            .add_systems(OnEnter(GameState::Menu), cosmetics_setup)
+            .add_systems(
+                OnExit(GameState::Menu),
+                despawn_components_system::<CosmeticsComponent>,
+            )
+            .add_systems(
+                Update,
+                (
+                    cosmetic_button_system::<PlayerColor>,
+                    cosmetic_button_system::<SwordTint>,
+                    cosmetic_button_system::<PlayerTrail>,
+                )
+                    .run_if(in_state(GameState::Menu)),
+            );
+    }
+}
+
+/// Replaces the current cosmetics with the ones saved for the provided profile
+pub fn load_profile_cosmetics(profile: &CurrentProfile, commands: &mut Commands) {
+    commands.insert_resource(
+        persistence::load_for_profile::<Cosmetics>(profile, COSMETICS_KEY).unwrap_or_default(),
+    );
+}
+
+/// Saves the provided cosmetics for the provided profile
+pub fn save_profile_cosmetics(profile: &CurrentProfile, cosmetics: &Cosmetics) {
+    persistence::save_for_profile(profile, COSMETICS_KEY, cosmetics);
+}
+
+/// The cosmetics the player has chosen, along with the stats that determine which ones they've unlocked
+#[derive(Resource, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct Cosmetics {
+    pub lifetime_stats: LifetimeStats,
+    pub player_color: PlayerColor,
+    pub sword_tint: SwordTint,
+    pub player_trail: PlayerTrail,
+}
+
+/// Stats tracked across every run on a profile
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct LifetimeStats {
+    /// The highest level reached in any run
+    pub best_level: u64,
+    /// How much XP the player had at the end of the run where they reached their best level, to break ties between runs that reached the same level
+    pub best_level_xp: u64,
+    /// The number of enemies killed across all runs
+    pub total_kills: u64,
+}
+
+impl LifetimeStats {
+    /// Adds the results of a finished run to these stats
+    pub fn record_run(&mut self, level: u64, xp: u64, kills: u64) {
+        if (level, xp) > (self.best_level, self.best_level_xp) {
+            self.best_level = level;
+            self.best_level_xp = xp;
+        }
+        self.total_kills += kills;
+    }
+}
+
+/// Something the player needs to achieve to unlock a cosmetic
+enum Milestone {
+    /// Nothing needs to be done
+    None,
+    /// Reach at least this level in a run
+    Level(u64),
+    /// Kill at least this many enemies across all runs
+    Kills(u64),
+}
+
+impl Milestone {
+    /// Determines whether this milestone has been reached
+    fn is_reached(&self, stats: &LifetimeStats) -> bool {
+        match self {
+            Milestone::None => true,
+            Milestone::Level(level) => stats.best_level >= *level,
+            Milestone::Kills(kills) => stats.total_kills >= *kills,
+        }
+    }
+
+    /// Gets the user-facing description of what needs to be done to reach this milestone
+    fn get_description(&self) -> String {
+        match self {
+            Milestone::None => String::new(),
+            Milestone::Level(level) => format!("reach level {level}"),
+            Milestone::Kills(kills) => format!("defeat {kills} enemies"),
+        }
+    }
+}
+
+/// One kind of cosmetic, with a set of options for it
+trait Cosmetic: IntoEnumIterator + PartialEq + Copy + Send + Sync + 'static {
+    /// The user-facing name of this kind of cosmetic
+    const KIND_NAME: &'static str;
+
+    /// Gets the user-facing name of this option
+    fn get_name(&self) -> &'static str;
+
+    /// Gets the milestone that unlocks this option
+    fn get_milestone(&self) -> Milestone;
+
+    /// Gets the option that's currently chosen
+    fn get_selected(cosmetics: &Cosmetics) -> Self;
+
+    /// Chooses this option
+    fn select(self, cosmetics: &mut Cosmetics);
+
+    /// Determines whether this option has been unlocked
+    fn is_unlocked(&self, stats: &LifetimeStats) -> bool {
+        self.get_milestone().is_reached(stats)
+    }
+
+    /// Gets the next unlocked option after this one when cycling through them
+    fn next_unlocked(&self, stats: &LifetimeStats) -> Self {
+        let options = Self::iter().collect::<Vec<Self>>();
+        let current_index = options
+            .iter()
+            .position(|option| option == self)
+            .unwrap_or(0);
+        options
+            .iter()
+            .cycle()
+            .skip(current_index + 1)
+            .take(options.len())
+            .find(|option| option.is_unlocked(stats))
+            .copied()
+            .unwrap_or(*self)
+    }
+}
+
+/// The color the player is drawn in
+#[derive(PartialEq, Eq, Clone, Copy, Default, EnumIter, Serialize, Deserialize)]
+pub enum PlayerColor {
+    #[default]
+    Purple,
+    Teal,
+    Gold,
+    Crimson,
+}
+
+impl PlayerColor {
+    /// Gets the color to draw the player in
+    pub fn get_color(&self) -> Color {
+        match self {
+            PlayerColor::Purple => Color::PURPLE,
+            PlayerColor::Teal => Color::TEAL,
+            PlayerColor::Gold => Color::GOLD,
+            PlayerColor::Crimson => Color::CRIMSON,
+        }
+    }
+}
+
+impl Cosmetic for PlayerColor {
+    const KIND_NAME: &'static str = "Color";
+
+    fn get_name(&self) -> &'static str {
+        match self {
+            PlayerColor::Purple => "Purple",
+            PlayerColor::Teal => "Teal",
+            PlayerColor::Gold => "Gold",
+            PlayerColor::Crimson => "Crimson",
+        }
+    }
+
+    fn get_milestone(&self) -> Milestone {
+        match self {
+            PlayerColor::Purple => Milestone::None,
+            PlayerColor::Teal => Milestone::Level(5),
+            PlayerColor::Gold => Milestone::Kills(500),
+            PlayerColor::Crimson => Milestone::Level(12),
+        }
+    }
+
+    fn get_selected(cosmetics: &Cosmetics) -> Self {
+        cosmetics.player_color
+    }
+
+    fn select(self, cosmetics: &mut Cosmetics) {
+        cosmetics.player_color = self;
+    }
+}
+
+/// The color the player's sword is drawn in
+#[derive(PartialEq, Eq, Clone, Copy, Default, EnumIter, Serialize, Deserialize)]
+pub enum SwordTint {
+    #[default]
+    Steel,
+    Ember,
+    Frost,
+    Gilded,
+}
+
+impl SwordTint {
+    /// Gets the color to draw the sword in
+    pub fn get_color(&self) -> Color {
+        match self {
+            SwordTint::Steel => Color::GRAY,
+            SwordTint::Ember => Color::ORANGE_RED,
+            SwordTint::Frost => Color::rgb(0.6, 0.85, 1.0),
+            SwordTint::Gilded => Color::GOLD,
+        }
+    }
+}
+
+impl Cosmetic for SwordTint {
+    const KIND_NAME: &'static str = "Sword";
+
+    fn get_name(&self) -> &'static str {
+        match self {
+            SwordTint::Steel => "Steel",
+            SwordTint::Ember => "Ember",
+            SwordTint::Frost => "Frost",
+            SwordTint::Gilded => "Gilded",
+        }
+    }
+
+    fn get_milestone(&self) -> Milestone {
+        match self {
+            SwordTint::Steel => Milestone::None,
+            SwordTint::Ember => Milestone::Kills(250),
+            SwordTint::Frost => Milestone::Level(8),
+            SwordTint::Gilded => Milestone::Kills(2000),
+        }
+    }
+
+    fn get_selected(cosmetics: &Cosmetics) -> Self {
+        cosmetics.sword_tint
+    }
+
+    fn select(self, cosmetics: &mut Cosmetics) {
+        cosmetics.sword_tint = self;
+    }
+}
+
+/// Whether the player leaves a trail behind them as they move
+#[derive(PartialEq, Eq, Clone, Copy, Default, EnumIter, Serialize, Deserialize)]
+pub enum PlayerTrail {
+    #[default]
+    Off,
+    On,
+}
+
+impl Cosmetic for PlayerTrail {
+    const KIND_NAME: &'static str = "Trail";
+
+    fn get_name(&self) -> &'static str {
+        match self {
+            PlayerTrail::Off => "Off",
+            PlayerTrail::On => "On",
+        }
+    }
+
+    fn get_milestone(&self) -> Milestone {
+        match self {
+            PlayerTrail::Off => Milestone::None,
+            PlayerTrail::On => Milestone::Level(10),
+        }
+    }
+
+    fn get_selected(cosmetics: &Cosmetics) -> Self {
+        cosmetics.player_trail
+    }
+
+    fn select(self, cosmetics: &mut Cosmetics) {
+        cosmetics.player_trail = self;
+    }
+}
+
+#[derive(Component)]
+struct CosmeticsComponent;
+
+#[derive(Component)]
+struct CosmeticButton<T: Cosmetic>(PhantomData<T>);
+
+/// Gets the user-facing label for the button for a kind of cosmetic
+fn get_cosmetic_label<T: Cosmetic>(cosmetics: &Cosmetics) -> String {
+    let num_unlocked = T::iter()
+        .filter(|option| option.is_unlocked(&cosmetics.lifetime_stats))
+        .count();
+    format!(
+        "{}: {} ({num_unlocked}/{} unlocked)",
+        T::KIND_NAME,
+        T::get_selected(cosmetics).get_name(),
+        T::iter().count()
+    )
+}
+
+/// Gets the user-facing description of how to unlock the next locked option of a kind of cosmetic, if there is one
+fn get_next_unlock_description<T: Cosmetic>(stats: &LifetimeStats) -> Option<String> {
+    T::iter()
+        .find(|option| !option.is_unlocked(stats))
+        .map(|option| {
+            format!(
+                "{} {}: {}",
+                option.get_name(),
+                T::KIND_NAME.to_lowercase(),
+                option.get_milestone().get_description()
+            )
+        })
+}
+
+fn cosmetics_setup(
+    mut commands: Commands,
+    cosmetics: Res<Cosmetics>,
+    theme: Res<Theme>,
+    asset_server: Res<AssetServer>,
+) {
+    let next_unlocks = [
+        get_next_unlock_description::<PlayerColor>(&cosmetics.lifetime_stats),
+        get_next_unlock_description::<SwordTint>(&cosmetics.lifetime_stats),
+        get_next_unlock_description::<PlayerTrail>(&cosmetics.lifetime_stats),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<String>>();
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                right: Val::Px(10.0),
+                bottom: Val::Px(10.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::FlexEnd,
+                ..default()
+            },
+            ..default()
+        })
+        .insert(CosmeticsComponent)
+        .with_children(|parent| {
+            spawn_cosmetic_button::<PlayerColor>(&cosmetics, parent, &theme, &asset_server);
+            spawn_cosmetic_button::<SwordTint>(&cosmetics, parent, &theme, &asset_server);
+            spawn_cosmetic_button::<PlayerTrail>(&cosmetics, parent, &theme, &asset_server);
+
+            if !next_unlocks.is_empty() {
+                parent.spawn(
+                    TextBundle::from_section(
+                        format!("To unlock:\n{}", next_unlocks.join("\n")),
+                        TextStyle {
+                            font: asset_server.load(MAIN_FONT),
+                            font_size: 20.0,
+                            color: Color::GRAY,
+                        },
+                    )
+                    .with_text_alignment(TextAlignment::Right),
+                );
+            }
+        });
+}
+
+/// Spawns a button for cycling through the options for a kind of cosmetic
+fn spawn_cosmetic_button<T: Cosmetic>(
+    cosmetics: &Cosmetics,
+    parent: &mut ChildBuilder,
+    theme: &Theme,
+    asset_server: &Res<AssetServer>,
+) {
+    parent
+        .spawn(ButtonBundle {
+            style: Style {
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                padding: UiRect::all(Val::Px(5.0)),
+                margin: UiRect::bottom(Val::Px(5.0)),
+                ..default()
+            },
+            background_color: theme.normal_button.into(),
+            ..default()
+        })
+        .insert(CosmeticButton::<T>(PhantomData))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                get_cosmetic_label::<T>(cosmetics),
+                TextStyle {
+                    font: asset_server.load(MAIN_FONT),
+                    font_size: 25.0,
+                    color: theme.button_text,
+                },
+            ));
+        });
+}
+
+type InteractedCosmeticButtonTuple<T> = (Changed<Interaction>, With<CosmeticButton<T>>);
+
+/// Handles interactions with the button for a kind of cosmetic.
+fn cosmetic_button_system<T: Cosmetic>(
+    mut cosmetics: ResMut<Cosmetics>,
+    current_profile: Res<CurrentProfile>,
+    interaction_query: Query<(&Interaction, &Children), InteractedCosmeticButtonTuple<T>>,
+    mut text_query: Query<&mut Text>,
+) {
+    for (interaction, children) in interaction_query.iter() {
+        if *interaction == Interaction::Pressed {
+            let next = T::get_selected(&cosmetics).next_unlocked(&cosmetics.lifetime_stats);
+            next.select(&mut cosmetics);
+            save_profile_cosmetics(&current_profile, &cosmetics);
+
+            for child in children.iter() {
+                if let Ok(mut text) = text_query.get_mut(*child) {
+                    text.sections[0].value = get_cosmetic_label::<T>(&cosmetics);
+                }
+            }
+        }
+    }
+}
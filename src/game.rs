@@ -1,5 +1,5 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{HashMap, HashSet, VecDeque},
     f32::consts::PI,
     ops::RangeInclusive,
     time::Duration,
@@ -7,9 +7,11 @@ use std::{
 
 use bevy::{
     audio::{PlaybackMode, Volume},
+    diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin},
     ecs::query::WorldQuery,
-    input::common_conditions::{input_just_pressed, input_pressed},
-    sprite::MaterialMesh2dBundle,
+    input::common_conditions::{input_just_pressed, input_toggle_active},
+    render::mesh::{Indices, PrimitiveTopology},
+    sprite::{MaterialMesh2dBundle, Mesh2dHandle},
 };
 use bevy_asset_loader::{
     asset_collection::AssetCollection,
@@ -25,7 +27,8 @@ use bevy_rapier2d::{
 };
 use bevy_tweening::{
     lens::{
-        ColorMaterialColorLens, TransformPositionLens, TransformRotateZLens, TransformScaleLens,
+        ColorMaterialColorLens, TextColorLens, TransformPositionLens, TransformRotateZLens,
+        TransformScaleLens, UiBackgroundColorLens, UiPositionLens,
     },
     Animator, AnimatorState, AssetAnimator, Delay, EaseFunction, EaseMethod, Sequence, Tracks,
     Tween, TweenCompleted,
@@ -36,6 +39,7 @@ use rand::{
     seq::{IteratorRandom, SliceRandom},
     Rng,
 };
+use serde::Serialize;
 use strum::{EnumIter, IntoEnumIterator};
 
 use crate::*;
@@ -46,11 +50,23 @@ const PLAYER_MOVE_FORCE: f32 = 100000.0;
 const PLAYER_DAMPING: f32 = 8.0;
 const PLAYER_MASS: f32 = 100.0;
 const PLAYER_INERTIA: f32 = 16000.0;
+const PLAYER_MAX_TURN_RATE: f32 = 6.0 * PI;
 
 const ENEMY_MOVE_FORCE: f32 = 35000.0;
 const ENEMY_DAMPING: f32 = 4.0;
 const ENEMY_MASS: f32 = 50.0;
 const ENEMY_INERTIA: f32 = 8000.0;
+const ENEMY_MAX_TURN_RATE: f32 = 2.0 * PI;
+const ENEMY_GRACE_PERIOD: Duration = Duration::from_secs(3);
+
+/// How long the player stays downed before the run ends, if they don't get revived
+const DOWNED_DURATION: Duration = Duration::from_secs(3);
+/// How close enemies need to be to keep a downed player from getting back up
+const DOWNED_REVIVE_RADIUS: f32 = 60.0;
+/// The fraction of max health a downed player gets back when they're revived
+const DOWNED_REVIVE_HEALTH_FRACTION: f64 = 0.25;
+const DOWNED_ANNOUNCEMENT: &str = "Downed! Clear the area!";
+const REVIVED_ANNOUNCEMENT: &str = "Back on your feet!";
 
 const HIT_IMPULSE: f32 = 50000.0;
 
@@ -58,6 +74,17 @@ const SWORD_WIDTH: f32 = 1.0;
 const SWORD_LENGTH: f32 = 14.0;
 
 const PLAYER_ATTACK_COOLDOWN: Duration = Duration::from_millis(750);
+const ATTACK_COMBO_WINDOW: Duration = Duration::from_millis(1200);
+/// How much wider each swing in a combo is, with the last one being the finisher
+const ATTACK_COMBO_ARC_MULTIPLIERS: [f32; 3] = [1.0, 1.15, 1.5];
+/// How much longer each swing in a combo takes, with the last one being the finisher
+const ATTACK_COMBO_SWING_TIME_MULTIPLIERS: [f32; 3] = [1.0, 0.85, 1.0];
+/// What happens to the attack combo when the player gets hit
+const COMBO_HIT_PENALTY: ComboHitPenalty = ComboHitPenalty::Reset;
+/// What happens to the attack combo when the player gets hit, if they've turned on lenient combos
+const LENIENT_COMBO_HIT_PENALTY: ComboHitPenalty = ComboHitPenalty::Halve;
+const FINISHER_KNOCKBACK_RADIUS: f32 = 30.0;
+const FINISHER_KNOCKBACK_IMPULSE: f32 = 30000.0;
 const SWORD_SWING_ROTATION_DEGREES: f32 = 60.0;
 const SWORD_SWING_TRANSLATION: f32 = 2.0;
 
@@ -79,6 +106,7 @@ const SWORD_SHADOW_DELAYS_AND_ALPHAS: [(Duration, f32); 10] = [
 
 const SWORD_START_SCALE: Vec3 = Vec3::new(1.0, 0.0, 1.0);
 const SWORD_END_SCALE: Vec3 = Vec3::ONE;
+const BLADESWORN_SWORD_LENGTH_MULTIPLIER: f32 = 1.5;
 // manually converting degrees to radians because `f32::to_radians` isn't `const` for some reason
 const SWORD_START_ROTATION: f32 = (SWORD_SWING_ROTATION_DEGREES / 2.0) * (PI / 180.0f32);
 const SWORD_END_ROTATION: f32 = (-SWORD_SWING_ROTATION_DEGREES / 2.0) * (PI / 180.0f32);
@@ -91,57 +119,364 @@ const SWORD_SWING_COMPLETE_EVENT_ID: u64 = 1;
 const ATTACK_DONE_EVENT_ID: u64 = 2;
 const EXPLOSION_COMPLETE_EVENT_ID: u64 = 3;
 const DEATH_ANIMATION_COMPLETE_EVENT_ID: u64 = 4;
+const ANNOUNCEMENT_COMPLETE_EVENT_ID: u64 = 5;
+const ENEMY_TRAIL_COMPLETE_EVENT_ID: u64 = 6;
+const COMBO_LOST_COMPLETE_EVENT_ID: u64 = 7;
+const PLAYER_TRAIL_COMPLETE_EVENT_ID: u64 = 8;
+const ENEMY_SPAWN_COMPLETE_EVENT_ID: u64 = 9;
+const LEVEL_UP_BURST_COMPLETE_EVENT_ID: u64 = 10;
+const ENEMY_HIT_FLASH_COMPLETE_EVENT_ID: u64 = 11;
+const XP_POPUP_COMPLETE_EVENT_ID: u64 = 12;
 
 const HIT_SLOW_MO_TIME: Duration = Duration::from_millis(150);
 const HIT_SLOW_MO_TIME_SCALE: f32 = 0.4;
+const FINISHER_SLOW_MO_TIME: Duration = Duration::from_millis(200);
+const FINISHER_SLOW_MO_TIME_SCALE: f32 = 0.5;
+const CLEARED_SLOW_MO_TIME: Duration = Duration::from_millis(600);
+const CLEARED_SLOW_MO_TIME_SCALE: f32 = 0.3;
+/// How many enemies there need to be at once for clearing them out to be worth celebrating
+const CLEARED_BUSY_ENEMY_COUNT: usize = 40;
+/// How few enemies there need to be left for the arena to count as cleared out
+const CLEARED_MAX_ENEMY_COUNT: usize = 2;
+const CLEARED_ANNOUNCEMENT: &str = "Cleared!";
+
+const BULLET_TIME_TIME_SCALE: f32 = 0.5;
+const BULLET_TIME_MAX_CHARGE: f32 = 1.0;
+const BULLET_TIME_CHARGE_PER_SECOND: f32 = 0.02;
+const BULLET_TIME_CHARGE_PER_KILL: f32 = 0.01;
+const BULLET_TIME_DRAIN_PER_SECOND: f32 = 0.25;
+
+const XP_BAR_FILL_COLOR: Color = Color::GOLD;
+/// How quickly the XP bar catches up to the player's actual XP, as the fraction of the remaining gap closed per second
+const XP_BAR_FILL_RATE: f32 = 8.0;
+
+/// How slow time goes while the player is holding down the secondary action button to aim it, if they've turned that on
+const AIM_SLOW_MO_TIME_SCALE: f32 = 0.6;
 
 const SWORD_Z: f32 = -1.0;
 const BACKGROUND_Z: f32 = -100.0;
 const EXPLOSION_Z: f32 = 1.0;
 const COOLDOWN_DISPLAY_Z: f32 = 10.0;
 
+const THREAT_RING_WIDTH: f32 = 1.0;
+const THREAT_RING_Z: f32 = -0.5;
+const THREAT_RING_COLOR: Color = Color::YELLOW;
+const ENEMY_DAMAGE_INDICATOR_Z: f32 = 0.5;
+const ENEMY_DAMAGE_INDICATOR_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.6);
+/// The color enemies flash when they're hit without dying
+const ENEMY_HIT_FLASH_COLOR: Color = Color::WHITE;
+const ENEMY_HIT_FLASH_DURATION: Duration = Duration::from_millis(150);
+
+/// How long it takes newly spawned enemies to fade in
+const ENEMY_SPAWN_ANIMATION_DURATION: Duration = Duration::from_millis(300);
+/// How big newly spawned enemies start out, relative to their full size
+const ENEMY_SPAWN_START_SCALE: f32 = 0.5;
+
+/// How long shielded enemies can't be hurt for at a time
+const SHIELD_UP_DURATION: Duration = Duration::from_millis(2000);
+/// How long shielded enemies can be hurt for at a time
+const SHIELD_DOWN_DURATION: Duration = Duration::from_millis(1500);
+const SHIELD_BUBBLE_SCALE: f32 = 1.6;
+const SHIELD_BUBBLE_Z: f32 = 0.6;
+const SHIELD_BUBBLE_COLOR: Color = Color::rgba(0.5, 0.8, 1.0, 0.35);
+
+/// How long blinking enemies wait between blinks
+const BLINK_INTERVAL: Duration = Duration::from_millis(3000);
+/// How long blinking enemies show where they're about to blink to before doing it
+const BLINK_TELEGRAPH_DURATION: Duration = Duration::from_millis(600);
+/// The farthest blinking enemies can move in one blink
+const BLINK_DISTANCE: f32 = 40.0;
+/// How close to the player blinking enemies are willing to blink, so they land in sword range rather than on top of the player
+const BLINK_MIN_PLAYER_DISTANCE: f32 = 20.0;
+
+/// How far away magnetic enemies pull other enemies from
+const MAGNET_PULL_RADIUS: f32 = 60.0;
+/// How hard magnetic enemies pull on enemies right next to them, relative to how hard enemies push themselves towards the player.
+/// Kept well below 1 so pulled enemies still mostly chase the player, rather than clumping up forever.
+const MAGNET_PULL_STRENGTH: f32 = 0.4;
+
+/// How close shooting enemies get to the player before they stop and just shoot at them
+const SHOOTER_RANGE: f32 = 90.0;
+/// How long shooting enemies wait between shots
+const SHOOTER_FIRE_INTERVAL: Duration = Duration::from_millis(2000);
+const ENEMY_PROJECTILE_RADIUS: f32 = 1.5;
+const ENEMY_PROJECTILE_SPEED: f32 = 80.0;
+const ENEMY_PROJECTILE_LIFETIME: Duration = Duration::from_millis(2000);
+const ENEMY_PROJECTILE_DAMAGE: u64 = 5;
+const ENEMY_PROJECTILE_COLOR: Color = Color::LIME_GREEN;
+const ENEMY_PROJECTILE_Z: f32 = 0.25;
+
+/// The chance that a killed enemy drops a health pickup, for enemies that don't always drop one
+const HEALTH_PICKUP_DROP_CHANCE: f64 = 0.05;
+const HEALTH_PICKUP_RADIUS: f32 = 2.5;
+const HEALTH_PICKUP_COLOR: Color = Color::GREEN;
+const HEALTH_PICKUP_Z: f32 = -0.3;
+/// How much health a health pickup restores
+const HEALTH_PICKUP_AMOUNT: u64 = 10;
+
+/// How much each critical hits perk raises the chance of a sword hit being a critical hit
+const CRIT_CHANCE_PER_PERK: f32 = 0.1;
+const MAX_CRIT_CHANCE: f32 = 0.5;
+/// How many hit points a critical hit takes away
+const CRIT_DAMAGE: u64 = 2;
+const CRIT_XP_MULTIPLIER: u64 = 2;
+/// How much bigger the death animation is for enemies killed by a critical hit
+const CRIT_DEATH_ANIMATION_SIZE_MULTIPLIER: f32 = 2.0;
+const CRIT_XP_POPUP_COLOR: Color = Color::ORANGE_RED;
+
+const XP_ORB_RADIUS: f32 = 1.5;
+const XP_ORB_COLOR: Color = Color::GOLD;
+const XP_ORB_Z: f32 = -0.35;
+/// How close XP orbs need to be to the player for the magnet perk to start pulling them in
+const XP_MAGNET_RADIUS: f32 = 60.0;
+/// How quickly XP orbs being pulled in by the magnet perk speed up
+const XP_MAGNET_ACCELERATION: f32 = 400.0;
+
+/// How fast an enemy needs to be moving to leave a trail behind it
+const ENEMY_TRAIL_MIN_SPEED: f32 = 40.0;
+const ENEMY_TRAIL_ALPHA: f32 = 0.3;
+const ENEMY_TRAIL_Z: f32 = -0.75;
+const ENEMY_TRAIL_INTERVAL: Duration = Duration::from_millis(50);
+const ENEMY_TRAIL_DURATION: Duration = Duration::from_millis(250);
+/// The most trail pieces that can exist at once, so big swarms of fast enemies don't bog down rendering
+const MAX_ENEMY_TRAILS: usize = 150;
+
+const PLAYER_TRAIL_MIN_SPEED: f32 = 20.0;
+const PLAYER_TRAIL_ALPHA: f32 = 0.4;
+const PLAYER_TRAIL_Z: f32 = -0.8;
+const PLAYER_TRAIL_INTERVAL: Duration = Duration::from_millis(40);
+const PLAYER_TRAIL_DURATION: Duration = Duration::from_millis(300);
+
+const PROXIMITY_WARNING_DISTANCE: f32 = 12.0;
+const PROXIMITY_WARNING_WIDTH: f32 = 1.5;
+const PROXIMITY_WARNING_Z: f32 = -0.5;
+const PROXIMITY_WARNING_COLOR: Color = Color::ORANGE;
+const PROXIMITY_WARNING_PULSE_PERIOD: Duration = Duration::from_millis(300);
+
+const SWORD_BEAM_WIDTH: f32 = 10.0;
+const SWORD_BEAM_THICKNESS: f32 = 1.5;
+const SWORD_BEAM_SPEED: f32 = 250.0;
+const SWORD_BEAM_LIFETIME: Duration = Duration::from_millis(400);
+const SWORD_BEAM_COLOR: Color = Color::rgba(0.6, 0.8, 1.0, 0.8);
+
+/// How long the lingering arc keeps hurting enemies after the sword finishes swinging
+const LINGERING_ARC_DURATION: Duration = Duration::from_millis(250);
+const LINGERING_ARC_COLOR: Color = Color::rgba(0.8, 0.9, 1.0, 0.3);
+const LINGERING_ARC_Z: f32 = -1.5;
+/// How many straight segments the curved edge of the lingering arc is made of
+const LINGERING_ARC_SEGMENTS: usize = 8;
+
 const EXPLOSION_START_RADIUS: f32 = 6.0;
 const EXPLOSION_DURATION: Duration = Duration::from_millis(250);
 const EXPLOSION_FADE_TIME: Duration = Duration::from_millis(250);
 const EXPLOSION_COLOR: Color = Color::rgba(1.0, 1.0, 0.0, 0.9);
 
+/// How far the ring around the player expands out to when they level up
+const LEVEL_UP_BURST_RADIUS: f32 = 40.0;
+const LEVEL_UP_BURST_DURATION: Duration = Duration::from_millis(400);
+const LEVEL_UP_BURST_COLOR: Color = Color::rgba(1.0, 0.85, 0.3, 0.5);
+const LEVEL_UP_BURST_Z: f32 = -0.25;
+
+const SLOW_FIELD_RADIUS: f32 = 25.0;
+const SLOW_FIELD_DURATION: Duration = Duration::from_secs(4);
+const SLOW_FIELD_COOLDOWN: Duration = Duration::from_secs(8);
+/// How much enemies inside a slow field have their max speed multiplied by
+const SLOW_FIELD_SPEED_MULTIPLIER: f32 = 0.4;
+const SLOW_FIELD_COLOR: Color = Color::rgba(0.3, 0.5, 1.0, 0.25);
+const SLOW_FIELD_Z: f32 = -1.5;
+
+/// How far the player goes when they dash
+const DASH_DISTANCE: f32 = 60.0;
+/// How long a dash takes, which is also how long the player can't be hurt for
+const DASH_DURATION: Duration = Duration::from_millis(150);
+const DASH_COOLDOWN: Duration = Duration::from_secs(3);
+const DASH_VOLUME: f32 = 0.3;
+
+const DAMAGE_FLASH_COLOR: Color = Color::rgba(1.0, 0.0, 0.0, 0.3);
+const DAMAGE_FLASH_DURATION: Duration = Duration::from_millis(200);
+const DAMAGE_BORDER_COLOR: Color = Color::rgba(1.0, 0.0, 0.0, 0.6);
+const DAMAGE_BORDER_WIDTH: f32 = 8.0;
+const DAMAGE_BORDER_DURATION: Duration = Duration::from_millis(400);
+
+/// How big text on the compact perk chooser is compared to the fullscreen one
+const COMPACT_PERK_CHOOSER_TEXT_SCALE: f32 = 0.6;
+
+const KNOCKBACK_INDICATOR_COLOR: Color = Color::rgba(1.0, 0.3, 0.3, 0.8);
+const KNOCKBACK_INDICATOR_SIZE: f32 = 3.0;
+/// How far from the player's center the knockback indicator shows up, in the direction they were knocked back
+const KNOCKBACK_INDICATOR_OFFSET: f32 = PLAYER_SIZE * 2.0;
+const KNOCKBACK_INDICATOR_DURATION: Duration = Duration::from_millis(300);
+const KNOCKBACK_INDICATOR_Z: f32 = 2.0;
+
+const ANNOUNCEMENT_COLOR: Color = Color::WHITE;
+const ANNOUNCEMENT_FONT_SIZE: f32 = 60.0;
+/// How far down the screen announcements start sliding in from, in percent of the window height
+const ANNOUNCEMENT_START_TOP: f32 = 15.0;
+/// How far down the screen announcements rest while they're showing, in percent of the window height
+const ANNOUNCEMENT_END_TOP: f32 = 20.0;
+const ANNOUNCEMENT_FADE_DURATION: Duration = Duration::from_millis(400);
+const ANNOUNCEMENT_HOLD_DURATION: Duration = Duration::from_millis(1500);
+const RUN_START_ANNOUNCEMENT: &str = "Here they come!";
+
+const COMBO_LOST_TEXT: &str = "Combo lost";
+const COMBO_LOST_COLOR: Color = Color::ORANGE_RED;
+const COMBO_LOST_FONT_SIZE: f32 = 24.0;
+/// Text is rendered at its font size in world units, so it's scaled down to keep it crisp while the camera is zoomed in
+const COMBO_LOST_SCALE: f32 = 0.33;
+/// How far above the player the combo lost text shows up
+const COMBO_LOST_OFFSET: f32 = PLAYER_SIZE * 3.0;
+const COMBO_LOST_DURATION: Duration = Duration::from_millis(700);
+const COMBO_LOST_Z: f32 = 5.0;
+
+const XP_POPUP_COLOR: Color = Color::GOLD;
+const XP_POPUP_FONT_SIZE: f32 = 20.0;
+/// Text is rendered at its font size in world units, so it's scaled down to keep it crisp while the camera is zoomed in
+const XP_POPUP_SCALE: f32 = 0.33;
+/// How far XP popups float upward before they're gone
+const XP_POPUP_RISE: f32 = 15.0;
+const XP_POPUP_DURATION: Duration = Duration::from_millis(600);
+const XP_POPUP_Z: f32 = 5.0;
+const NEW_ENEMY_TYPE_ANNOUNCEMENT: &str = "The horde grows stronger";
+
+const CLING_SLOW_DURATION: Duration = Duration::from_millis(1500);
+const CLING_SPEED_MULTIPLIER: f32 = 0.5;
+
 const DEATH_ANIMATION_DURATION: Duration = Duration::from_millis(200);
 
 const PLAY_AREA_SIZE: Vec2 = Vec2::new(1000.0, 1000.0);
 
+const OBSTACLE_COLOR: Color = Color::DARK_GRAY;
+const OBSTACLE_Z: f32 = -2.0;
+const PILLAR_SIZE: f32 = 30.0;
+const WALL_LENGTH: f32 = 200.0;
+const WALL_THICKNESS: f32 = 12.0;
+
 const SPAWN_AREA_DEPTH: f32 = 25.0;
 const SPAWN_AREA_BUFFER: f32 = 10.0;
+/// How close to the player enemies are allowed to spawn, so they don't show up right on top of them
+const SPAWN_AVOIDANCE_RADIUS: f32 = 80.0;
+/// How far ahead to predict where the player will be, so enemies don't spawn right where they're heading
+const SPAWN_AVOIDANCE_PREDICTION_TIME: f32 = 1.0;
+/// How many times to try finding a spawn point away from the player before giving up and spawning close anyway
+const MAX_SPAWN_POINT_ATTEMPTS: usize = 5;
 
 const START_SPAWN_INTERVAL: Duration = Duration::from_millis(500);
 const SPAWN_INTERVAL_CHANGE_INTERVAL: Duration = Duration::from_secs(5);
 const SPAWN_INTERVAL_CHANGE_MULTIPLIER: f32 = 0.95;
 const MIN_SPAWN_INTERVAL: Duration = Duration::from_millis(5);
 
+const DYNAMIC_DIFFICULTY_INTERVAL: Duration = Duration::from_secs(2);
+const DYNAMIC_DIFFICULTY_SMOOTHING: f32 = 0.3;
+const DYNAMIC_DIFFICULTY_KILL_RATE_WEIGHT: f32 = 0.05;
+const DYNAMIC_DIFFICULTY_HEALTH_LOSS_RATE_WEIGHT: f32 = 0.1;
+const DYNAMIC_DIFFICULTY_MIN_SPAWN_RATE_MULTIPLIER: f32 = 0.5;
+const DYNAMIC_DIFFICULTY_MAX_SPAWN_RATE_MULTIPLIER: f32 = 1.5;
+
+const SPAWN_BUDGET_INTERVAL: Duration = Duration::from_secs(1);
+/// Frames taking longer than this on average make enemies spawn slower
+const SPAWN_BUDGET_MAX_FRAME_TIME_MS: f64 = 1000.0 / 45.0;
+/// Frames taking less than this on average let enemy spawning get back to normal
+const SPAWN_BUDGET_RECOVERY_FRAME_TIME_MS: f64 = 1000.0 / 55.0;
+const SPAWN_BUDGET_STEP: f32 = 0.1;
+const SPAWN_BUDGET_MIN_SPAWN_RATE_MULTIPLIER: f32 = 0.5;
+
+/// A spawn storm starts every time the player reaches a multiple of this level
+const SPAWN_STORM_LEVEL_INTERVAL: u64 = 5;
+/// How long the player gets between a spawn storm being announced and it starting
+const SPAWN_STORM_WARNING_TIME: Duration = Duration::from_secs(3);
+const SPAWN_STORM_DURATION: Duration = Duration::from_secs(5);
+/// How much faster than normal enemies spawn during a spawn storm
+const SPAWN_STORM_SPAWN_RATE_MULTIPLIER: f32 = 5.0;
+const SPAWN_STORM_ANNOUNCEMENT: &str = "A storm is coming";
+
+const FIRST_WAVE_SIZE: u64 = 10;
+/// How many more enemies each wave has than the one before it
+const WAVE_SIZE_INCREASE: u64 = 5;
+/// Waves stop getting bigger past this size, so they don't bog down the game
+const MAX_WAVE_SIZE: u64 = 200;
+/// The next wave comes once there are this many enemies or fewer left from the current one
+const NEXT_WAVE_MAX_ENEMY_COUNT: usize = 2;
+
+/// A boss shows up every time the player reaches a multiple of this level
+const BOSS_LEVEL_INTERVAL: u64 = 5;
+const BOSS_ANNOUNCEMENT: &str = "A champion approaches";
+/// How long after the boss being practiced against is killed until the next one shows up
+const BOSS_PRACTICE_RESPAWN_DELAY: Duration = Duration::from_secs(2);
+const BOSS_HEALTH_BAR_WIDTH: f32 = 300.0;
+const BOSS_HEALTH_BAR_HEIGHT: f32 = 12.0;
+
 const SPAWN_WEIGHTS_CHANGE_INTERVAL: Duration = Duration::from_secs(5);
-const SPAWN_WEIGHT_CHANGES: [EnemyType; 4] = [
+const SPAWN_WEIGHT_CHANGES: [EnemyType; 9] = [
     EnemyType::Assassin,
+    EnemyType::Clinger,
     EnemyType::Assassin,
     EnemyType::UltraBigAndSlow,
     EnemyType::UltraAssassin,
+    EnemyType::Shielded,
+    EnemyType::Blinker,
+    EnemyType::Magnetar,
+    EnemyType::Shooter,
 ];
+/// How many of the most common enemy types to show at the end of a run
+const NUM_LAST_RUN_ENEMY_TYPES: usize = 3;
+
+/// The key finished runs are logged under, when run logging is on
+pub const RUN_LOG_KEY: &str = "run-log";
 
 const NEXT_LEVEL_ADDITIONAL_XP_MULTIPLIER: f64 = 1.4;
 const STARTING_XP_THRESHOLD: u64 = 5;
 const NUM_PERK_CHOICES: usize = 3;
+const PERK_CHOOSER_PROMPT: &str = "Choose a perk (or press 1-3)";
+const PERK_CHOOSER_PROMPT_COLOR: Color = Color::GRAY;
+const PERK_CHOOSER_PROMPT_PULSE_PERIOD: Duration = Duration::from_millis(1500);
+const PERK_CHOOSER_PROMPT_MIN_ALPHA: f32 = 0.3;
+const PERK_CHOOSER_IDLE_TIME: Duration = Duration::from_secs(60);
+const PERK_CHOOSER_IDLE_WARNING_TIME: Duration = Duration::from_secs(10);
+const CLEANSE_PERK_OFFER_CHANCE: f64 = 0.2;
 const STARTING_HEALTH: u64 = 100;
 
+const FRENZY_ENEMY_SPEED_MULTIPLIER: f32 = 1.2;
+const FRENZY_XP_MULTIPLIER: u64 = 2;
+const GLASS_CANNON_HEALTH_MULTIPLIER: f64 = 0.5;
+const GLASS_CANNON_ATTACK_COOLDOWN_MULTIPLIER: f32 = 0.5;
+
 const MAX_ZOOM_LEVEL: f32 = 1.0;
 const ZOOM_LEVEL_MULTIPLIER: f32 = 1.05;
-
-const MOVE_LEFT_KEY: KeyCode = KeyCode::A;
-const MOVE_RIGHT_KEY: KeyCode = KeyCode::D;
-const MOVE_UP_KEY: KeyCode = KeyCode::W;
-const MOVE_DOWN_KEY: KeyCode = KeyCode::S;
-const ATTACK_INPUT: MouseButton = MouseButton::Left;
-const SECONDARY_ACTION_INPUT: KeyCode = KeyCode::Space;
-const PAUSE_INPUT: KeyCode = KeyCode::P;
+/// How quickly the zoom catches up to where leveling up has set it, as the fraction of the remaining gap closed per second
+const LEVEL_UP_ZOOM_RATE: f32 = 6.0;
+/// The fraction of enemies that auto zoom tries to keep in view, so a few stragglers don't force the camera all the way out
+const AUTO_ZOOM_ENEMY_FRACTION: f32 = 0.9;
+const AUTO_ZOOM_MARGIN: f32 = 1.1;
+const AUTO_ZOOM_SPEED: f32 = 2.0;
+
+const MOUSE_FOLLOW_STOP_DISTANCE: f32 = PLAYER_SIZE * 2.0;
+const GAMEPAD_ATTACK_INPUT: GamepadButtonType = GamepadButtonType::RightTrigger2;
+/// How far the aiming stick needs to be pushed before it takes over aiming from the mouse
+const GAMEPAD_AIM_DEADZONE: f32 = 0.3;
+/// How far away enemies can be to have aim pulled towards them
+const AIM_ASSIST_RANGE: f32 = 250.0;
+/// How far off from the aim direction enemies can be to have aim pulled towards them, in radians
+const AIM_ASSIST_MAX_ANGLE: f32 = PI / 8.0;
+/// Choose the perk in the corresponding position on the perk chooser
+pub const PERK_CHOICE_INPUTS: [KeyCode; NUM_PERK_CHOICES] =
+    [KeyCode::Key1, KeyCode::Key2, KeyCode::Key3];
+pub const BULLET_TIME_INPUT: KeyCode = KeyCode::ShiftLeft;
+pub const DEBUG_OVERLAY_INPUT: KeyCode = KeyCode::F3;
+const FRAME_TIME_SPIKE_THRESHOLD_MS: f64 = 50.0;
+pub const SPAWN_AREA_OVERLAY_INPUT: KeyCode = KeyCode::F4;
+const SPAWN_AREA_OVERLAY_COLOR: Color = Color::YELLOW;
+/// Restores the game to how it was a few seconds ago, for reproducing bugs. Only available in dev mode.
+pub const REWIND_INPUT: KeyCode = KeyCode::F5;
+const REWIND_SNAPSHOT_INTERVAL: Duration = Duration::from_millis(250);
+/// How many snapshots to keep around, which determines how far back a rewind goes
+const MAX_REWIND_SNAPSHOTS: usize = 12;
+const PLAY_AREA_OVERLAY_COLOR: Color = Color::GREEN;
 
 const BG_MUSIC_VOLUME: f32 = 0.5;
+/// How loud the music and sound effects are while the perk chooser is open, relative to normal
+const PERK_CHOOSER_VOLUME_MULTIPLIER: f32 = 0.4;
+/// How fast the music and sound effects get quieter or louder when the perk chooser opens or closes, in full volume changes per second
+const AUDIO_DUCKING_CHANGE_SPEED: f32 = 2.0;
 const SWING_VOLUME: f32 = 0.4;
 const TELEPORT_VOLUME: f32 = 0.4;
 const EXPLOSION_VOLUME: f32 = 0.5;
@@ -149,6 +484,11 @@ const HIT_VOLUME: f32 = 0.25;
 const PLAYER_HIT_VOLUME: f32 = 0.6;
 const LEVEL_UP_VOLUME: f32 = 0.6;
 const SECONDARY_ACTION_READY_VOLUME: f32 = 0.4;
+const HEALTH_PICKUP_VOLUME: f32 = 0.5;
+/// How far the playback speed of each swing sound can be from normal, so repeated swings don't all sound the same
+const SWING_SPEED_VARIATION: f32 = 0.08;
+/// How far the playback speed of each hit sound can be from normal, so repeated hits don't all sound the same
+const HIT_SPEED_VARIATION: f32 = 0.05;
 
 pub struct GamePlugin;
 
@@ -171,11 +511,23 @@ impl Plugin for GamePlugin {
 
         app.add_systems(
             OnEnter(GameState::Game),
-            (game_setup, start_background_music),
+            (
+                (game_setup, apply_deferred, apply_imported_build).chain(),
+                start_background_music,
+            ),
         )
         .add_systems(
             OnExit(GameState::Game),
             (
+                record_last_run_build,
+                record_last_run_spawn_stats,
+                log_run
+                    .run_if(|settings: Res<Settings>| settings.log_runs)
+                    .run_if(|imported_build_run: Res<ImportedBuildRun>| !imported_build_run.0)
+                    .run_if(normal_run),
+                record_lifetime_stats
+                    .run_if(|imported_build_run: Res<ImportedBuildRun>| !imported_build_run.0)
+                    .run_if(normal_run),
                 despawn_components_system::<GameComponent>,
                 stop_background_music,
             ),
@@ -198,26 +550,72 @@ impl Plugin for GamePlugin {
         .insert_resource(PerkChooserDelayTimers {
             initial_delay: Timer::from_seconds(1.0, TimerMode::Once),
             button_delays: Vec::new(),
-        });
+        })
+        .insert_resource(PerkChooserIdleTimer(Timer::new(
+            PERK_CHOOSER_IDLE_TIME,
+            TimerMode::Once,
+        )))
+        .insert_resource(BulletTime {
+            charge: 0.0,
+            active: false,
+        })
+        .insert_resource(AimingSecondaryAction(false))
+        .insert_resource(GracePeriod(Timer::new(ENEMY_GRACE_PERIOD, TimerMode::Once)))
+        .insert_resource(build_starting_downed())
+        .insert_resource(AnnouncementQueue(VecDeque::new()));
+
+        app.insert_resource(BossPractice(None));
 
         app.add_event::<LevelUp>()
+            .add_event::<ShowPerkChooser>()
+            .add_event::<EnemyKilled>()
+            .add_event::<PlayerHit>()
+            .add_event::<StartSpawnStorm>()
             .add_systems(
                 Update,
                 (
-                    update_attack_cooldown.before(player_attack),
-                    player_movement,
+                    update_attack_cooldown
+                        .before(player_attack)
+                        .run_if(gameplay_active),
+                    // none of the player's controls should do anything while the game is stopped, like while choosing a perk
+                    player_movement.run_if(gameplay_active),
                     player_attack
                         .run_if(in_state(GameState::Game))
-                        .run_if(input_pressed(ATTACK_INPUT)),
+                        .run_if(gameplay_active)
+                        .run_if(
+                            attack_input_pressed
+                                .or_else(gamepad_button_pressed(GAMEPAD_ATTACK_INPUT)),
+                        ),
                     update_secondary_action_cooldown
                         .before(player_secondary_action)
-                        .run_if(in_state(GameState::Game)),
-                    player_secondary_action.run_if(input_pressed(SECONDARY_ACTION_INPUT)),
+                        .run_if(in_state(GameState::Game))
+                        .run_if(gameplay_active),
+                    player_secondary_action
+                        .run_if(in_state(GameState::Game))
+                        .run_if(gameplay_active)
+                        .run_if(secondary_action_triggered),
                     tween_completed,
+                    restore_enemy_materials
+                        .after(tween_completed)
+                        .run_if(in_state(GameState::Game)),
                     move_camera.after(player_movement),
                     keep_player_in_bounds.after(player_movement),
-                    spawn_enemies.run_if(in_state(GameState::Game)),
-                    change_spawn_weights.run_if(in_state(GameState::Game)),
+                    (
+                        spawn_enemies.run_if(|spawn_mode: Res<SelectedSpawnMode>| {
+                            spawn_mode.0 == SpawnMode::Continuous
+                        }),
+                        spawn_waves.run_if(|spawn_mode: Res<SelectedSpawnMode>| {
+                            spawn_mode.0 == SpawnMode::Waves
+                        }),
+                    )
+                        .after(update_spawn_storm)
+                        .run_if(in_state(GameState::Game))
+                        .run_if(gameplay_active)
+                        .run_if(normal_run),
+                    change_spawn_weights
+                        .run_if(in_state(GameState::Game))
+                        .run_if(gameplay_active)
+                        .run_if(normal_run),
                     move_enemies,
                     collisions.run_if(in_state(GameState::Game)),
                     update_enemy_count_display,
@@ -225,10 +623,13 @@ impl Plugin for GamePlugin {
                     level_up
                         .after(update_level_display)
                         .run_if(in_state(GameState::Game)),
-                    toggle_pause.run_if(input_just_pressed(PAUSE_INPUT)),
+                    toggle_pause.run_if(pause_input_just_pressed),
                     show_perk_chooser_buttons,
-                    choose_perk,
-                    health_regen.run_if(in_state(GameState::Game)),
+                    // a number key pressed to skip the button delay can choose a perk right away too
+                    choose_perk.after(show_perk_chooser_buttons),
+                    health_regen
+                        .run_if(in_state(GameState::Game))
+                        .run_if(gameplay_active),
                 ),
             )
             .add_systems(
@@ -240,17 +641,302 @@ impl Plugin for GamePlugin {
                     update_health_display
                         .after(collisions)
                         .run_if(resource_changed::<Health>()),
+                    update_window_title
+                        .after(update_level_display)
+                        .run_if(in_state(GameState::Game))
+                        .run_if(resource_changed::<Level>()),
+                    update_xp_bar
+                        .after(update_level_display)
+                        .run_if(in_state(GameState::Game)),
                     check_for_death.run_if(resource_changed::<Health>()),
+                    count_wave_kills
+                        .after(collisions)
+                        .before(spawn_waves)
+                        .run_if(in_state(GameState::Game)),
+                    update_wave_display
+                        .after(spawn_waves)
+                        .run_if(in_state(GameState::Game))
+                        .run_if(resource_exists_and_changed::<Wave>()),
+                ),
+            )
+            .add_systems(
+                Update,
+                (
+                    toggle_bullet_time
+                        .run_if(in_state(GameState::Game))
+                        .run_if(gameplay_active)
+                        .run_if(input_just_pressed(BULLET_TIME_INPUT)),
+                    update_bullet_time
+                        .after(toggle_bullet_time)
+                        .before(slow_mo)
+                        .run_if(in_state(GameState::Game))
+                        .run_if(gameplay_active),
+                    update_bullet_time_display.run_if(resource_changed::<BulletTime>()),
+                    update_secondary_action_aim
+                        .before(slow_mo)
+                        .run_if(in_state(GameState::Game))
+                        .run_if(gameplay_active),
+                    cleanse_on_level_up
+                        .after(update_level_display)
+                        .run_if(in_state(GameState::Game)),
+                    show_damage_indicator
+                        .after(collisions)
+                        .run_if(in_state(GameState::Game)),
+                    move_sword_beams
+                        .after(player_attack)
+                        .run_if(in_state(GameState::Game)),
+                    update_sword_arcs
+                        .after(player_attack)
+                        .run_if(in_state(GameState::Game)),
+                    update_perk_chooser_idle_timer
+                        .before(choose_perk)
+                        .run_if(in_state(GameState::Game)),
+                    update_perk_chooser_prompt
+                        .after(update_perk_chooser_idle_timer)
+                        .run_if(in_state(GameState::Game)),
+                    toggle_debug_overlay
+                        .run_if(in_state(GameState::Game))
+                        .run_if(input_just_pressed(DEBUG_OVERLAY_INPUT)),
+                    update_debug_overlay
+                        .after(toggle_debug_overlay)
+                        .run_if(in_state(GameState::Game)),
+                    update_proximity_warning
+                        .after(move_enemies)
+                        .run_if(in_state(GameState::Game)),
+                    (
+                        auto_zoom.run_if(|settings: Res<Settings>| settings.auto_zoom),
+                        ease_zoom_to_target.run_if(|settings: Res<Settings>| !settings.auto_zoom),
+                    )
+                        .run_if(in_state(GameState::Game)),
+                    update_audio_volume.run_if(in_state(GameState::Game)),
+                    update_dynamic_difficulty
+                        .before(spawn_enemies)
+                        .after(collisions)
+                        .run_if(in_state(GameState::Game))
+                        .run_if(gameplay_active)
+                        .run_if(|settings: Res<Settings>| settings.dynamic_difficulty),
+                    celebrate_clearing_enemies
+                        .after(collisions)
+                        .before(slow_mo)
+                        .run_if(in_state(GameState::Game))
+                        .run_if(gameplay_active),
+                    update_spawn_budget
+                        .before(spawn_enemies)
+                        .run_if(in_state(GameState::Game))
+                        .run_if(gameplay_active)
+                        .run_if(|settings: Res<Settings>| settings.spawn_budget),
+                    (update_slowed, update_dashing)
+                        .before(player_movement)
+                        .run_if(in_state(GameState::Game))
+                        .run_if(gameplay_active),
+                ),
+            )
+            .add_systems(
+                Update,
+                (
+                    show_perk_chooser
+                        .after(level_up)
+                        .run_if(in_state(GameState::Game)),
+                    (update_enemy_damage_indicators, flash_damaged_enemies)
+                        .after(collisions)
+                        .run_if(in_state(GameState::Game)),
+                    cycle_shields
+                        .before(collisions)
+                        .run_if(in_state(GameState::Game))
+                        .run_if(gameplay_active),
+                    pull_enemies_toward_magnets
+                        .after(move_enemies)
+                        .run_if(in_state(GameState::Game))
+                        .run_if(gameplay_active),
+                    (
+                        blink_enemies,
+                        move_xp_orbs,
+                        enemy_shooting,
+                        move_enemy_projectiles.after(enemy_shooting),
+                    )
+                        .after(move_enemies)
+                        .run_if(in_state(GameState::Game))
+                        .run_if(gameplay_active),
+                    update_pause_menu.run_if(in_state(GameState::Game)),
+                    respec_button_system.run_if(in_state(GameState::Game)),
+                    (resume_button_system, quit_to_menu_button_system)
+                        .run_if(in_state(GameState::Game)),
+                    offer_pending_perk_choice
+                        .after(respec_button_system)
+                        .before(show_perk_chooser)
+                        .run_if(in_state(GameState::Game))
+                        .run_if(gameplay_active),
+                    show_announcements
+                        .after(change_spawn_weights)
+                        .run_if(in_state(GameState::Game)),
+                    spawn_enemy_trails
+                        .after(move_enemies)
+                        .run_if(in_state(GameState::Game))
+                        .run_if(gameplay_active),
+                    update_downed
+                        .after(check_for_death)
+                        .run_if(in_state(GameState::Game))
+                        .run_if(gameplay_active),
+                    slow_field_collisions
+                        .before(move_enemies)
+                        .run_if(in_state(GameState::Game)),
+                    update_slow_fields
+                        .after(slow_field_collisions)
+                        .run_if(in_state(GameState::Game))
+                        .run_if(gameplay_active),
+                    update_run_stats
+                        .after(collisions)
+                        .run_if(in_state(GameState::Game))
+                        .run_if(gameplay_active),
+                    update_run_clock_display
+                        .after(update_run_stats)
+                        .run_if(in_state(GameState::Game))
+                        .run_if(resource_exists_and_changed::<RunStats>()),
+                    (
+                        update_spawn_storm,
+                        spawn_boss_on_level_up.run_if(normal_run),
+                    )
+                        .after(level_up)
+                        .run_if(in_state(GameState::Game)),
+                    (
+                        break_combo_on_hit,
+                        spawn_xp_popups,
+                        update_knockback_indicators,
+                        update_boss_health_bar,
+                        // offered on the next frame, so it doesn't get lost if the kill also levels the player up
+                        grant_perk_choice_for_boss_kills.after(offer_pending_perk_choice),
+                    )
+                        .after(collisions)
+                        .run_if(in_state(GameState::Game)),
+                    spawn_player_trail
+                        .after(player_movement)
+                        .run_if(in_state(GameState::Game))
+                        .run_if(gameplay_active),
+                ),
+            )
+            .add_systems(
+                Update,
+                (
+                    spawn_practice_boss
+                        .run_if(in_state(GameState::Game))
+                        .run_if(gameplay_active)
+                        .run_if(not(normal_run)),
+                    apply_boss_practice_god_mode
+                        .after(collisions)
+                        .before(check_for_death)
+                        .run_if(in_state(GameState::Game))
+                        .run_if(not(normal_run)),
                 ),
             )
+            .add_systems(OnEnter(GameState::Menu), end_boss_practice)
             .add_systems(PostUpdate, despawn_entities);
+
+        if DEV_MODE {
+            app.add_systems(
+                Update,
+                (
+                    draw_spawn_areas
+                        .run_if(in_state(GameState::Game))
+                        .run_if(input_toggle_active(false, SPAWN_AREA_OVERLAY_INPUT)),
+                    record_rewind_snapshot
+                        .after(collisions)
+                        .run_if(in_state(GameState::Game))
+                        .run_if(gameplay_active),
+                    rewind
+                        .before(record_rewind_snapshot)
+                        .run_if(in_state(GameState::Game))
+                        .run_if(input_just_pressed(REWIND_INPUT)),
+                ),
+            );
+        }
+
+        if LOG_FRAME_TIME_SPIKES {
+            app.add_systems(
+                Update,
+                log_frame_time_spikes
+                    .after(show_perk_chooser)
+                    .run_if(in_state(GameState::Game)),
+            );
+        }
+    }
+}
+
+/// Run condition that's only true while the game is actually being played, i.e. not paused or choosing a perk.
+/// Anything that ticks a gameplay timer should use this so the timer doesn't advance while the game is stopped.
+fn gameplay_active(
+    time: Res<Time<Virtual>>,
+    perk_chooser_query: Query<&Visibility, With<PerkChooser>>,
+) -> bool {
+    !time.is_paused()
+        && perk_chooser_query
+            .iter()
+            .all(|visibility| *visibility == Visibility::Hidden)
+}
+
+/// Run condition that's true unless the current run is boss practice
+fn normal_run(boss_practice: Res<BossPractice>) -> bool {
+    boss_practice.0.is_none()
+}
+
+/// Gets the state to go to in order to start a game.
+/// Assets only need to be loaded the first time, so the loading screen is skipped after that.
+pub fn get_game_start_state(
+    image_assets: &Option<Res<ImageAssets>>,
+    audio_assets: &Option<Res<AudioAssets>>,
+) -> GameState {
+    if image_assets.is_some() && audio_assets.is_some() {
+        GameState::Game
+    } else {
+        GameState::GameLoading
+    }
+}
+
+/// Builds the health the player starts with
+fn build_starting_health(mutators: &ActiveMutators, loadout: Loadout) -> Health {
+    let starting_health = if mutators.has(Mutator::GlassCannon) {
+        (STARTING_HEALTH as f64 * GLASS_CANNON_HEALTH_MULTIPLIER) as u64
+    } else {
+        STARTING_HEALTH
+    };
+
+    let mut health = Health {
+        current_health: starting_health,
+        max_health: starting_health,
+    };
+    if loadout == Loadout::Tank {
+        activate_higher_max_health(&mut health);
+    }
+
+    health
+}
+
+/// Gets the speed multiplier enemies start with
+fn get_starting_enemy_speed_multiplier(mutators: &ActiveMutators) -> f32 {
+    if mutators.has(Mutator::Frenzy) {
+        FRENZY_ENEMY_SPEED_MULTIPLIER
+    } else {
+        1.0
+    }
+}
+
+fn build_starting_downed() -> Downed {
+    Downed {
+        active: false,
+        used: false,
+        timer: Timer::new(DOWNED_DURATION, TimerMode::Once),
     }
 }
 
 /// Sets up resources that the game starts with
-fn insert_starting_resources(commands: &mut Commands) {
+fn insert_starting_resources(
+    commands: &mut Commands,
+    mutators: &ActiveMutators,
+    loadout: Loadout,
+    difficulty: Difficulty,
+) {
     commands.insert_resource(ZoomLevel(STARTING_ZOOM_LEVEL));
-    commands.insert_resource(build_starting_spawn_timer());
+    commands.insert_resource(TargetZoomLevel(STARTING_ZOOM_LEVEL));
+    commands.insert_resource(build_starting_spawn_timer(difficulty));
     commands.insert_resource(build_starting_spawn_interval_change_timer());
     commands.insert_resource(SpawnWeightsChangeTimer(Timer::new(
         SPAWN_WEIGHTS_CHANGE_INTERVAL,
@@ -265,12 +951,27 @@ fn insert_starting_resources(commands: &mut Commands) {
         previous_xp_needed: 0,
         xp_needed: STARTING_XP_THRESHOLD,
     });
-    commands.insert_resource(Health {
-        current_health: STARTING_HEALTH,
-        max_health: STARTING_HEALTH,
+    let starting_health = build_starting_health(mutators, loadout);
+    let starting_max_health = starting_health.max_health;
+    commands.insert_resource(starting_health);
+    commands.insert_resource(DynamicDifficulty {
+        timer: Timer::new(DYNAMIC_DIFFICULTY_INTERVAL, TimerMode::Repeating),
+        last_health: starting_max_health,
+        health_lost: 0,
+        kills: 0,
+        health_loss_rate: 0.0,
+        kill_rate: 0.0,
+        spawn_rate_multiplier: 1.0,
+    });
+    commands.insert_resource(SpawnBudget {
+        timer: Timer::new(SPAWN_BUDGET_INTERVAL, TimerMode::Repeating),
+        spawn_rate_multiplier: 1.0,
     });
+    commands.insert_resource(BusyStretch(false));
     commands.insert_resource(AvailablePerks(Vec::new()));
-    commands.insert_resource(EnemySpeedMultiplier(1.0));
+    commands.insert_resource(EnemySpeedMultiplier(get_starting_enemy_speed_multiplier(
+        mutators,
+    )));
     commands.insert_resource(PerkChooserDelayTimers {
         initial_delay: Timer::new(Duration::from_millis(500), TimerMode::Once),
         button_delays: vec![
@@ -279,6 +980,47 @@ fn insert_starting_resources(commands: &mut Commands) {
             Timer::new(Duration::from_millis(500), TimerMode::Once),
         ],
     });
+    commands.insert_resource(PerkChooserIdleTimer(Timer::new(
+        PERK_CHOOSER_IDLE_TIME,
+        TimerMode::Once,
+    )));
+    commands.insert_resource(PendingPerkChoices(0));
+    commands.insert_resource(BossPerkChoices(0));
+    commands.insert_resource(RewindHistory {
+        timer: Timer::new(REWIND_SNAPSHOT_INTERVAL, TimerMode::Repeating),
+        snapshots: VecDeque::new(),
+    });
+    commands.insert_resource(RunStats {
+        kills: 0,
+        survival_time: Duration::ZERO,
+    });
+    commands.insert_resource(ImportedBuildRun(false));
+    commands.insert_resource(GracePeriod(Timer::new(ENEMY_GRACE_PERIOD, TimerMode::Once)));
+    commands.insert_resource(SpawnStorm::finished());
+    commands.insert_resource(Wave {
+        number: 0,
+        size: 0,
+        killed: 0,
+    });
+    commands.insert_resource(BossPracticeRespawnTimer(Timer::new(
+        BOSS_PRACTICE_RESPAWN_DELAY,
+        TimerMode::Once,
+    )));
+
+    let mut announcement_queue = AnnouncementQueue(VecDeque::new());
+    announce(RUN_START_ANNOUNCEMENT, &mut announcement_queue);
+    commands.insert_resource(announcement_queue);
+    commands.insert_resource(build_starting_downed());
+    commands.insert_resource(EnemyTrailTimer(Timer::new(
+        ENEMY_TRAIL_INTERVAL,
+        TimerMode::Repeating,
+    )));
+
+    commands.insert_resource(BulletTime {
+        charge: 0.0,
+        active: false,
+    });
+    commands.insert_resource(AimingSecondaryAction(false));
 
     let mut slow_mo_timer = Timer::new(HIT_SLOW_MO_TIME, TimerMode::Once);
     slow_mo_timer.pause();
@@ -288,6 +1030,58 @@ fn insert_starting_resources(commands: &mut Commands) {
     });
 }
 
+/// Builds the handles for the assets shared between spawns.
+/// Explosion materials aren't shared since each explosion fades its own material out.
+fn build_shared_asset_handles(
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+    settings: &Settings,
+) -> SharedAssetHandles {
+    let damage_indicator_material =
+        materials.add(ColorMaterial::from(ENEMY_DAMAGE_INDICATOR_COLOR));
+    let shield_bubble_material = materials.add(ColorMaterial::from(SHIELD_BUBBLE_COLOR));
+    let enemies = EnemyType::iter()
+        .map(|enemy_type| {
+            let mut params = enemy_type.get_params();
+            params.scale_size(settings.enemy_size.get_multiplier());
+            let size = *params.size.start();
+            let threat_fraction = params.get_threat() / EnemyType::get_max_threat();
+            let handles = EnemyAssetHandles {
+                size,
+                mesh: meshes.add(shape::Circle::new(size).into()),
+                material: materials.add(ColorMaterial::from(params.color)),
+                threat_ring_mesh: meshes.add(shape::Circle::new(size + THREAT_RING_WIDTH).into()),
+                threat_ring_material: materials.add(ColorMaterial::from(
+                    THREAT_RING_COLOR.with_a(threat_fraction),
+                )),
+                damage_indicator_material: damage_indicator_material.clone(),
+                shield_bubble_material: shield_bubble_material.clone(),
+                trail_material: materials
+                    .add(ColorMaterial::from(params.color.with_a(ENEMY_TRAIL_ALPHA))),
+            };
+            (enemy_type, handles)
+        })
+        .collect();
+
+    SharedAssetHandles {
+        enemies,
+        explosion_mesh: meshes.add(shape::Circle::new(EXPLOSION_START_RADIUS).into()),
+        sword_beam_mesh: meshes
+            .add(shape::Quad::new(Vec2::new(SWORD_BEAM_WIDTH, SWORD_BEAM_THICKNESS)).into()),
+        sword_beam_material: materials.add(ColorMaterial::from(SWORD_BEAM_COLOR)),
+        enemy_projectile_mesh: meshes.add(shape::Circle::new(ENEMY_PROJECTILE_RADIUS).into()),
+        enemy_projectile_material: materials.add(ColorMaterial::from(ENEMY_PROJECTILE_COLOR)),
+        health_pickup_mesh: meshes.add(shape::Circle::new(HEALTH_PICKUP_RADIUS).into()),
+        health_pickup_material: materials.add(ColorMaterial::from(HEALTH_PICKUP_COLOR)),
+        xp_orb_mesh: meshes.add(shape::Circle::new(XP_ORB_RADIUS).into()),
+        xp_orb_material: materials.add(ColorMaterial::from(XP_ORB_COLOR)),
+        slow_field_mesh: meshes.add(shape::Circle::new(SLOW_FIELD_RADIUS).into()),
+        slow_field_material: materials.add(ColorMaterial::from(SLOW_FIELD_COLOR)),
+        knockback_indicator_mesh: meshes
+            .add(shape::RegularPolygon::new(KNOCKBACK_INDICATOR_SIZE, 3).into()),
+    }
+}
+
 /// Builds the spawn areas
 fn build_spawn_areas() -> SpawnAreas {
     SpawnAreas(vec![
@@ -323,8 +1117,11 @@ fn build_spawn_areas() -> SpawnAreas {
 }
 
 /// Builds the spawn timer that the game starts with
-fn build_starting_spawn_timer() -> SpawnTimer {
-    SpawnTimer(Timer::new(START_SPAWN_INTERVAL, TimerMode::Repeating))
+fn build_starting_spawn_timer(difficulty: Difficulty) -> SpawnTimer {
+    SpawnTimer(Timer::new(
+        START_SPAWN_INTERVAL.mul_f32(difficulty.get_spawn_interval_multiplier()),
+        TimerMode::Repeating,
+    ))
 }
 
 /// Builds the spawn interval change timer that the game starts with
@@ -348,6 +1145,13 @@ fn build_starting_spawn_weights() -> SpawnWeights {
             EnemyType::UltraBigAndSlow => 0,
             EnemyType::Assassin => 0,
             EnemyType::UltraAssassin => 0,
+            EnemyType::Clinger => 0,
+            EnemyType::Shielded => 0,
+            EnemyType::Blinker => 0,
+            EnemyType::Magnetar => 0,
+            // bosses only show up on level ups
+            EnemyType::Boss => 0,
+            EnemyType::Shooter => 0,
         };
         types.push(enemy_type);
         weights.push(weight);
@@ -364,7 +1168,7 @@ fn build_starting_spawn_weights() -> SpawnWeights {
 }
 
 #[derive(PartialEq, Eq, Clone, Copy, Hash, Debug, EnumIter)]
-enum PerkType {
+pub enum PerkType {
     LongerSword,
     WiderSwordSwing,
     ShorterAttackCooldown,
@@ -382,6 +1186,13 @@ enum PerkType {
     FasterHealthRegen,
     Retaliate,
     SlowerEnemies,
+    Cleanse,
+    SwordBeam,
+    UnlockSlowField,
+    LingeringArc,
+    UnlockDash,
+    XpMagnet,
+    CriticalHits,
 }
 
 impl PerkType {
@@ -390,18 +1201,34 @@ impl PerkType {
         amount: usize,
         existing_perks: &HashSet<PerkType>,
         health: &Health,
+        crit_chance: &CritChance,
+        mutators: &ActiveMutators,
     ) -> Vec<PerkType> {
         let has_grenade = existing_perks.contains(&PerkType::UnlockGrenade);
         let has_teleport = existing_perks.contains(&PerkType::UnlockTeleport);
         let has_teleport_explosion = existing_perks.contains(&PerkType::UnlockTeleportExplosion);
         let has_health_regen = existing_perks.contains(&PerkType::UnlockHealthRegen);
         let has_retaliate = existing_perks.contains(&PerkType::Retaliate);
+        let has_cleanse = existing_perks.contains(&PerkType::Cleanse);
+        let has_sword_beam = existing_perks.contains(&PerkType::SwordBeam);
+        let has_lingering_arc = existing_perks.contains(&PerkType::LingeringArc);
+        let has_slow_field = existing_perks.contains(&PerkType::UnlockSlowField);
+        let has_dash = existing_perks.contains(&PerkType::UnlockDash);
+        let has_xp_magnet = existing_perks.contains(&PerkType::XpMagnet);
         let is_full_health = health.current_health == health.max_health;
+        let is_max_crit_chance = crit_chance.0 >= MAX_CRIT_CHANCE;
+        let allows_secondary_actions = !mutators.has(Mutator::Bladesworn);
+
+        let mut rng = rand::thread_rng();
+        // cleanse is powerful, so it's only offered some of the time
+        let offer_cleanse = rng.gen_bool(CLEANSE_PERK_OFFER_CHANCE);
         let valid_perks = PerkType::iter().filter(|perk_type| match perk_type {
             PerkType::UnlockGrenade => false, // !has_grenade,
             PerkType::LargerGrenadeExplosion => has_grenade,
             PerkType::ShorterGrenadeCooldown => has_grenade,
-            PerkType::UnlockTeleport => !has_teleport,
+            PerkType::UnlockTeleport => {
+                allows_secondary_actions && !has_teleport && !has_slow_field && !has_dash
+            }
             PerkType::ShorterTeleportCooldown => has_teleport,
             PerkType::UnlockTeleportExplosion => has_teleport && !has_teleport_explosion,
             PerkType::LargerTeleportExplosion => has_teleport_explosion,
@@ -409,15 +1236,29 @@ impl PerkType {
             PerkType::FasterHealthRegen => has_health_regen,
             PerkType::Retaliate => !has_retaliate,
             PerkType::Heal => !is_full_health,
+            PerkType::Cleanse => !has_cleanse && offer_cleanse,
+            PerkType::SwordBeam => !has_sword_beam,
+            PerkType::LingeringArc => !has_lingering_arc,
+            PerkType::UnlockSlowField => {
+                allows_secondary_actions && !has_slow_field && !has_teleport && !has_dash
+            }
+            PerkType::UnlockDash => {
+                allows_secondary_actions && !has_dash && !has_teleport && !has_slow_field
+            }
+            PerkType::XpMagnet => !has_xp_magnet,
+            PerkType::CriticalHits => !is_max_crit_chance,
             _ => true,
         });
 
-        let mut rng = rand::thread_rng();
         valid_perks.choose_multiple(&mut rng, amount)
     }
 
     /// Gets the user-facing name and description of this perk type
-    fn get_name_and_description(&self) -> (String, String) {
+    fn get_name_and_description(&self, key_bindings: &KeyBindings) -> (String, String) {
+        let secondary_action_key = format!("{:?}", key_bindings.secondary_action);
+        let teleport_description = format!("You may find yourself at the location of your mouse cursor, and you may ask yourself, \"Well, how did I get here?\" (You got there by pressing {secondary_action_key}.)");
+        let slow_field_description = format!("Press {secondary_action_key} to create a field at your mouse cursor that slows down enemies inside it for a few seconds");
+        let dash_description = format!("Press {secondary_action_key} to dash forward, unable to be hurt until the dash is over");
         let (name, desc) = match self {
             PerkType::LongerSword => ("Reach", "Increases sword length by 10%"),
             PerkType::WiderSwordSwing => ("Wider Swing", "Increases sword swing arc by 10%"),
@@ -438,10 +1279,7 @@ impl PerkType {
             PerkType::ShorterGrenadeCooldown => {
                 ("More Grenades", "Decreases grenade throw cooldown by 10%")
             }
-            PerkType::UnlockTeleport => (
-                "Teleportation Device",
-                "You may find yourself at the location of your mouse cursor, and you may ask yourself, \"Well, how did I get here?\" (You got there by pressing the space bar.)",
-            ),
+            PerkType::UnlockTeleport => ("Teleportation Device", teleport_description.as_str()),
             PerkType::ShorterTeleportCooldown => {
                 ("Better Teleporter", "Decreases the teleport cooldown by 10%")
             }
@@ -451,6 +1289,13 @@ impl PerkType {
             PerkType::FasterHealthRegen => ("More Resilient", "Increases health regeneration rate by 10%"),
             PerkType::Retaliate => ("Retaliation", "When an enemy hits you, they die"),
             PerkType::SlowerEnemies => ("Faster Reflexes", "All enemies move 5% slower"),
+            PerkType::Cleanse => ("Cleanse", "When you level up, all enemies are destroyed"),
+            PerkType::SwordBeam => ("Sword Beam", "Each sword swing also fires a short-range slash that kills the first enemy it hits"),
+            PerkType::UnlockSlowField => ("Stasis Field", slow_field_description.as_str()),
+            PerkType::LingeringArc => ("Lingering Blade", "Each sword swing leaves behind an arc that hurts enemies who wander into it for a moment afterwards"),
+            PerkType::UnlockDash => ("Dash", dash_description.as_str()),
+            PerkType::XpMagnet => ("Magnetism", "XP orbs near you are pulled in toward you"),
+            PerkType::CriticalHits => ("Critical Hits", "Sword hits have an extra 10% chance (up to 50%) to do double damage, and enemies killed by them give double XP"),
         };
 
         (name.to_string(), desc.to_string())
@@ -488,6 +1333,15 @@ pub struct AudioAssets {
 #[derive(Resource)]
 struct SpawnTimer(Timer);
 
+/// How intense spawning had gotten by the end of the most recent run
+#[derive(Resource)]
+pub struct LastRunSpawnStats {
+    /// The time between enemy spawns
+    pub spawn_interval: Duration,
+    /// The names of the most common enemy types, along with the fraction of spawns they made up, most common first
+    pub top_enemy_types: Vec<(&'static str, f32)>,
+}
+
 #[derive(Resource)]
 struct SpawnIntervalChangeTimer(Timer);
 
@@ -497,27 +1351,215 @@ struct SpawnWeightsChangeTimer(Timer);
 #[derive(Resource)]
 struct SpawnAreas(Vec<Rect>);
 
-struct EnemyParams {
-    color: Color,
-    size: RangeInclusive<f32>,
-    max_speed: RangeInclusive<f32>,
+/// Recent snapshots of the game, oldest first, so it can be rewound while debugging
+#[derive(Resource)]
+struct RewindHistory {
+    timer: Timer,
+    snapshots: VecDeque<RewindSnapshot>,
+}
+
+/// The state of the game at one point in time.
+/// This doesn't cover everything, just enough to get back to roughly the same situation.
+struct RewindSnapshot {
+    player_transform: Transform,
+    health: Health,
+    level: Level,
+    enemies: Vec<EnemySnapshot>,
+}
+
+/// The state of a single enemy at one point in time
+struct EnemySnapshot {
+    enemy_type: EnemyType,
+    transform: Transform,
     damage: u64,
     xp_reward: u64,
+    max_speed: f32,
+    size: f32,
+    clings: bool,
+    current_health: u64,
+    max_health: u64,
+}
+
+/// Stats about the current run that aren't tracked anywhere else
+#[derive(Resource)]
+pub struct RunStats {
+    kills: u64,
+    /// How long the run has been played for in real time, not counting time spent paused or choosing perks
+    pub survival_time: Duration,
+}
+
+/// Whether the current run started from an imported build, in which case it doesn't count towards the player's progression
+#[derive(Resource)]
+struct ImportedBuildRun(bool);
+
+/// Gets the user-facing text for how long a run has lasted, as minutes and seconds
+pub fn format_survival_time(survival_time: Duration) -> String {
+    let total_seconds = survival_time.as_secs();
+    format!("{:02}:{:02}", total_seconds / 60, total_seconds % 60)
+}
+
+/// A summary of a finished run, as it's written to the run log
+#[derive(Serialize)]
+struct RunLogEntry {
+    /// When the run ended, in seconds since the Unix epoch
+    timestamp: u64,
+    difficulty: &'static str,
+    level: u64,
+    xp: u64,
+    kills: u64,
+    survival_seconds: f32,
+    perks: Vec<String>,
+}
+
+/// A short burst of much faster spawning, announced a little before it starts
+#[derive(Resource)]
+struct SpawnStorm {
+    /// Counts down to the storm starting after it's been announced
+    warning_timer: Timer,
+    /// Counts down to the storm ending after it's started
+    duration_timer: Timer,
+}
+
+impl SpawnStorm {
+    /// Builds a spawn storm that has already ended
+    fn finished() -> SpawnStorm {
+        let mut warning_timer = Timer::new(SPAWN_STORM_WARNING_TIME, TimerMode::Once);
+        warning_timer.set_elapsed(SPAWN_STORM_WARNING_TIME);
+        let mut duration_timer = Timer::new(SPAWN_STORM_DURATION, TimerMode::Once);
+        duration_timer.set_elapsed(SPAWN_STORM_DURATION);
+        SpawnStorm {
+            warning_timer,
+            duration_timer,
+        }
+    }
+
+    /// Gets how much faster than normal enemies should currently spawn
+    fn get_spawn_rate_multiplier(&self) -> f32 {
+        if self.warning_timer.finished() && !self.duration_timer.finished() {
+            SPAWN_STORM_SPAWN_RATE_MULTIPLIER
+        } else {
+            1.0
+        }
+    }
+}
+
+/// Tracks how well the player is doing so spawning can be sped up or slowed down to match
+#[derive(Resource)]
+struct DynamicDifficulty {
+    timer: Timer,
+    last_health: u64,
+    health_lost: u64,
+    kills: usize,
+    /// Smoothed health lost per second
+    health_loss_rate: f32,
+    /// Smoothed enemies killed per second
+    kill_rate: f32,
+    /// How much faster than normal enemies should spawn
+    spawn_rate_multiplier: f32,
+}
+
+/// Slows down spawning while the game is running slowly, so large numbers of enemies don't make it unplayable.
+/// This is only about performance, so it never makes enemies spawn faster than normal.
+#[derive(Resource)]
+struct SpawnBudget {
+    timer: Timer,
+    /// How much faster than normal enemies should spawn
+    spawn_rate_multiplier: f32,
+}
+
+pub struct EnemyParams {
+    pub color: Color,
+    pub size: RangeInclusive<f32>,
+    pub max_speed: RangeInclusive<f32>,
+    pub damage: u64,
+    pub xp_reward: u64,
+    /// Whether hitting the player slows them down
+    pub clings: bool,
+    /// Whether the enemy periodically becomes invulnerable
+    pub shielded: bool,
+    /// Whether the enemy periodically teleports toward the player
+    pub blinks: bool,
+    /// Whether the enemy pulls other nearby enemies towards itself
+    pub pulls_enemies: bool,
+    /// Whether the enemy keeps its distance and shoots at the player
+    pub shoots: bool,
+    /// How many hits it takes to kill the enemy
+    pub health: u64,
+}
+
+impl EnemyParams {
+    /// Makes enemies with these parameters bigger or smaller
+    fn scale_size(&mut self, multiplier: f32) {
+        self.size = (self.size.start() * multiplier)..=(self.size.end() * multiplier);
+    }
+
+    /// Gets a score for how dangerous an enemy with these parameters is
+    fn get_threat(&self) -> f32 {
+        let average_speed = (self.max_speed.start() + self.max_speed.end()) / 2.0;
+        self.damage as f32 * average_speed
+    }
 }
 
 #[derive(Hash, PartialEq, Eq, Clone, Copy, EnumIter)]
-enum EnemyType {
+pub enum EnemyType {
     Regular,
     SmallAndFast,
     BigAndSlow,
     UltraBigAndSlow,
     Assassin,
     UltraAssassin,
+    Clinger,
+    Shielded,
+    Blinker,
+    Magnetar,
+    Boss,
+    Shooter,
 }
 
 impl EnemyType {
+    /// Gets the user-facing name of this enemy type
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            EnemyType::Regular => "Regular",
+            EnemyType::SmallAndFast => "Small and fast",
+            EnemyType::BigAndSlow => "Big and slow",
+            EnemyType::UltraBigAndSlow => "Huge and slow",
+            EnemyType::Assassin => "Assassin",
+            EnemyType::UltraAssassin => "Ultra assassin",
+            EnemyType::Clinger => "Clinger",
+            EnemyType::Shielded => "Shielded",
+            EnemyType::Blinker => "Blinker",
+            EnemyType::Magnetar => "Magnetar",
+            EnemyType::Boss => "Champion",
+            EnemyType::Shooter => "Shooter",
+        }
+    }
+
+    /// Gets a short user-facing description of how this enemy type behaves
+    pub fn get_description(&self) -> &'static str {
+        match self {
+            EnemyType::Regular => "Heads straight for you",
+            EnemyType::SmallAndFast => "Hard to see and quick, but doesn't hit very hard",
+            EnemyType::BigAndSlow => "Takes two hits to bring down",
+            EnemyType::UltraBigAndSlow => "Takes three hits to bring down, and hits very hard",
+            EnemyType::Assassin => "Fast and deadly",
+            EnemyType::UltraAssassin => "Even faster and just as deadly",
+            EnemyType::Clinger => "Barely hurts, but slows you down when it hits you",
+            EnemyType::Shielded => "Can only be hurt while its shield is down",
+            EnemyType::Blinker => "Shows where it's going, then teleports toward you",
+            EnemyType::Magnetar => "Pulls nearby enemies in toward itself",
+            EnemyType::Boss => "Shows up every few levels, and takes a beating to bring down",
+            EnemyType::Shooter => "Keeps its distance and shoots at you",
+        }
+    }
+
+    /// Determines whether this enemy type is a boss, which only shows up every few levels
+    pub fn is_boss(&self) -> bool {
+        matches!(self, EnemyType::Boss)
+    }
+
     /// Gets the parameters describing the provided enemy type
-    fn get_params(&self) -> EnemyParams {
+    pub fn get_params(&self) -> EnemyParams {
         match self {
             EnemyType::Regular => EnemyParams {
                 color: Color::RED,
@@ -525,6 +1567,12 @@ impl EnemyType {
                 max_speed: 15.0..=25.0,
                 damage: 5,
                 xp_reward: 1,
+                clings: false,
+                shielded: false,
+                blinks: false,
+                pulls_enemies: false,
+                shoots: false,
+                health: 1,
             },
             EnemyType::SmallAndFast => EnemyParams {
                 color: Color::SEA_GREEN,
@@ -532,6 +1580,12 @@ impl EnemyType {
                 max_speed: 25.0..=35.0,
                 damage: 3,
                 xp_reward: 1,
+                clings: false,
+                shielded: false,
+                blinks: false,
+                pulls_enemies: false,
+                shoots: false,
+                health: 1,
             },
             EnemyType::BigAndSlow => EnemyParams {
                 color: Color::ORANGE_RED,
@@ -539,6 +1593,12 @@ impl EnemyType {
                 max_speed: 5.0..=15.0,
                 damage: 10,
                 xp_reward: 1,
+                clings: false,
+                shielded: false,
+                blinks: false,
+                pulls_enemies: false,
+                shoots: false,
+                health: 2,
             },
             EnemyType::UltraBigAndSlow => EnemyParams {
                 color: Color::PINK,
@@ -546,6 +1606,12 @@ impl EnemyType {
                 max_speed: 10.0..=15.0,
                 damage: 25,
                 xp_reward: 3,
+                clings: false,
+                shielded: false,
+                blinks: false,
+                pulls_enemies: false,
+                shoots: false,
+                health: 3,
             },
             EnemyType::Assassin => EnemyParams {
                 color: Color::AQUAMARINE,
@@ -553,6 +1619,12 @@ impl EnemyType {
                 max_speed: 40.0..=50.0,
                 damage: 15,
                 xp_reward: 2,
+                clings: false,
+                shielded: false,
+                blinks: false,
+                pulls_enemies: false,
+                shoots: false,
+                health: 1,
             },
             EnemyType::UltraAssassin => EnemyParams {
                 color: Color::WHITE,
@@ -560,9 +1632,105 @@ impl EnemyType {
                 max_speed: 70.0..=80.0,
                 damage: 15,
                 xp_reward: 3,
+                clings: false,
+                shielded: false,
+                blinks: false,
+                pulls_enemies: false,
+                shoots: false,
+                health: 1,
+            },
+            EnemyType::Clinger => EnemyParams {
+                color: Color::PURPLE,
+                size: 3.5..=3.5,
+                max_speed: 30.0..=40.0,
+                damage: 1,
+                xp_reward: 2,
+                clings: true,
+                shielded: false,
+                blinks: false,
+                pulls_enemies: false,
+                shoots: false,
+                health: 1,
+            },
+            EnemyType::Shielded => EnemyParams {
+                color: Color::SILVER,
+                size: 5.0..=5.0,
+                max_speed: 15.0..=20.0,
+                damage: 10,
+                xp_reward: 3,
+                clings: false,
+                shielded: true,
+                blinks: false,
+                pulls_enemies: false,
+                shoots: false,
+                health: 1,
+            },
+            EnemyType::Blinker => EnemyParams {
+                color: Color::GOLD,
+                size: 3.5..=3.5,
+                max_speed: 10.0..=15.0,
+                damage: 10,
+                xp_reward: 3,
+                clings: false,
+                shielded: false,
+                blinks: true,
+                pulls_enemies: false,
+                shoots: false,
+                health: 1,
+            },
+            EnemyType::Magnetar => EnemyParams {
+                color: Color::INDIGO,
+                size: 6.0..=6.0,
+                max_speed: 10.0..=15.0,
+                damage: 10,
+                xp_reward: 4,
+                clings: false,
+                shielded: false,
+                blinks: false,
+                pulls_enemies: true,
+                shoots: false,
+                health: 2,
+            },
+            EnemyType::Boss => EnemyParams {
+                color: Color::MAROON,
+                size: 14.0..=14.0,
+                max_speed: 8.0..=12.0,
+                damage: 30,
+                xp_reward: 20,
+                clings: false,
+                shielded: false,
+                blinks: false,
+                pulls_enemies: false,
+                shoots: false,
+                health: 20,
+            },
+            EnemyType::Shooter => EnemyParams {
+                color: Color::LIME_GREEN,
+                size: 4.0..=4.0,
+                max_speed: 15.0..=20.0,
+                damage: 5,
+                xp_reward: 3,
+                clings: false,
+                shielded: false,
+                blinks: false,
+                pulls_enemies: false,
+                shoots: true,
+                health: 1,
             },
         }
     }
+
+    /// Determines whether enemies of this type always drop a health pickup when they're killed
+    fn always_drops_health_pickup(&self) -> bool {
+        matches!(self, EnemyType::UltraBigAndSlow | EnemyType::Boss)
+    }
+
+    /// Gets the highest threat score of any enemy type
+    fn get_max_threat() -> f32 {
+        EnemyType::iter()
+            .map(|enemy_type| enemy_type.get_params().get_threat())
+            .fold(0.0, f32::max)
+    }
 }
 
 #[derive(Resource)]
@@ -584,39 +1752,165 @@ impl SpawnWeights {
 #[derive(Resource)]
 struct EntitiesToDespawn(Vec<Entity>);
 
+/// Big text shown briefly in the middle of the screen to call out something happening in the run
+#[derive(Component)]
+struct Announcement;
+
+/// Announcements waiting to be shown, so they're shown one after another instead of on top of each other
 #[derive(Resource)]
-pub struct Level {
-    pub current_level: u64,
-    pub current_xp: u64,
-    previous_xp_needed: u64,
-    xp_needed: u64,
-}
+struct AnnouncementQueue(VecDeque<String>);
 
-impl Level {
-    /// Advances to the next level
-    fn advance(&mut self) {
-        self.current_level += 1;
+/// Queues up an announcement to be shown once any announcements before it are done
+fn announce(text: impl Into<String>, queue: &mut AnnouncementQueue) {
+    queue.0.push_back(text.into());
+}
 
-        let xp_since_last_level = self.xp_needed - self.previous_xp_needed;
-        let additional_xp_needed = xp_since_last_level as f64 * NEXT_LEVEL_ADDITIONAL_XP_MULTIPLIER;
+/// An arrangement of obstacles in the arena, one of which is picked at random for each run
+#[derive(Clone, Copy, EnumIter)]
+enum ObstacleLayout {
+    /// Square pillars scattered around the arena
+    Pillars,
+    /// Wall segments surrounding the center of the arena
+    Walls,
+}
 
-        self.previous_xp_needed = self.xp_needed;
-        self.xp_needed += additional_xp_needed.round() as u64;
+impl ObstacleLayout {
+    /// Gets the areas covered by the obstacles in this layout
+    fn get_obstacles(&self) -> Vec<Rect> {
+        match self {
+            ObstacleLayout::Pillars => [
+                Vec2::new(-200.0, -200.0),
+                Vec2::new(-200.0, 200.0),
+                Vec2::new(200.0, -200.0),
+                Vec2::new(200.0, 200.0),
+                Vec2::new(0.0, -350.0),
+                Vec2::new(0.0, 350.0),
+                Vec2::new(-350.0, 0.0),
+                Vec2::new(350.0, 0.0),
+            ]
+            .into_iter()
+            .map(|center| Rect::from_center_size(center, Vec2::splat(PILLAR_SIZE)))
+            .collect(),
+            ObstacleLayout::Walls => vec![
+                // top and bottom
+                Rect::from_center_size(
+                    Vec2::new(0.0, 250.0),
+                    Vec2::new(WALL_LENGTH, WALL_THICKNESS),
+                ),
+                Rect::from_center_size(
+                    Vec2::new(0.0, -250.0),
+                    Vec2::new(WALL_LENGTH, WALL_THICKNESS),
+                ),
+                // left and right
+                Rect::from_center_size(
+                    Vec2::new(-250.0, 0.0),
+                    Vec2::new(WALL_THICKNESS, WALL_LENGTH),
+                ),
+                Rect::from_center_size(
+                    Vec2::new(250.0, 0.0),
+                    Vec2::new(WALL_THICKNESS, WALL_LENGTH),
+                ),
+            ],
+        }
+    }
+}
+
+/// Mesh and material handles that are reused between spawns, so new assets don't need to be allocated for every spawned entity
+#[derive(Resource)]
+struct SharedAssetHandles {
+    enemies: HashMap<EnemyType, EnemyAssetHandles>,
+    explosion_mesh: Handle<Mesh>,
+    sword_beam_mesh: Handle<Mesh>,
+    sword_beam_material: Handle<ColorMaterial>,
+    enemy_projectile_mesh: Handle<Mesh>,
+    enemy_projectile_material: Handle<ColorMaterial>,
+    health_pickup_mesh: Handle<Mesh>,
+    health_pickup_material: Handle<ColorMaterial>,
+    xp_orb_mesh: Handle<Mesh>,
+    xp_orb_material: Handle<ColorMaterial>,
+    slow_field_mesh: Handle<Mesh>,
+    slow_field_material: Handle<ColorMaterial>,
+    knockback_indicator_mesh: Handle<Mesh>,
+}
+
+/// The handles used to draw an enemy of a particular type
+struct EnemyAssetHandles {
+    /// The size the mesh was built for
+    size: f32,
+    mesh: Handle<Mesh>,
+    material: Handle<ColorMaterial>,
+    threat_ring_mesh: Handle<Mesh>,
+    threat_ring_material: Handle<ColorMaterial>,
+    damage_indicator_material: Handle<ColorMaterial>,
+    shield_bubble_material: Handle<ColorMaterial>,
+    trail_material: Handle<ColorMaterial>,
+}
+
+#[derive(Resource, Clone)]
+pub struct Level {
+    pub current_level: u64,
+    pub current_xp: u64,
+    previous_xp_needed: u64,
+    xp_needed: u64,
+}
+
+impl Level {
+    /// Advances to the next level
+    fn advance(&mut self) {
+        self.current_level += 1;
+
+        let xp_since_last_level = self.xp_needed - self.previous_xp_needed;
+        let additional_xp_needed = xp_since_last_level as f64 * NEXT_LEVEL_ADDITIONAL_XP_MULTIPLIER;
+
+        self.previous_xp_needed = self.xp_needed;
+        self.xp_needed += additional_xp_needed.round() as u64;
     }
 }
 
+/// The zoom level that leveling up has set, which the actual zoom level eases towards
 #[derive(Resource)]
+struct TargetZoomLevel(f32);
+
+#[derive(Resource, Clone)]
 struct Health {
     current_health: u64,
     max_health: u64,
 }
 
+/// Temporarily slows down virtual time.
+///
+/// The timer is ticked with real time, since ticking it with the virtual time it's slowing down would stretch the effect out by the inverse of the time scale.
 #[derive(Resource)]
 struct SlowMoTimer {
     target_time_scale: f32,
     timer: Timer,
 }
 
+impl SlowMoTimer {
+    /// Starts slowing time down to the provided scale for the provided duration
+    fn start(&mut self, time_scale: f32, duration: Duration) {
+        self.target_time_scale = time_scale;
+        self.timer.set_duration(duration);
+        self.timer.reset();
+        self.timer.unpause();
+    }
+}
+
+/// Player-controlled slow motion that charges up over time and from kills
+#[derive(Resource)]
+struct BulletTime {
+    charge: f32,
+    active: bool,
+}
+
+/// Whether there have been lots of enemies around since the last time the arena was cleared out
+#[derive(Resource)]
+struct BusyStretch(bool);
+
+/// Whether the player is holding down the secondary action button to aim it
+#[derive(Resource)]
+struct AimingSecondaryAction(bool);
+
 #[derive(Resource)]
 struct AvailablePerks(Vec<PerkType>);
 
@@ -629,6 +1923,69 @@ struct PerkChooserDelayTimers {
     button_delays: Vec<Timer>,
 }
 
+/// Tracks how long the player has been idle while the perk chooser is open
+#[derive(Resource)]
+struct PerkChooserIdleTimer(Timer);
+
+/// The number of perk choices earned outside of leveling up, or refunded by a respec, that haven't been offered to the player yet
+#[derive(Resource)]
+struct PendingPerkChoices(u64);
+
+/// The number of extra perk choices earned from killing bosses this run
+#[derive(Resource)]
+struct BossPerkChoices(u64);
+
+/// What to practice against in a run spent fighting a single boss over and over
+#[derive(Clone, Copy)]
+pub struct BossPracticeSettings {
+    pub boss_type: EnemyType,
+    /// Whether the player should be kept from dying, so the boss's attacks can be studied without pressure
+    pub god_mode: bool,
+}
+
+/// The boss practice settings for the next run, or `None` for a normal run.
+/// Boss practice runs skip all the normal spawning, and don't count towards lifetime stats or the run log.
+#[derive(Resource)]
+pub struct BossPractice(pub Option<BossPracticeSettings>);
+
+/// Counts down to sending in the next boss during boss practice
+#[derive(Resource)]
+struct BossPracticeRespawnTimer(Timer);
+
+/// The most recent wave of enemies, when spawning in waves
+#[derive(Resource)]
+struct Wave {
+    /// The number of this wave. Zero before the first wave.
+    number: u64,
+    /// How many enemies need to be killed to clear this wave, including any left over from the last one when it started.
+    /// Bosses aren't part of waves, so they aren't included.
+    size: u64,
+    /// How many enemies that are part of this wave have been killed
+    killed: u64,
+}
+
+impl Wave {
+    /// Gets how many enemies in this wave are still alive
+    fn get_remaining(&self) -> u64 {
+        self.size.saturating_sub(self.killed)
+    }
+}
+
+/// The time at the start of a run during which enemies gradually work up to moving at full force
+#[derive(Resource)]
+struct GracePeriod(Timer);
+
+/// Tracks the player being knocked down at zero health, on difficulties that give them a chance to get back up
+#[derive(Resource)]
+struct Downed {
+    /// Whether the player is currently downed
+    active: bool,
+    /// Whether the player has already been downed this run, since they can only get back up once
+    used: bool,
+    /// How long the player has left to get back up
+    timer: Timer,
+}
+
 #[derive(Component)]
 struct LoadingComponent;
 
@@ -641,18 +1998,78 @@ struct GameComponent;
 #[derive(Component)]
 struct BackgroundMusic;
 
+/// How much the background music and sound effects are currently turned down, as a multiplier on their normal volume
+#[derive(Resource)]
+struct AudioDucking(f32);
+
+/// A sound effect that's playing, with the volume it would be played at without any ducking
+#[derive(Component)]
+struct SoundEffect {
+    volume: f32,
+}
+
 #[derive(Component)]
 struct Player;
 
 #[derive(Component)]
 struct AttackCooldown(Timer);
 
+/// A way of punishing the player's attack combo
+enum ComboHitPenalty {
+    /// Go back to the first swing
+    Reset,
+    /// Go back half of the way to the first swing
+    Halve,
+}
+
+impl ComboHitPenalty {
+    /// Gets which swing the combo should be on after this penalty, given the swing it's on now
+    fn apply(&self, next_swing: usize) -> usize {
+        match self {
+            ComboHitPenalty::Reset => 0,
+            ComboHitPenalty::Halve => next_swing / 2,
+        }
+    }
+}
+
+/// Briefly shown above the player when they lose their attack combo
+#[derive(Component)]
+struct ComboLostText;
+
+/// Shows how much XP an enemy was worth where it died
+#[derive(Component)]
+struct XpPopup;
+
+/// Tracks which swing of a combo the player is on
+#[derive(Component)]
+struct AttackCombo {
+    /// The index of the next swing in the combo
+    next_swing: usize,
+    /// The combo is dropped if this finishes before the next swing
+    reset_timer: Timer,
+}
+
 #[derive(Component)]
 struct MaxSpeed(f32);
 
 #[derive(Component)]
 struct Perks(HashSet<PerkType>);
 
+/// Lowers the player's max speed until the timer finishes
+#[derive(Component)]
+struct Slowed {
+    timer: Timer,
+    speed_multiplier: f32,
+}
+
+/// Keeps the player moving at a fixed velocity, and unable to be hurt, until the timer finishes
+#[derive(Component)]
+struct Dashing {
+    timer: Timer,
+    velocity: Vec2,
+}
+
+#[derive(Clone)]
 enum SecondaryActionType {
     None,
     Grenade {
@@ -664,12 +2081,34 @@ enum SecondaryActionType {
         explodes: bool,
         explosion_radius: f32,
     },
+    SlowField {
+        cooldown_timer: Timer,
+        radius: f32,
+        duration: Duration,
+    },
+    Dash {
+        cooldown_timer: Timer,
+        distance: f32,
+    },
+}
+
+impl SecondaryActionType {
+    /// Determines whether this action can be used right now
+    fn is_ready(&self) -> bool {
+        match self {
+            SecondaryActionType::None => false,
+            SecondaryActionType::Grenade { cooldown_timer, .. } => cooldown_timer.finished(),
+            SecondaryActionType::Teleport { cooldown_timer, .. } => cooldown_timer.finished(),
+            SecondaryActionType::SlowField { cooldown_timer, .. } => cooldown_timer.finished(),
+            SecondaryActionType::Dash { cooldown_timer, .. } => cooldown_timer.finished(),
+        }
+    }
 }
 
 #[derive(Component)]
 struct SecondaryAction(SecondaryActionType);
 
-#[derive(Component)]
+#[derive(Component, Clone)]
 struct HealthRegen {
     timer: Timer,
     amount: u64,
@@ -678,24 +2117,147 @@ struct HealthRegen {
 #[derive(Component)]
 struct Retaliate(bool);
 
+#[derive(Component)]
+struct SwordBeam(bool);
+
+#[derive(Component)]
+struct LingeringArc(bool);
+
+/// The chance that a sword hit is a critical hit
+#[derive(Component)]
+struct CritChance(f32);
+
+/// The area a sword swing passed through, which hurts enemies that wander into it shortly after the swing
+#[derive(Component)]
+struct SwordArc {
+    /// The swing that left this arc behind, so enemies the swing already hit aren't hit again
+    swing: u64,
+    /// Counts down to the sword finishing its swing, when the arc starts hurting enemies
+    activation_timer: Timer,
+    lifetime: Timer,
+    /// The collider to give the arc once it activates
+    collider: Option<Collider>,
+}
+
 #[derive(Component)]
 struct SwordPivot;
 
 #[derive(Component)]
 struct Sword {
     active: bool,
+    /// Counts up with each swing, so hits from the same swing can be told apart
+    swing: u64,
 }
 
 #[derive(Component)]
 struct Attacking(bool);
 
+#[derive(Component)]
+struct ProximityWarning;
+
 #[derive(Component)]
 struct Enemy {
+    enemy_type: EnemyType,
     damage: u64,
     xp_reward: u64,
     max_speed: f32,
     size: f32,
     color: Color,
+    clings: bool,
+}
+
+/// The material used for the trail an enemy leaves behind when it's moving fast
+#[derive(Component)]
+struct EnemyTrailMaterial(Handle<ColorMaterial>);
+
+/// A fading copy of a fast-moving enemy left behind where it used to be
+#[derive(Component)]
+struct EnemyTrail;
+
+#[derive(Resource)]
+struct EnemyTrailTimer(Timer);
+
+/// Makes the player leave fading copies of themselves behind as they move, if they've chosen the trail cosmetic
+#[derive(Component)]
+struct LeavesTrail {
+    timer: Timer,
+    material: Handle<ColorMaterial>,
+}
+
+/// A fading copy of the player left behind where they used to be
+#[derive(Component)]
+struct PlayerTrailSegment;
+
+/// A ring that expands out from the player when they level up
+#[derive(Component)]
+struct LevelUpBurst;
+
+#[derive(Component)]
+struct EnemyHealth {
+    current: u64,
+    max: u64,
+    /// The last sword swing that damaged this enemy
+    last_sword_swing: Option<u64>,
+}
+
+/// Covers more of an enemy the more damage it's taken
+#[derive(Component)]
+struct EnemyDamageIndicator;
+
+/// Makes an enemy switch between being invulnerable and vulnerable
+#[derive(Component)]
+struct Shield {
+    /// Counts down to the next switch
+    timer: Timer,
+    /// Whether the enemy is currently invulnerable
+    active: bool,
+}
+
+/// Shows that an enemy's shield is up
+#[derive(Component)]
+struct ShieldBubble;
+
+/// Makes an enemy periodically teleport a short distance toward the player
+#[derive(Component)]
+struct Blink {
+    /// Counts down to the next telegraph or blink
+    timer: Timer,
+    /// Where the enemy is about to blink to, if it's telegraphing a blink
+    target: Option<Vec2>,
+}
+
+/// Makes an enemy pull other nearby enemies towards itself
+#[derive(Component)]
+struct Magnet;
+
+/// Makes an enemy keep its distance from the player and shoot at them. The timer counts down to the next shot.
+#[derive(Component)]
+struct Shooter(Timer);
+
+/// Gives the player XP when they touch it, dropped by killed enemies
+#[derive(Component)]
+struct XpOrb {
+    xp: u64,
+    /// How fast the orb is being pulled toward the player by the magnet perk
+    velocity: Vec2,
+}
+
+/// Restores some of the player's health when they touch it
+#[derive(Component)]
+struct HealthPickup;
+
+/// A shot fired by an enemy toward where the player was
+#[derive(Component)]
+struct EnemyProjectile {
+    velocity: Vec2,
+    lifetime: Timer,
+}
+
+/// Shows where an enemy is about to blink to
+#[derive(Component)]
+struct BlinkMarker {
+    /// The enemy that's about to blink
+    owner: Entity,
 }
 
 #[derive(Component)]
@@ -704,12 +2266,37 @@ struct LevelText;
 #[derive(Component)]
 struct XpText;
 
+/// The part of the XP bar that fills up as the player gets closer to the next level
+#[derive(Component)]
+struct XpBarFill {
+    /// How full the bar is currently drawn, from 0 to 1, which lags behind the actual XP so the bar fills smoothly
+    shown_fraction: f32,
+}
+
 #[derive(Component)]
 struct EnemyCountText;
 
+#[derive(Component)]
+struct RunClockText;
+
+#[derive(Component)]
+struct WaveText;
+
 #[derive(Component)]
 struct HealthText;
 
+#[derive(Component)]
+struct BulletTimeMeterFill;
+
+#[derive(Component)]
+struct BossHealthBar;
+
+#[derive(Component)]
+struct BossHealthBarFill;
+
+#[derive(Component)]
+struct DebugOverlayText;
+
 #[derive(Component)]
 struct PerkChooser;
 
@@ -719,20 +2306,97 @@ struct ChoosePerkButton(usize);
 #[derive(Component)]
 struct PerkText(usize);
 
+#[derive(Component)]
+struct PerkChooserPrompt;
+
+#[derive(Component)]
+struct PauseMenu;
+
+#[derive(Component)]
+struct RespecButton;
+
+#[derive(Component)]
+struct ResumeButton;
+
+#[derive(Component)]
+struct QuitToMenuButton;
+
+/// The player's stats from the start of the run, so chosen perks can be undone when respeccing
+#[derive(Component)]
+struct BaseStats {
+    attack_cooldown: Duration,
+    max_speed: f32,
+    max_health: u64,
+    enemy_speed_multiplier: f32,
+    secondary_action: SecondaryActionType,
+    health_regen: HealthRegen,
+    retaliate: bool,
+    sword_beam: bool,
+    lingering_arc: bool,
+    crit_chance: f32,
+    perks: HashSet<PerkType>,
+}
+
+/// The swing parameters a sword pivot started with, before any perks were applied
+#[derive(Component)]
+struct BaseSwordAnimationParams(SwordAnimationParams);
+
 #[derive(Component)]
 struct Explosion;
 
+/// An area that slows down enemies inside it until it runs out
+#[derive(Component)]
+struct SlowField(Timer);
+
+/// The slow fields an enemy is currently inside of
+#[derive(Component)]
+struct SlowFieldOverlaps(HashSet<Entity>);
+
+#[derive(Component)]
+struct SwordBeamProjectile {
+    velocity: Vec2,
+    lifetime: Timer,
+}
+
 #[derive(Component)]
 struct DeathAnimation;
 
 #[derive(Component)]
 struct SecondaryActionCooldownDisplay;
 
+#[derive(Component)]
+struct DamageIndicator {
+    border_timer: Timer,
+}
+
+/// An arrow briefly shown next to the player pointing in the direction they were knocked back.
+/// The timer tracks how much longer it should be shown for.
+#[derive(Component)]
+struct KnockbackIndicator(Timer);
+
 #[derive(Event)]
 struct LevelUp {
     new_level: u64,
 }
 
+#[derive(Event)]
+struct ShowPerkChooser;
+
+#[derive(Event)]
+struct StartSpawnStorm;
+
+#[derive(Event)]
+struct EnemyKilled {
+    enemy_type: EnemyType,
+    position: Vec2,
+    xp_reward: u64,
+    /// Whether the enemy was killed by a critical hit
+    critical: bool,
+}
+
+#[derive(Event)]
+struct PlayerHit;
+
 /// Sets up the loading screen.
 fn loading_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
     commands
@@ -772,14 +2436,33 @@ fn display_loading_progress(
 }
 
 /// Sets up the game
+#[allow(clippy::too_many_arguments)]
 fn game_setup(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
     image_assets: Res<ImageAssets>,
+    theme: Res<Theme>,
     asset_server: Res<AssetServer>,
+    mutators: Res<ActiveMutators>,
+    selected_loadout: Res<SelectedLoadout>,
+    selected_difficulty: Res<SelectedDifficulty>,
+    selected_spawn_mode: Res<SelectedSpawnMode>,
+    settings: Res<Settings>,
+    cosmetics: Res<Cosmetics>,
+    key_bindings: Res<KeyBindings>,
 ) {
-    insert_starting_resources(&mut commands);
+    insert_starting_resources(
+        &mut commands,
+        &mutators,
+        selected_loadout.0,
+        selected_difficulty.0,
+    );
+    commands.insert_resource(build_shared_asset_handles(
+        &mut meshes,
+        &mut materials,
+        &settings,
+    ));
 
     // background
     commands
@@ -795,38 +2478,79 @@ fn game_setup(
         })
         .insert(GameComponent);
 
-    let mut attack_cooldown = AttackCooldown(Timer::new(PLAYER_ATTACK_COOLDOWN, TimerMode::Once));
-    attack_cooldown.0.set_elapsed(PLAYER_ATTACK_COOLDOWN);
-
-    let sword_swing_params = SwordAnimationParams {
-        start_delay: Duration::from_nanos(1),
-        start_scale: SWORD_START_SCALE,
-        end_scale: SWORD_END_SCALE,
-        swing_time: SWORD_ANIMATION_TIME,
-        start_rotation: SWORD_START_ROTATION,
-        end_rotation: SWORD_END_ROTATION,
-        start_translation: SWORD_START_TRANSLATION,
-        end_translation: SWORD_END_TRANSLATION,
-        send_swing_complete_event: false,
-        swing_end_delay: SWORD_ANIMATION_END_DELAY,
-        put_away_time: SWORD_PUT_AWAY_TIME,
-        send_attack_done_event: false,
+    let attack_cooldown_duration = if mutators.has(Mutator::GlassCannon) {
+        PLAYER_ATTACK_COOLDOWN.mul_f32(GLASS_CANNON_ATTACK_COOLDOWN_MULTIPLIER)
+    } else {
+        PLAYER_ATTACK_COOLDOWN
+    };
+    let mut attack_cooldown = AttackCooldown(Timer::new(attack_cooldown_duration, TimerMode::Once));
+    let mut secondary_action = SecondaryAction(SecondaryActionType::None);
+    let mut starting_perks = HashSet::new();
+    match selected_loadout.0 {
+        Loadout::Standard => (),
+        Loadout::Swordmaster => {
+            activate_shorter_attack_cooldown(&mut attack_cooldown);
+            starting_perks.insert(PerkType::ShorterAttackCooldown);
+        }
+        Loadout::Teleporter => {
+            if !mutators.has(Mutator::Bladesworn) {
+                activate_unlock_teleport(&mut secondary_action);
+                starting_perks.insert(PerkType::UnlockTeleport);
+            }
+        }
+        Loadout::Tank => {
+            // the max health increase is applied when the health resource is built
+            starting_perks.insert(PerkType::HigherMaxHealth);
+        }
+    }
+    attack_cooldown.0.set_elapsed(attack_cooldown.0.duration());
+
+    let base_stats = BaseStats {
+        attack_cooldown: attack_cooldown.0.duration(),
+        max_speed: PLAYER_MAX_SPEED,
+        max_health: build_starting_health(&mutators, selected_loadout.0).max_health,
+        enemy_speed_multiplier: get_starting_enemy_speed_multiplier(&mutators),
+        secondary_action: secondary_action.0.clone(),
+        health_regen: HealthRegen {
+            timer: Timer::new(Duration::from_nanos(1), TimerMode::Once),
+            amount: 0,
+        },
+        retaliate: false,
+        sword_beam: false,
+        lingering_arc: false,
+        crit_chance: 0.0,
+        perks: starting_perks.clone(),
     };
 
-    let mut sword_shadow_swing_params = SWORD_SHADOW_DELAYS_AND_ALPHAS
+    let mut sword_end_scale = SWORD_END_SCALE;
+    if mutators.has(Mutator::Bladesworn) {
+        sword_end_scale.y *= BLADESWORN_SWORD_LENGTH_MULTIPLIER;
+    }
+
+    let sword_swing_params = build_main_sword_animation_params(sword_end_scale);
+
+    // count back from the last shadow so it's always kept if any are, since it's the last to finish swinging
+    let shadow_step = settings.sword_shadows.get_step();
+    let last_shadow_index = SWORD_SHADOW_DELAYS_AND_ALPHAS.len() - 1;
+    let sword_shadow_swing_params = SWORD_SHADOW_DELAYS_AND_ALPHAS
         .iter()
+        .enumerate()
+        .filter(|(i, _)| {
+            shadow_step.is_some_and(|step| (last_shadow_index - i).is_multiple_of(step))
+        })
+        .map(|(_, shadow)| shadow)
         .map(|(delay, alpha)| {
             (
                 SwordAnimationParams {
                     start_delay: *delay,
                     start_scale: SWORD_START_SCALE,
-                    end_scale: SWORD_END_SCALE,
+                    end_scale: sword_end_scale,
                     swing_time: SWORD_ANIMATION_TIME,
                     start_rotation: SWORD_START_ROTATION,
                     end_rotation: SWORD_END_ROTATION,
                     start_translation: SWORD_START_TRANSLATION,
                     end_translation: SWORD_END_TRANSLATION,
-                    send_swing_complete_event: false,
+                    swing_complete_event_delay: None,
                     swing_end_delay: SWORD_ANIMATION_END_DELAY - *delay,
                     put_away_time: SWORD_PUT_AWAY_TIME,
                     send_attack_done_event: false,
@@ -836,16 +2560,12 @@ fn game_setup(
         })
         .collect::<Vec<(SwordAnimationParams, f32)>>();
 
-    if let Some(last_params) = sword_shadow_swing_params.last_mut() {
-        last_params.0.send_swing_complete_event = true;
-        last_params.0.send_attack_done_event = true;
-    }
-
     // player
-    commands
+    let player_color = cosmetics.player_color.get_color();
+    let player_entity = commands
         .spawn(MaterialMesh2dBundle {
             mesh: meshes.add(shape::Circle::new(PLAYER_SIZE).into()).into(),
-            material: materials.add(ColorMaterial::from(Color::PURPLE)),
+            material: materials.add(ColorMaterial::from(player_color)),
             transform: Transform::from_translation(Vec3::new(0., 0., 0.)),
             ..default()
         })
@@ -869,25 +2589,93 @@ fn game_setup(
         .insert(Player)
         .insert(Attacking(false))
         .insert(MaxSpeed(PLAYER_MAX_SPEED))
-        .insert(SecondaryAction(SecondaryActionType::None))
-        .insert(HealthRegen {
-            timer: Timer::new(Duration::from_nanos(1), TimerMode::Once),
-            amount: 0,
-        })
+        .insert(secondary_action)
+        .insert(base_stats.health_regen.clone())
         .insert(Retaliate(false))
-        .insert(Perks(HashSet::new()))
+        .insert(SwordBeam(false))
+        .insert(LingeringArc(false))
+        .insert(CritChance(base_stats.crit_chance))
+        .insert(base_stats)
+        .insert(Perks(starting_perks))
         .insert(attack_cooldown)
+        .insert(AttackCombo {
+            next_swing: 0,
+            reset_timer: Timer::new(ATTACK_COMBO_WINDOW, TimerMode::Once),
+        })
         .with_children(|parent| {
-            spawn_sword_pivot(parent, &mut meshes, &mut materials, sword_swing_params, 1.0);
+            let sword_color = cosmetics.sword_tint.get_color();
+            spawn_sword_pivot(
+                parent,
+                &mut meshes,
+                &mut materials,
+                sword_swing_params,
+                sword_color,
+                1.0,
+            );
 
             for (params, alpha) in sword_shadow_swing_params {
-                spawn_sword_pivot(parent, &mut meshes, &mut materials, params, alpha);
+                spawn_sword_pivot(
+                    parent,
+                    &mut meshes,
+                    &mut materials,
+                    params,
+                    sword_color,
+                    alpha,
+                );
             }
-        });
 
-    // health display
-    commands
-        .spawn(
+            // proximity warning outline, drawn as a slightly larger circle behind the player
+            parent
+                .spawn(MaterialMesh2dBundle {
+                    mesh: meshes
+                        .add(shape::Circle::new(PLAYER_SIZE + PROXIMITY_WARNING_WIDTH).into())
+                        .into(),
+                    material: materials.add(ColorMaterial::from(Color::NONE)),
+                    transform: Transform::from_translation(Vec3::new(
+                        0.0,
+                        0.0,
+                        PROXIMITY_WARNING_Z,
+                    )),
+                    ..default()
+                })
+                .insert(ProximityWarning);
+        })
+        .id();
+
+    if cosmetics.player_trail == PlayerTrail::On {
+        commands.entity(player_entity).insert(LeavesTrail {
+            timer: Timer::new(PLAYER_TRAIL_INTERVAL, TimerMode::Repeating),
+            material: materials.add(ColorMaterial::from(player_color.with_a(PLAYER_TRAIL_ALPHA))),
+        });
+    }
+
+    // obstacles
+    // enemies just steer straight at the player, so they'll get stuck on these until there's proper pathfinding
+    let obstacle_layout = ObstacleLayout::iter()
+        .choose(&mut rand::thread_rng())
+        .unwrap_or(ObstacleLayout::Pillars);
+    for obstacle in obstacle_layout.get_obstacles() {
+        commands
+            .spawn(SpriteBundle {
+                sprite: Sprite {
+                    custom_size: Some(obstacle.size()),
+                    color: OBSTACLE_COLOR,
+                    ..default()
+                },
+                transform: Transform::from_translation(obstacle.center().extend(OBSTACLE_Z)),
+                ..default()
+            })
+            .insert(GameComponent)
+            .insert(RigidBody::Fixed)
+            .insert(Collider::cuboid(
+                obstacle.half_size().x,
+                obstacle.half_size().y,
+            ));
+    }
+
+    // health display
+    commands
+        .spawn(
             TextBundle::from_section(
                 format!("Health: {STARTING_HEALTH}/{STARTING_HEALTH}"),
                 TextStyle {
@@ -911,23 +2699,166 @@ fn game_setup(
         .insert(GameComponent)
         .insert(HealthText);
 
-    // right sidebar
+    // boss health bar
     commands
         .spawn(NodeBundle {
             style: Style {
-                width: Val::Percent(33.3),
-                height: Val::Percent(100.0),
+                width: Val::Px(BOSS_HEALTH_BAR_WIDTH),
+                height: Val::Px(BOSS_HEALTH_BAR_HEIGHT),
                 position_type: PositionType::Absolute,
-                right: Val::Px(0.0),
-                top: Val::Px(0.0),
+                // just below the health display
+                top: Val::Px(60.0),
                 margin: UiRect {
+                    left: Val::Auto,
+                    right: Val::Auto,
+                    ..default()
+                },
+                ..default()
+            },
+            background_color: BackgroundColor(Color::DARK_GRAY),
+            visibility: Visibility::Hidden,
+            ..default()
+        })
+        .insert(GameComponent)
+        .insert(BossHealthBar)
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Percent(100.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    background_color: BackgroundColor(EnemyType::Boss.get_params().color),
+                    ..default()
+                })
+                .insert(BossHealthBarFill);
+        });
+
+    // damage indicator
+    let mut damage_border_timer = Timer::new(DAMAGE_BORDER_DURATION, TimerMode::Once);
+    damage_border_timer.set_elapsed(DAMAGE_BORDER_DURATION);
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                position_type: PositionType::Absolute,
+                border: UiRect::all(Val::Px(DAMAGE_BORDER_WIDTH)),
+                ..default()
+            },
+            background_color: BackgroundColor(Color::NONE),
+            border_color: BorderColor(Color::NONE),
+            ..default()
+        })
+        .insert(GameComponent)
+        .insert(DamageIndicator {
+            border_timer: damage_border_timer,
+        });
+
+    let (sidebar_left, sidebar_right, sidebar_margin, sidebar_alignment) =
+        match settings.sidebar_side {
+            SidebarSide::Left => (
+                Val::Px(0.0),
+                Val::Auto,
+                UiRect {
+                    left: Val::Px(5.0),
+                    top: Val::Px(5.0),
+                    ..default()
+                },
+                AlignItems::FlexStart,
+            ),
+            SidebarSide::Right => (
+                Val::Auto,
+                Val::Px(0.0),
+                UiRect {
                     right: Val::Px(5.0),
                     top: Val::Px(5.0),
                     ..default()
                 },
+                AlignItems::FlexEnd,
+            ),
+        };
+    let (debug_overlay_left, debug_overlay_right, debug_overlay_alignment) =
+        match settings.sidebar_side {
+            SidebarSide::Left => (Val::Auto, Val::Px(10.0), AlignItems::FlexEnd),
+            SidebarSide::Right => (Val::Px(10.0), Val::Auto, AlignItems::FlexStart),
+        };
+
+    let run_clock = || {
+        TextBundle::from_section(
+            "00:00",
+            TextStyle {
+                font: asset_server.load(MONO_FONT),
+                font_size: 25.0,
+                color: Color::WHITE,
+            },
+        )
+    };
+
+    // run clock, when it's at the top
+    if settings.run_clock == RunClockPosition::Top {
+        commands
+            .spawn(run_clock().with_style(Style {
+                position_type: PositionType::Absolute,
+                // kept on the opposite side from the sidebar, like the debug overlay
+                left: debug_overlay_left,
+                right: debug_overlay_right,
+                top: Val::Px(10.0),
+                ..default()
+            }))
+            .insert(GameComponent)
+            .insert(RunClockText);
+    }
+
+    // debug overlay, with the run clock below it when it's at the bottom so they don't overlap
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                // kept on the opposite side from the sidebar so it doesn't cover the enemy count
+                left: debug_overlay_left,
+                right: debug_overlay_right,
+                bottom: Val::Px(10.0),
+                flex_direction: FlexDirection::Column,
+                align_items: debug_overlay_alignment,
+                ..default()
+            },
+            ..default()
+        })
+        .insert(GameComponent)
+        .with_children(|parent| {
+            parent
+                .spawn(TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: asset_server.load(MONO_FONT),
+                        font_size: 20.0,
+                        color: Color::WHITE,
+                    },
+                ))
+                .insert(Visibility::Hidden)
+                .insert(DebugOverlayText);
+
+            if settings.run_clock == RunClockPosition::Bottom {
+                parent.spawn(run_clock()).insert(RunClockText);
+            }
+        });
+
+    // sidebar
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(33.3),
+                height: Val::Percent(100.0),
+                position_type: PositionType::Absolute,
+                left: sidebar_left,
+                right: sidebar_right,
+                top: Val::Px(0.0),
+                margin: sidebar_margin,
                 flex_direction: FlexDirection::Column,
                 justify_content: JustifyContent::Start,
-                align_items: AlignItems::FlexEnd,
+                align_items: sidebar_alignment,
                 ..default()
             },
             ..default()
@@ -962,14 +2893,39 @@ fn game_setup(
                 .insert(LevelText);
 
             // xp display
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(150.0),
+                        height: Val::Px(15.0),
+                        ..default()
+                    },
+                    background_color: BackgroundColor(Color::DARK_GRAY),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                width: Val::Percent(0.0),
+                                height: Val::Percent(100.0),
+                                ..default()
+                            },
+                            background_color: BackgroundColor(XP_BAR_FILL_COLOR),
+                            ..default()
+                        })
+                        .insert(XpBarFill {
+                            shown_fraction: 0.0,
+                        });
+                });
             parent
                 .spawn(
                     TextBundle::from_section(
                         format!("XP: 0/{STARTING_XP_THRESHOLD}"),
                         TextStyle {
                             font: asset_server.load(MONO_FONT),
-                            font_size: 35.0,
-                            color: Color::WHITE,
+                            font_size: 20.0,
+                            color: Color::GRAY,
                         },
                     )
                     .with_text_alignment(TextAlignment::Center)
@@ -983,6 +2939,63 @@ fn game_setup(
                 )
                 .insert(XpText);
 
+            // bullet time meter
+            parent.spawn(
+                TextBundle::from_section(
+                    "Bullet Time",
+                    TextStyle {
+                        font: asset_server.load(MONO_FONT),
+                        font_size: 20.0,
+                        color: Color::WHITE,
+                    },
+                )
+                .with_text_alignment(TextAlignment::Center),
+            );
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(150.0),
+                        height: Val::Px(10.0),
+                        margin: UiRect {
+                            bottom: Val::Px(5.0),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    background_color: BackgroundColor(Color::DARK_GRAY),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                width: Val::Percent(0.0),
+                                height: Val::Percent(100.0),
+                                ..default()
+                            },
+                            background_color: BackgroundColor(Color::ALICE_BLUE),
+                            ..default()
+                        })
+                        .insert(BulletTimeMeterFill);
+                });
+
+            // wave display
+            if selected_spawn_mode.0 == SpawnMode::Waves {
+                parent
+                    .spawn(
+                        TextBundle::from_section(
+                            "Wave 0 - 0 left",
+                            TextStyle {
+                                font: asset_server.load(MONO_FONT),
+                                font_size: 25.0,
+                                color: Color::WHITE,
+                            },
+                        )
+                        .with_text_alignment(TextAlignment::Center),
+                    )
+                    .insert(WaveText);
+            }
+
             // enemy count display
             parent
                 .spawn(
@@ -1009,14 +3022,116 @@ fn game_setup(
         });
 
     // perk chooser
+    let (perk_chooser_style, perk_chooser_alpha, perk_button_direction, perk_text_scale) =
+        match settings.perk_chooser_layout {
+            PerkChooserLayout::Fullscreen => (
+                Style {
+                    width: Val::Percent(80.0),
+                    height: Val::Percent(80.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    margin: UiRect::all(Val::Auto),
+                    ..default()
+                },
+                0.8,
+                FlexDirection::Column,
+                1.0,
+            ),
+            PerkChooserLayout::Compact => (
+                Style {
+                    width: Val::Percent(100.0),
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(0.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    padding: UiRect::all(Val::Px(5.0)),
+                    ..default()
+                },
+                0.5,
+                FlexDirection::Row,
+                COMPACT_PERK_CHOOSER_TEXT_SCALE,
+            ),
+        };
+    commands
+        .spawn(NodeBundle {
+            style: perk_chooser_style,
+            background_color: BackgroundColor(Color::BLACK.with_a(perk_chooser_alpha)),
+            visibility: Visibility::Hidden,
+            ..default()
+        })
+        .insert(GameComponent)
+        .insert(PerkChooser)
+        .with_children(|parent| {
+            // text
+            parent.spawn(
+                TextBundle::from_section(
+                    "You grow stronger.",
+                    TextStyle {
+                        font: asset_server.load(MAIN_FONT),
+                        font_size: 50.0 * perk_text_scale,
+                        color: Color::WHITE,
+                    },
+                )
+                .with_text_alignment(TextAlignment::Center)
+                .with_style(Style {
+                    margin: UiRect::bottom(Val::Px(5.0)),
+                    ..default()
+                }),
+            );
+
+            // perk buttons
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Percent(100.0),
+                        flex_direction: perk_button_direction,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for index in 0..NUM_PERK_CHOICES {
+                        spawn_perk_chooser_button(
+                            index,
+                            perk_text_scale,
+                            parent,
+                            &theme,
+                            &asset_server,
+                        );
+                    }
+                });
+
+            // prompt
+            parent
+                .spawn(
+                    TextBundle::from_section(
+                        PERK_CHOOSER_PROMPT,
+                        TextStyle {
+                            font: asset_server.load(MAIN_FONT),
+                            font_size: 20.0,
+                            color: PERK_CHOOSER_PROMPT_COLOR,
+                        },
+                    )
+                    .with_text_alignment(TextAlignment::Center)
+                    .with_style(Style {
+                        margin: UiRect::top(Val::Px(10.0)),
+                        ..default()
+                    }),
+                )
+                .insert(PerkChooserPrompt);
+        });
+
+    // pause menu
     commands
         .spawn(NodeBundle {
             style: Style {
-                width: Val::Percent(80.0),
-                height: Val::Percent(80.0),
+                width: Val::Percent(50.0),
                 flex_direction: FlexDirection::Column,
                 justify_content: JustifyContent::Center,
                 align_items: AlignItems::Center,
+                padding: UiRect::all(Val::Px(20.0)),
                 margin: UiRect::all(Val::Auto),
                 ..default()
             },
@@ -1025,28 +3140,83 @@ fn game_setup(
             ..default()
         })
         .insert(GameComponent)
-        .insert(PerkChooser)
+        .insert(PauseMenu)
         .with_children(|parent| {
-            // text
             parent.spawn(
                 TextBundle::from_section(
-                    "You grow stronger.",
+                    "Paused",
                     TextStyle {
                         font: asset_server.load(MAIN_FONT),
                         font_size: 50.0,
                         color: Color::WHITE,
                     },
                 )
+                .with_text_alignment(TextAlignment::Center),
+            );
+
+            parent.spawn(
+                TextBundle::from_section(
+                    format!("Press {:?} to resume", key_bindings.pause),
+                    TextStyle {
+                        font: asset_server.load(MAIN_FONT),
+                        font_size: 20.0,
+                        color: Color::GRAY,
+                    },
+                )
                 .with_text_alignment(TextAlignment::Center)
                 .with_style(Style {
-                    margin: UiRect::bottom(Val::Px(5.0)),
+                    margin: UiRect::bottom(Val::Px(15.0)),
                     ..default()
                 }),
             );
 
-            spawn_perk_chooser_button(0, parent, &asset_server);
-            spawn_perk_chooser_button(1, parent, &asset_server);
-            spawn_perk_chooser_button(2, parent, &asset_server);
+            spawn_pause_menu_button("Resume", ResumeButton, parent, &theme, &asset_server);
+
+            // respec button, for runs that have perks to respec
+            if !mutators.has(Mutator::Purist) {
+                parent
+                    .spawn(ButtonBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Column,
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            padding: UiRect::all(Val::Px(10.0)),
+                            ..default()
+                        },
+                        background_color: theme.normal_button.into(),
+                        ..default()
+                    })
+                    .insert(RespecButton)
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(
+                            "Respec",
+                            TextStyle {
+                                font: asset_server.load(MAIN_FONT),
+                                font_size: 30.0,
+                                color: theme.button_text,
+                            },
+                        ));
+                        parent.spawn(
+                            TextBundle::from_section(
+                                "Give up all your perks and choose them again, at the cost of your progress towards the next level",
+                                TextStyle {
+                                    font: asset_server.load(MAIN_FONT),
+                                    font_size: 20.0,
+                                    color: theme.button_text,
+                                },
+                            )
+                            .with_text_alignment(TextAlignment::Center),
+                        );
+                    });
+            }
+
+            spawn_pause_menu_button(
+                "Quit to menu",
+                QuitToMenuButton,
+                parent,
+                &theme,
+                &asset_server,
+            );
         });
 
     // secondary action cooldown display
@@ -1062,7 +3232,7 @@ fn game_setup(
         .insert(SecondaryActionCooldownDisplay);
 }
 
-#[derive(Component)]
+#[derive(Component, Clone)]
 struct SwordAnimationParams {
     start_delay: Duration,
     start_scale: Vec3,
@@ -1072,15 +3242,38 @@ struct SwordAnimationParams {
     end_rotation: f32,
     start_translation: Vec3,
     end_translation: Vec3,
-    send_swing_complete_event: bool,
+    /// If set, the swing complete event is sent this long after the sword finishes swinging
+    swing_complete_event_delay: Option<Duration>,
     swing_end_delay: Duration,
     put_away_time: Duration,
     send_attack_done_event: bool,
 }
 
+/// Builds the animation parameters for the player's actual sword, as opposed to its shadows.
+/// The main swing is in charge of saying the attack is over, since it's always there even if the shadows aren't.
+fn build_main_sword_animation_params(end_scale: Vec3) -> SwordAnimationParams {
+    let last_shadow_delay =
+        SWORD_SHADOW_DELAYS_AND_ALPHAS[SWORD_SHADOW_DELAYS_AND_ALPHAS.len() - 1].0;
+    SwordAnimationParams {
+        start_delay: Duration::from_nanos(1),
+        start_scale: SWORD_START_SCALE,
+        end_scale,
+        swing_time: SWORD_ANIMATION_TIME,
+        start_rotation: SWORD_START_ROTATION,
+        end_rotation: SWORD_END_ROTATION,
+        start_translation: SWORD_START_TRANSLATION,
+        end_translation: SWORD_END_TRANSLATION,
+        // keep the sword able to hit until the last shadow would have finished swinging, regardless of whether the shadows are shown
+        swing_complete_event_delay: Some(last_shadow_delay),
+        swing_end_delay: SWORD_ANIMATION_END_DELAY,
+        put_away_time: SWORD_PUT_AWAY_TIME,
+        send_attack_done_event: true,
+    }
+}
+
 /// Builds the animation for a sword swing
 fn build_sword_animation(params: &SwordAnimationParams) -> Sequence<Transform> {
-    let mut rotate_tween = Tween::new(
+    let rotate_tween = Tween::new(
         EaseFunction::QuadraticOut,
         params.swing_time,
         TransformRotateZLens {
@@ -1088,9 +3281,6 @@ fn build_sword_animation(params: &SwordAnimationParams) -> Sequence<Transform> {
             end: params.end_rotation,
         },
     );
-    if params.send_swing_complete_event {
-        rotate_tween = rotate_tween.with_completed_event(SWORD_SWING_COMPLETE_EVENT_ID);
-    }
 
     let mut put_away_tween = Tween::new(
         EaseFunction::QuadraticIn,
@@ -1104,7 +3294,7 @@ fn build_sword_animation(params: &SwordAnimationParams) -> Sequence<Transform> {
         put_away_tween = put_away_tween.with_completed_event(ATTACK_DONE_EVENT_ID);
     }
 
-    Delay::new(params.start_delay)
+    let swing = Delay::new(params.start_delay)
         .then(Tween::new(
             EaseMethod::Discrete(0.0),
             Duration::from_nanos(1),
@@ -1123,9 +3313,21 @@ fn build_sword_animation(params: &SwordAnimationParams) -> Sequence<Transform> {
                     end: params.end_translation,
                 },
             ),
-        ]))
-        .then(Delay::new(params.swing_end_delay))
-        .then(put_away_tween)
+        ]));
+
+    let swing = match params.swing_complete_event_delay {
+        Some(swing_complete_event_delay) => swing
+            .then(
+                Delay::new(swing_complete_event_delay)
+                    .with_completed_event(SWORD_SWING_COMPLETE_EVENT_ID),
+            )
+            .then(Delay::new(
+                params.swing_end_delay - swing_complete_event_delay,
+            )),
+        None => swing.then(Delay::new(params.swing_end_delay)),
+    };
+
+    swing.then(put_away_tween)
 }
 
 fn spawn_sword_pivot(
@@ -1133,6 +3335,7 @@ fn spawn_sword_pivot(
     meshes: &mut ResMut<Assets<Mesh>>,
     materials: &mut ResMut<Assets<ColorMaterial>>,
     animation_params: SwordAnimationParams,
+    color: Color,
     alpha: f32,
 ) {
     // pivot
@@ -1147,6 +3350,7 @@ fn spawn_sword_pivot(
             Animator::new(build_sword_animation(&animation_params))
                 .with_state(AnimatorState::Paused),
         )
+        .insert(BaseSwordAnimationParams(animation_params.clone()))
         .insert(animation_params)
         .with_children(|pivot| {
             // sword
@@ -1155,20 +3359,25 @@ fn spawn_sword_pivot(
                     mesh: meshes
                         .add(shape::Quad::new(Vec2::new(SWORD_WIDTH, SWORD_LENGTH)).into())
                         .into(),
-                    material: materials.add(ColorMaterial::from(Color::GRAY.with_a(alpha))),
+                    material: materials.add(ColorMaterial::from(color.with_a(alpha))),
                     transform: Transform::from_translation(Vec3::new(0., SWORD_LENGTH / 2.0, 0.)),
                     ..default()
                 })
                 .insert(Collider::cuboid(SWORD_WIDTH, SWORD_LENGTH / 2.0))
                 .insert(Sensor)
-                .insert(Sword { active: false });
+                .insert(Sword {
+                    active: false,
+                    swing: 0,
+                });
         });
 }
 
 /// Spawns a perk chooser button with the provided index
 fn spawn_perk_chooser_button(
     index: usize,
+    text_scale: f32,
     parent: &mut ChildBuilder,
+    theme: &Theme,
     asset_server: &Res<AssetServer>,
 ) {
     parent
@@ -1193,7 +3402,7 @@ fn spawn_perk_chooser_button(
                         width: Val::Percent(100.0),
                         ..default()
                     },
-                    background_color: NORMAL_BUTTON.into(),
+                    background_color: theme.normal_button.into(),
                     ..default()
                 })
                 .insert(ChoosePerkButton(index))
@@ -1205,24 +3414,24 @@ fn spawn_perk_chooser_button(
                                     "perk name",
                                     TextStyle {
                                         font: asset_server.load(MAIN_FONT),
-                                        font_size: 40.0,
-                                        color: NORMAL_BUTTON_TEXT_COLOR,
+                                        font_size: 40.0 * text_scale,
+                                        color: theme.button_text,
                                     },
                                 ),
                                 TextSection::new(
                                     "\n",
                                     TextStyle {
                                         font: asset_server.load(MAIN_FONT),
-                                        font_size: 30.0,
-                                        color: NORMAL_BUTTON_TEXT_COLOR,
+                                        font_size: 30.0 * text_scale,
+                                        color: theme.button_text,
                                     },
                                 ),
                                 TextSection::new(
                                     "perk description",
                                     TextStyle {
                                         font: asset_server.load(MAIN_FONT),
-                                        font_size: 30.0,
-                                        color: NORMAL_BUTTON_TEXT_COLOR,
+                                        font_size: 30.0 * text_scale,
+                                        color: theme.button_text,
                                     },
                                 ),
                             ])
@@ -1234,12 +3443,19 @@ fn spawn_perk_chooser_button(
 }
 
 /// Handles events for completed tweens
+#[allow(clippy::too_many_arguments)]
 fn tween_completed(
     mut reader: EventReader<TweenCompleted>,
     mut sword_query: Query<&mut Sword>,
     mut player_attacking_query: Query<&mut Attacking, With<Player>>,
     explosions_query: Query<Entity, With<Explosion>>,
     death_animations_query: Query<Entity, With<DeathAnimation>>,
+    announcements_query: Query<Entity, With<Announcement>>,
+    enemy_trails_query: Query<Entity, With<EnemyTrail>>,
+    combo_lost_text_query: Query<Entity, With<ComboLostText>>,
+    player_trail_query: Query<Entity, With<PlayerTrailSegment>>,
+    level_up_burst_query: Query<Entity, With<LevelUpBurst>>,
+    xp_popup_query: Query<Entity, With<XpPopup>>,
     mut entities_to_despawn: ResMut<EntitiesToDespawn>,
 ) {
     for ev in reader.read() {
@@ -1270,71 +3486,290 @@ fn tween_completed(
                 }
             }
         }
-    }
-}
 
-/// Updates attack cooldowns
-fn update_attack_cooldown(mut query: Query<&mut AttackCooldown>, time: Res<Time>) {
-    for mut cooldown in query.iter_mut() {
-        cooldown.0.tick(time.delta());
-    }
-}
+        if ev.user_data == ANNOUNCEMENT_COMPLETE_EVENT_ID {
+            for entity in announcements_query.iter() {
+                if ev.entity == entity {
+                    entities_to_despawn.0.push(entity);
+                }
+            }
+        }
 
-/// Applies impulses to the player based on pressed keys
-fn player_movement(
-    mut player_query: Query<
-        (
-            &mut ExternalForce,
-            &mut Velocity,
-            &mut Transform,
-            &Attacking,
-            &MaxSpeed,
-        ),
-        With<Player>,
-    >,
-    camera_query: Query<(&Camera, &GlobalTransform)>,
-    window_query: Query<&Window>,
-    keycode: Res<Input<KeyCode>>,
+        if ev.user_data == ENEMY_TRAIL_COMPLETE_EVENT_ID {
+            for entity in enemy_trails_query.iter() {
+                if ev.entity == entity {
+                    entities_to_despawn.0.push(entity);
+                }
+            }
+        }
+
+        if ev.user_data == COMBO_LOST_COMPLETE_EVENT_ID {
+            for entity in combo_lost_text_query.iter() {
+                if ev.entity == entity {
+                    entities_to_despawn.0.push(entity);
+                }
+            }
+        }
+
+        if ev.user_data == PLAYER_TRAIL_COMPLETE_EVENT_ID {
+            for entity in player_trail_query.iter() {
+                if ev.entity == entity {
+                    entities_to_despawn.0.push(entity);
+                }
+            }
+        }
+
+        if ev.user_data == LEVEL_UP_BURST_COMPLETE_EVENT_ID {
+            for entity in level_up_burst_query.iter() {
+                if ev.entity == entity {
+                    entities_to_despawn.0.push(entity);
+                }
+            }
+        }
+
+        if ev.user_data == XP_POPUP_COMPLETE_EVENT_ID {
+            for entity in xp_popup_query.iter() {
+                if ev.entity == entity {
+                    entities_to_despawn.0.push(entity);
+                }
+            }
+        }
+    }
+}
+
+/// Puts enemies back on their shared material once their material tweens are done
+fn restore_enemy_materials(
+    mut reader: EventReader<TweenCompleted>,
+    mut enemy_material_query: Query<(&Enemy, &mut Handle<ColorMaterial>)>,
+    shared_asset_handles: Res<SharedAssetHandles>,
+    entities_to_despawn: Res<EntitiesToDespawn>,
+    mut commands: Commands,
 ) {
-    let Some(cursor_world_position) = get_cursor_world_position(&camera_query, &window_query)
-    else {
-        return;
-    };
+    for ev in reader.read() {
+        if entities_to_despawn.0.contains(&ev.entity) {
+            continue;
+        }
+
+        if ev.user_data == ENEMY_SPAWN_COMPLETE_EVENT_ID {
+            if let Ok((enemy, mut material)) = enemy_material_query.get_mut(ev.entity) {
+                // the enemy is fully faded in, so it can go back to the shared material
+                *material = shared_asset_handles.enemies[&enemy.enemy_type]
+                    .material
+                    .clone();
+                commands
+                    .entity(ev.entity)
+                    .remove::<(Animator<Transform>, AssetAnimator<ColorMaterial>)>();
+            }
+        }
+
+        if ev.user_data == ENEMY_HIT_FLASH_COMPLETE_EVENT_ID {
+            if let Ok((enemy, mut material)) = enemy_material_query.get_mut(ev.entity) {
+                // the flash is over, so the enemy can go back to the shared material
+                *material = shared_asset_handles.enemies[&enemy.enemy_type]
+                    .material
+                    .clone();
+                commands
+                    .entity(ev.entity)
+                    .remove::<AssetAnimator<ColorMaterial>>();
+            }
+        }
+    }
+}
+
+/// Updates attack cooldowns
+fn update_attack_cooldown(
+    mut query: Query<&mut AttackCooldown>,
+    mut combo_query: Query<&mut AttackCombo>,
+    time: Res<Time>,
+) {
+    for mut cooldown in query.iter_mut() {
+        cooldown.0.tick(time.delta());
+    }
+
+    for mut combo in combo_query.iter_mut() {
+        combo.reset_timer.tick(time.delta());
+        if combo.reset_timer.just_finished() {
+            combo.next_swing = 0;
+        }
+    }
+}
+
+type MovingPlayerTuple = (
+    &'static mut ExternalForce,
+    &'static mut Velocity,
+    &'static mut Transform,
+    &'static Attacking,
+    &'static MaxSpeed,
+    Option<&'static Slowed>,
+    Option<&'static Dashing>,
+);
+
+/// Applies impulses to the player based on pressed keys
+#[allow(clippy::too_many_arguments)]
+fn player_movement(
+    mut player_query: Query<MovingPlayerTuple, With<Player>>,
+    enemy_query: Query<&Transform, (With<Enemy>, Without<Player>)>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    window_query: Query<&Window>,
+    keycode: Res<Input<KeyCode>>,
+    key_bindings: Res<KeyBindings>,
+    gamepads: Res<Gamepads>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    settings: Res<Settings>,
+    downed: Res<Downed>,
+    time: Res<Time>,
+) {
+    let cursor_world_position = get_cursor_world_position(&camera_query, &window_query);
 
-    for (mut force, mut velocity, mut transform, attacking, max_speed) in &mut player_query {
+    for (mut force, mut velocity, mut transform, attacking, max_speed, slowed, dashing) in
+        &mut player_query
+    {
         // translation
-        if keycode.pressed(MOVE_LEFT_KEY) {
-            force.force.x = -PLAYER_MOVE_FORCE;
-        } else if keycode.pressed(MOVE_RIGHT_KEY) {
-            force.force.x = PLAYER_MOVE_FORCE;
-        } else {
-            force.force.x = 0.0;
+        match settings.movement_scheme {
+            MovementScheme::Keyboard => {
+                if keycode.pressed(key_bindings.move_left) {
+                    force.force.x = -PLAYER_MOVE_FORCE;
+                } else if keycode.pressed(key_bindings.move_right) {
+                    force.force.x = PLAYER_MOVE_FORCE;
+                } else {
+                    force.force.x = 0.0;
+                }
+
+                if keycode.pressed(key_bindings.move_up) {
+                    force.force.y = PLAYER_MOVE_FORCE;
+                } else if keycode.pressed(key_bindings.move_down) {
+                    force.force.y = -PLAYER_MOVE_FORCE;
+                } else {
+                    force.force.y = 0.0;
+                }
+            }
+            MovementScheme::MouseFollow => {
+                let Some(cursor_world_position) = cursor_world_position else {
+                    continue;
+                };
+                let to_cursor = cursor_world_position - transform.translation.xy();
+                // stop once the cursor is reached, rather than jittering back and forth over it
+                force.force = if to_cursor.length() > MOUSE_FOLLOW_STOP_DISTANCE {
+                    to_cursor.clamp_length(PLAYER_MOVE_FORCE, PLAYER_MOVE_FORCE)
+                } else {
+                    Vec2::ZERO
+                };
+            }
         }
 
-        if keycode.pressed(MOVE_UP_KEY) {
-            force.force.y = PLAYER_MOVE_FORCE;
-        } else if keycode.pressed(MOVE_DOWN_KEY) {
-            force.force.y = -PLAYER_MOVE_FORCE;
-        } else {
-            force.force.y = 0.0;
+        // dashing takes over movement until it's done
+        if let Some(dashing) = dashing {
+            force.force = Vec2::ZERO;
+            velocity.linvel = dashing.velocity;
+        }
+
+        // downed players can still swing their sword, but can't go anywhere
+        if downed.active {
+            force.force = Vec2::ZERO;
+            velocity.linvel = Vec2::ZERO;
         }
 
         // don't allow rotation while attacking because rapid spinning can increase the effective size of the sword swing
         if !attacking.0 {
             // rotation
-            if let Some(to_cursor) =
-                (cursor_world_position - transform.translation.xy()).try_normalize()
-            {
-                let rotate_to_cursor = Quat::from_rotation_arc(Vec3::Y, to_cursor.extend(0.));
-                transform.rotation = rotate_to_cursor;
+            if let Some(aim_direction) = get_aim_direction(
+                transform.translation.xy(),
+                cursor_world_position,
+                &gamepads,
+                &gamepad_axes,
+                enemy_query
+                    .iter()
+                    .map(|transform| transform.translation.xy()),
+                &settings,
+            ) {
+                let rotate_to_aim = Quat::from_rotation_arc_2d(Vec2::Y, aim_direction);
+                transform.rotation = if settings.smooth_aim {
+                    rotate_towards(
+                        transform.rotation,
+                        rotate_to_aim,
+                        PLAYER_MAX_TURN_RATE * time.delta_seconds(),
+                    )
+                } else {
+                    rotate_to_aim
+                };
             }
         }
 
         // prevent player from spinning around by itself
         velocity.angvel = 0.0;
 
-        // clamp speed
-        velocity.linvel = velocity.linvel.clamp_length_max(max_speed.0);
+        // clamp speed, unless the player is dashing past their usual max speed
+        if dashing.is_none() {
+            let speed_multiplier = slowed.map_or(1.0, |slowed| slowed.speed_multiplier);
+            velocity.linvel = velocity
+                .linvel
+                .clamp_length_max(max_speed.0 * speed_multiplier);
+        }
+    }
+}
+
+/// Counts down slowdowns on the player and removes them when they wear off
+fn update_slowed(
+    mut commands: Commands,
+    mut player_query: Query<(Entity, &mut Slowed), With<Player>>,
+    time: Res<Time>,
+) {
+    for (entity, mut slowed) in player_query.iter_mut() {
+        slowed.timer.tick(time.delta());
+        if slowed.timer.finished() {
+            commands.entity(entity).remove::<Slowed>();
+        }
+    }
+}
+
+/// Counts down dashes and ends them when they're over
+fn update_dashing(
+    mut commands: Commands,
+    mut player_query: Query<(Entity, &mut Dashing), With<Player>>,
+    time: Res<Time>,
+) {
+    for (entity, mut dashing) in player_query.iter_mut() {
+        dashing.timer.tick(time.delta());
+        if dashing.timer.finished() {
+            commands.entity(entity).remove::<Dashing>();
+        }
+    }
+}
+
+/// Shows a warning around the player when an enemy is about to hit them
+fn update_proximity_warning(
+    player_query: Query<&Transform, With<Player>>,
+    enemy_query: Query<(&Transform, &Enemy)>,
+    warning_query: Query<&Handle<ColorMaterial>, With<ProximityWarning>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    settings: Res<Settings>,
+    time: Res<Time<Real>>,
+) {
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+
+    let enemy_nearby = enemy_query.iter().any(|(enemy_transform, enemy)| {
+        let distance = enemy_transform
+            .translation
+            .xy()
+            .distance(player_transform.translation.xy());
+        distance - enemy.size - PLAYER_SIZE <= PROXIMITY_WARNING_DISTANCE
+    });
+
+    let alpha = if !enemy_nearby {
+        0.0
+    } else if settings.reduce_motion {
+        1.0
+    } else {
+        let phase = time.elapsed_seconds() / PROXIMITY_WARNING_PULSE_PERIOD.as_secs_f32();
+        (1.0 + (phase * 2.0 * PI).cos()) / 2.0
+    };
+
+    for material_handle in warning_query.iter() {
+        if let Some(material) = materials.get_mut(material_handle) {
+            material.color = PROXIMITY_WARNING_COLOR.with_a(alpha);
+        }
     }
 }
 
@@ -1352,35 +3787,86 @@ fn keep_player_in_bounds(mut player_query: Query<&mut Transform, With<Player>>)
     }
 }
 
+type AttackingPlayerTuple = (
+    &'static mut AttackCooldown,
+    &'static mut Attacking,
+    &'static mut Transform,
+    &'static SwordBeam,
+    &'static LingeringArc,
+    &'static mut AttackCombo,
+);
+
+type EnemyNotPlayerTuple = (With<Enemy>, Without<Player>);
+
 /// Makes the player attack
+#[allow(clippy::too_many_arguments)]
 fn player_attack(
     mut commands: Commands,
-    mut player_query: Query<(&mut AttackCooldown, &mut Attacking, &mut Transform), With<Player>>,
-    mut sword_pivot_query: Query<&mut Animator<Transform>, With<SwordPivot>>,
+    mut player_query: Query<AttackingPlayerTuple, With<Player>>,
+    mut sword_pivot_query: Query<
+        (&mut Animator<Transform>, &SwordAnimationParams),
+        With<SwordPivot>,
+    >,
     mut sword_query: Query<&mut Sword>,
+    mut enemy_query: Query<(&Transform, &mut ExternalImpulse), EnemyNotPlayerTuple>,
+    mut slow_mo_timer: ResMut<SlowMoTimer>,
     camera_query: Query<(&Camera, &GlobalTransform)>,
     window_query: Query<&Window>,
+    gamepads: Res<Gamepads>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    settings: Res<Settings>,
     audio_assets: Res<AudioAssets>,
+    volume_settings: Res<VolumeSettings>,
+    shared_asset_handles: Res<SharedAssetHandles>,
+    (mut meshes, mut materials): (ResMut<Assets<Mesh>>, ResMut<Assets<ColorMaterial>>),
 ) {
-    let Some(cursor_world_position) = get_cursor_world_position(&camera_query, &window_query)
-    else {
-        return;
-    };
+    let cursor_world_position = get_cursor_world_position(&camera_query, &window_query);
 
-    for (mut cooldown, mut attacking, mut player_transform) in player_query.iter_mut() {
+    for (mut cooldown, mut attacking, mut player_transform, sword_beam, lingering_arc, mut combo) in
+        player_query.iter_mut()
+    {
         if !cooldown.0.finished() {
             continue;
         }
 
-        for mut animator in sword_pivot_query.iter_mut() {
+        let swing = combo.next_swing;
+        let is_finisher = swing == ATTACK_COMBO_ARC_MULTIPLIERS.len() - 1;
+        combo.next_swing = (swing + 1) % ATTACK_COMBO_ARC_MULTIPLIERS.len();
+        combo.reset_timer.reset();
+
+        // the swing with the latest end determines when the arc it leaves behind starts hurting enemies
+        let mut arc_swing_params: Option<SwordAnimationParams> = None;
+        let mut latest_swing_end = Duration::ZERO;
+        for (mut animator, swing_params) in sword_pivot_query.iter_mut() {
             animator.stop();
 
-            // rotate player to cursor so you can still rotate between rapid attacks
-            if let Some(to_cursor) =
-                (cursor_world_position - player_transform.translation.xy()).try_normalize()
-            {
-                let rotate_to_cursor = Quat::from_rotation_arc(Vec3::Y, to_cursor.extend(0.));
-                player_transform.rotation = rotate_to_cursor;
+            // each swing in the combo gets its own variation on the normal swing
+            let mut combo_swing_params = swing_params.clone();
+            combo_swing_params.start_rotation *= ATTACK_COMBO_ARC_MULTIPLIERS[swing];
+            combo_swing_params.end_rotation *= ATTACK_COMBO_ARC_MULTIPLIERS[swing];
+            combo_swing_params.swing_time = combo_swing_params
+                .swing_time
+                .mul_f32(ATTACK_COMBO_SWING_TIME_MULTIPLIERS[swing]);
+            animator.set_tweenable(build_sword_animation(&combo_swing_params));
+            let swing_end = combo_swing_params.start_delay + combo_swing_params.swing_time;
+            if swing_end >= latest_swing_end {
+                latest_swing_end = swing_end;
+                arc_swing_params = Some(combo_swing_params.clone());
+            }
+
+            // rotate player to aim direction so you can still rotate between rapid attacks
+            if let Some(aim_direction) = get_aim_direction(
+                player_transform.translation.xy(),
+                cursor_world_position,
+                &gamepads,
+                &gamepad_axes,
+                enemy_query
+                    .iter()
+                    .map(|(transform, _)| transform.translation.xy()),
+                &settings,
+            ) {
+                let rotate_to_aim = Quat::from_rotation_arc(Vec3::Y, aim_direction.extend(0.));
+                player_transform.rotation = rotate_to_aim;
             }
 
             attacking.0 = true;
@@ -1388,17 +3874,209 @@ fn player_attack(
             animator.state = AnimatorState::Playing;
         }
 
+        let mut swing_number = 0;
         for mut sword in sword_query.iter_mut() {
             sword.active = true;
+            sword.swing += 1;
+            swing_number = sword.swing;
+        }
+
+        if sword_beam.0 {
+            spawn_sword_beam(&player_transform, &mut commands, &shared_asset_handles);
+        }
+
+        if let Some(arc_swing_params) = arc_swing_params.filter(|_| lingering_arc.0) {
+            spawn_sword_arc(
+                &player_transform,
+                &arc_swing_params,
+                swing_number,
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+            );
+        }
+
+        if is_finisher {
+            // push nearby enemies away
+            for (enemy_transform, mut impulse) in enemy_query.iter_mut() {
+                let player_to_enemy = enemy_transform.translation - player_transform.translation;
+                if player_to_enemy.length() <= FINISHER_KNOCKBACK_RADIUS {
+                    // an enemy right on top of the player has no direction to be pushed in, so it just stays put
+                    impulse.impulse =
+                        player_to_enemy.truncate().normalize_or_zero() * FINISHER_KNOCKBACK_IMPULSE;
+                }
+            }
+
+            slow_mo_timer.start(FINISHER_SLOW_MO_TIME_SCALE, FINISHER_SLOW_MO_TIME);
         }
 
         cooldown.0.reset();
 
-        play_sound(audio_assets.swing.clone(), SWING_VOLUME, &mut commands);
+        play_varied_sound(
+            audio_assets.swing.clone(),
+            SWING_VOLUME,
+            SWING_SPEED_VARIATION,
+            &volume_settings,
+            &mut commands,
+        );
+    }
+}
+
+/// Fires a sword beam from the provided player position in the direction the player is facing
+fn spawn_sword_beam(
+    player_transform: &Transform,
+    commands: &mut Commands,
+    shared_asset_handles: &SharedAssetHandles,
+) {
+    let direction = (player_transform.rotation * Vec3::Y).xy();
+    let start = player_transform.translation.xy() + direction * SWORD_LENGTH;
+
+    commands
+        .spawn(MaterialMesh2dBundle {
+            mesh: shared_asset_handles.sword_beam_mesh.clone().into(),
+            material: shared_asset_handles.sword_beam_material.clone(),
+            transform: Transform::from_translation(start.extend(SWORD_Z))
+                .with_rotation(player_transform.rotation),
+            ..default()
+        })
+        .insert(GameComponent)
+        .insert(Collider::cuboid(
+            SWORD_BEAM_WIDTH / 2.0,
+            SWORD_BEAM_THICKNESS / 2.0,
+        ))
+        .insert(Sensor)
+        .insert(SwordBeamProjectile {
+            velocity: direction * SWORD_BEAM_SPEED,
+            lifetime: Timer::new(SWORD_BEAM_LIFETIME, TimerMode::Once),
+        });
+}
+
+/// Leaves behind an arc covering the area the provided sword swing will pass through.
+/// It doesn't hurt anything until the swing is done, so it only catches enemies that move in after the sword has passed.
+fn spawn_sword_arc(
+    player_transform: &Transform,
+    swing_params: &SwordAnimationParams,
+    swing: u64,
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+) {
+    let radius = SWORD_LENGTH * swing_params.end_scale.y;
+    let edge_points = (0..=LINGERING_ARC_SEGMENTS)
+        .map(|i| {
+            let fraction = i as f32 / LINGERING_ARC_SEGMENTS as f32;
+            let angle = swing_params.start_rotation
+                + (swing_params.end_rotation - swing_params.start_rotation) * fraction;
+            Vec2::from_angle(angle).rotate(Vec2::Y) * radius
+        })
+        .collect::<Vec<Vec2>>();
+
+    let collider = Collider::compound(
+        edge_points
+            .windows(2)
+            .map(|points| {
+                (
+                    Vec2::ZERO,
+                    0.0,
+                    Collider::triangle(Vec2::ZERO, points[0], points[1]),
+                )
+            })
+            .collect(),
+    );
+
+    let activation_delay = swing_params.start_delay + swing_params.swing_time;
+    let fade_animation = Delay::new(activation_delay).then(Tween::new(
+        EaseFunction::QuadraticIn,
+        LINGERING_ARC_DURATION,
+        ColorMaterialColorLens {
+            start: LINGERING_ARC_COLOR,
+            end: LINGERING_ARC_COLOR.with_a(0.0),
+        },
+    ));
+
+    // the arc starts where the sword pivots from
+    let pivot_offset = player_transform.rotation * Vec3::new(0.0, SWORD_START_TRANSLATION.y, 0.0);
+    let translation = (player_transform.translation + pivot_offset)
+        .xy()
+        .extend(LINGERING_ARC_Z);
+
+    commands
+        .spawn(MaterialMesh2dBundle {
+            mesh: meshes.add(build_fan_mesh(&edge_points)).into(),
+            // each arc needs its own material, since it gets faded out
+            material: materials.add(ColorMaterial::from(LINGERING_ARC_COLOR.with_a(0.0))),
+            transform: Transform::from_translation(translation)
+                .with_rotation(player_transform.rotation),
+            ..default()
+        })
+        .insert(GameComponent)
+        .insert(SwordArc {
+            swing,
+            activation_timer: Timer::new(activation_delay, TimerMode::Once),
+            lifetime: Timer::new(activation_delay + LINGERING_ARC_DURATION, TimerMode::Once),
+            collider: Some(collider),
+        })
+        .insert(AssetAnimator::new(fade_animation));
+}
+
+/// Builds a flat mesh made of triangles fanning out from the origin to each pair of neighboring edge points
+fn build_fan_mesh(edge_points: &[Vec2]) -> Mesh {
+    let positions = std::iter::once(Vec2::ZERO)
+        .chain(edge_points.iter().copied())
+        .map(|point| [point.x, point.y, 0.0])
+        .collect::<Vec<[f32; 3]>>();
+    let num_vertices = positions.len();
+    let indices = (1..num_vertices as u32 - 1)
+        .flat_map(|i| [0, i, i + 1])
+        .collect::<Vec<u32>>();
+
+    Mesh::new(PrimitiveTopology::TriangleList)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0, 0.0, 1.0]; num_vertices])
+        .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0.0, 0.0]; num_vertices])
+        .with_indices(Some(Indices::U32(indices)))
+}
+
+/// Turns sword arcs on once their swing is done, and gets rid of them once they've lingered long enough
+fn update_sword_arcs(
+    mut commands: Commands,
+    mut sword_arc_query: Query<(Entity, &mut SwordArc)>,
+    mut entities_to_despawn: ResMut<EntitiesToDespawn>,
+    time: Res<Time>,
+) {
+    for (entity, mut sword_arc) in sword_arc_query.iter_mut() {
+        sword_arc.activation_timer.tick(time.delta());
+        if sword_arc.activation_timer.finished() {
+            if let Some(collider) = sword_arc.collider.take() {
+                commands.entity(entity).insert(collider).insert(Sensor);
+            }
+        }
+
+        sword_arc.lifetime.tick(time.delta());
+        if sword_arc.lifetime.just_finished() && !entities_to_despawn.0.contains(&entity) {
+            entities_to_despawn.0.push(entity);
+        }
+    }
+}
+
+/// Moves sword beams along and gets rid of ones that have traveled far enough
+fn move_sword_beams(
+    mut sword_beam_query: Query<(Entity, &mut Transform, &mut SwordBeamProjectile)>,
+    mut entities_to_despawn: ResMut<EntitiesToDespawn>,
+    time: Res<Time>,
+) {
+    for (entity, mut transform, mut sword_beam) in sword_beam_query.iter_mut() {
+        transform.translation += (sword_beam.velocity * time.delta_seconds()).extend(0.0);
+
+        sword_beam.lifetime.tick(time.delta());
+        if sword_beam.lifetime.just_finished() && !entities_to_despawn.0.contains(&entity) {
+            entities_to_despawn.0.push(entity);
+        }
     }
 }
 
 /// Updates secondary action cooldowns
+#[allow(clippy::too_many_arguments)]
 fn update_secondary_action_cooldown(
     mut query: Query<&mut SecondaryAction>,
     time: Res<Time>,
@@ -1407,12 +4085,15 @@ fn update_secondary_action_cooldown(
     window_query: Query<&Window>,
     mut commands: Commands,
     audio_assets: Res<AudioAssets>,
+    volume_settings: Res<VolumeSettings>,
 ) {
     for mut secondary_action in query.iter_mut() {
         let timer = match &mut secondary_action.0 {
             SecondaryActionType::None => continue,
             SecondaryActionType::Grenade { cooldown_timer, .. } => cooldown_timer,
             SecondaryActionType::Teleport { cooldown_timer, .. } => cooldown_timer,
+            SecondaryActionType::SlowField { cooldown_timer, .. } => cooldown_timer,
+            SecondaryActionType::Dash { cooldown_timer, .. } => cooldown_timer,
         };
         timer.tick(time.delta());
 
@@ -1420,6 +4101,7 @@ fn update_secondary_action_cooldown(
             play_sound(
                 audio_assets.secondary_action_ready.clone(),
                 SECONDARY_ACTION_READY_VOLUME,
+                &volume_settings,
                 &mut commands,
             );
         }
@@ -1441,17 +4123,65 @@ fn update_secondary_action_cooldown(
     }
 }
 
+/// Run condition for the player using their secondary action.
+/// If they're holding the button to aim, it happens when they let go, otherwise it happens as long as the button is held.
+fn secondary_action_triggered(
+    settings: Res<Settings>,
+    key_bindings: Res<KeyBindings>,
+    keyboard_input: Res<Input<KeyCode>>,
+) -> bool {
+    if settings.hold_to_aim {
+        keyboard_input.just_released(key_bindings.secondary_action)
+    } else {
+        keyboard_input.pressed(key_bindings.secondary_action)
+    }
+}
+
+/// Run condition for the player holding the attack button
+fn attack_input_pressed(
+    key_bindings: Res<KeyBindings>,
+    mouse_input: Res<Input<MouseButton>>,
+) -> bool {
+    mouse_input.pressed(key_bindings.attack)
+}
+
+/// Run condition for the player pressing the pause button
+fn pause_input_just_pressed(
+    key_bindings: Res<KeyBindings>,
+    keyboard_input: Res<Input<KeyCode>>,
+) -> bool {
+    keyboard_input.just_pressed(key_bindings.pause)
+}
+
+/// Keeps track of whether the player is holding down the secondary action button to aim it
+fn update_secondary_action_aim(
+    mut aiming: ResMut<AimingSecondaryAction>,
+    player_query: Query<&SecondaryAction, With<Player>>,
+    keyboard_input: Res<Input<KeyCode>>,
+    key_bindings: Res<KeyBindings>,
+    settings: Res<Settings>,
+) {
+    // there's nothing to aim if the action can't be used yet
+    aiming.0 = settings.hold_to_aim
+        && keyboard_input.pressed(key_bindings.secondary_action)
+        && player_query
+            .iter()
+            .any(|secondary_action| secondary_action.0.is_ready());
+}
+
 /// Makes the player do their secondary action
+#[allow(clippy::too_many_arguments)]
 fn player_secondary_action(
     mut commands: Commands,
-    mut player_query: Query<(&mut SecondaryAction, &mut Transform), With<Player>>,
+    mut player_query: Query<(Entity, &mut SecondaryAction, &mut Transform), With<Player>>,
     camera_query: Query<(&Camera, &GlobalTransform)>,
     window_query: Query<&Window>,
-    mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
+    shared_asset_handles: Res<SharedAssetHandles>,
     audio_assets: Res<AudioAssets>,
+    volume_settings: Res<VolumeSettings>,
 ) {
-    for (mut secondary_action, mut transform) in player_query.iter_mut() {
+    for (player_entity, mut secondary_action, mut transform) in player_query.iter_mut() {
         match &mut secondary_action.0 {
             SecondaryActionType::None => continue,
             SecondaryActionType::Grenade {
@@ -1488,9 +4218,53 @@ fn player_secondary_action(
                     *explodes,
                     *explosion_radius,
                     cursor_world_position,
-                    &mut meshes,
                     &mut materials,
+                    &shared_asset_handles,
                     &audio_assets,
+                    &volume_settings,
+                );
+                cooldown_timer.reset();
+            }
+            SecondaryActionType::SlowField {
+                ref mut cooldown_timer,
+                radius,
+                duration,
+            } => {
+                if !cooldown_timer.finished() {
+                    continue;
+                }
+                let Some(cursor_world_position) =
+                    get_cursor_world_position(&camera_query, &window_query)
+                else {
+                    return;
+                };
+                spawn_slow_field(
+                    cursor_world_position,
+                    *radius,
+                    *duration,
+                    &mut commands,
+                    &shared_asset_handles,
+                );
+                cooldown_timer.reset();
+            }
+            SecondaryActionType::Dash {
+                ref mut cooldown_timer,
+                distance,
+            } => {
+                if !cooldown_timer.finished() {
+                    continue;
+                }
+                // dash the way the player is facing
+                let direction = (transform.rotation * Vec3::Y).truncate();
+                commands.entity(player_entity).insert(Dashing {
+                    timer: Timer::new(DASH_DURATION, TimerMode::Once),
+                    velocity: direction * (*distance / DASH_DURATION.as_secs_f32()),
+                });
+                play_sound(
+                    audio_assets.teleport.clone(),
+                    DASH_VOLUME,
+                    &volume_settings,
+                    &mut commands,
                 );
                 cooldown_timer.reset();
             }
@@ -1498,28 +4272,107 @@ fn player_secondary_action(
     }
 }
 
-/// Teleports the provided transform to the provided position
-fn teleport(
+/// Creates a field that slows down enemies at the provided position
+fn spawn_slow_field(
+    location: Vec2,
+    radius: f32,
+    duration: Duration,
     commands: &mut Commands,
-    transform: &mut Transform,
-    explodes: bool,
-    explosion_radius: f32,
-    target_position: Vec2,
-    meshes: &mut Assets<Mesh>,
-    materials: &mut Assets<ColorMaterial>,
-    audio_assets: &AudioAssets,
+    shared_asset_handles: &SharedAssetHandles,
 ) {
-    transform.translation = target_position.extend(transform.translation.z);
-    play_sound(audio_assets.teleport.clone(), TELEPORT_VOLUME, commands);
-
-    if explodes {
-        spawn_explosion(
+    let scale = radius / SLOW_FIELD_RADIUS;
+    commands
+        .spawn(MaterialMesh2dBundle {
+            mesh: shared_asset_handles.slow_field_mesh.clone().into(),
+            material: shared_asset_handles.slow_field_material.clone(),
+            transform: Transform::from_translation(location.extend(SLOW_FIELD_Z))
+                .with_scale(Vec3::new(scale, scale, 1.0)),
+            ..default()
+        })
+        .insert(GameComponent)
+        .insert(Collider::ball(SLOW_FIELD_RADIUS))
+        .insert(Sensor)
+        .insert(SlowField(Timer::new(duration, TimerMode::Once)));
+}
+
+/// Keeps track of which slow fields enemies are inside of
+fn slow_field_collisions(
+    mut collision_events: EventReader<CollisionEvent>,
+    mut overlaps_query: Query<&mut SlowFieldOverlaps>,
+    slow_field_query: Query<&SlowField>,
+) {
+    for event in collision_events.read() {
+        let (a, b, entered) = match event {
+            CollisionEvent::Started(a, b, _) => (*a, *b, true),
+            CollisionEvent::Stopped(a, b, _) => (*a, *b, false),
+        };
+
+        let Some((_, slow_field_entity)) =
+            get_from_either::<SlowField, &SlowField>(a, b, &slow_field_query)
+        else {
+            continue;
+        };
+        let enemy_entity = if slow_field_entity == a { b } else { a };
+
+        if let Ok(mut overlaps) = overlaps_query.get_mut(enemy_entity) {
+            if entered {
+                overlaps.0.insert(slow_field_entity);
+            } else {
+                overlaps.0.remove(&slow_field_entity);
+            }
+        }
+    }
+}
+
+/// Gets rid of slow fields that have run out
+fn update_slow_fields(
+    mut slow_field_query: Query<(Entity, &mut SlowField)>,
+    mut overlaps_query: Query<&mut SlowFieldOverlaps>,
+    mut entities_to_despawn: ResMut<EntitiesToDespawn>,
+    time: Res<Time>,
+) {
+    for (entity, mut slow_field) in slow_field_query.iter_mut() {
+        slow_field.0.tick(time.delta());
+        if slow_field.0.just_finished() {
+            entities_to_despawn.0.push(entity);
+            // don't rely on the collision ending to let enemies go, since the field is about to disappear
+            for mut overlaps in overlaps_query.iter_mut() {
+                overlaps.0.remove(&entity);
+            }
+        }
+    }
+}
+
+/// Teleports the provided transform to the provided position
+#[allow(clippy::too_many_arguments)]
+fn teleport(
+    commands: &mut Commands,
+    transform: &mut Transform,
+    explodes: bool,
+    explosion_radius: f32,
+    target_position: Vec2,
+    materials: &mut Assets<ColorMaterial>,
+    shared_asset_handles: &SharedAssetHandles,
+    audio_assets: &AudioAssets,
+    volume_settings: &VolumeSettings,
+) {
+    transform.translation = target_position.extend(transform.translation.z);
+    play_sound(
+        audio_assets.teleport.clone(),
+        TELEPORT_VOLUME,
+        volume_settings,
+        commands,
+    );
+
+    if explodes {
+        spawn_explosion(
             target_position,
             explosion_radius,
             commands,
-            meshes,
             materials,
+            shared_asset_handles,
             audio_assets,
+            volume_settings,
         );
     }
 }
@@ -1529,20 +4382,11 @@ fn spawn_explosion(
     location: Vec2,
     radius: f32,
     commands: &mut Commands,
-    meshes: &mut Assets<Mesh>,
     materials: &mut Assets<ColorMaterial>,
+    shared_asset_handles: &SharedAssetHandles,
     audio_assets: &AudioAssets,
+    volume_settings: &VolumeSettings,
 ) {
-    let end_scale = radius / EXPLOSION_START_RADIUS;
-    let scale_animation = Tween::new(
-        EaseFunction::QuadraticOut,
-        EXPLOSION_DURATION,
-        TransformScaleLens {
-            start: Vec3::ONE,
-            end: Vec3::new(end_scale, end_scale, 1.0),
-        },
-    );
-
     let fade_animation = Delay::new(EXPLOSION_DURATION).then(
         Tween::new(
             EaseFunction::QuadraticIn,
@@ -1557,9 +4401,8 @@ fn spawn_explosion(
 
     commands
         .spawn(MaterialMesh2dBundle {
-            mesh: meshes
-                .add(shape::Circle::new(EXPLOSION_START_RADIUS).into())
-                .into(),
+            mesh: shared_asset_handles.explosion_mesh.clone().into(),
+            // each explosion needs its own material, since it gets faded out
             material: materials.add(ColorMaterial::from(EXPLOSION_COLOR)),
             transform: Transform::from_translation(location.extend(EXPLOSION_Z)),
             ..default()
@@ -1568,10 +4411,82 @@ fn spawn_explosion(
         .insert(Collider::ball(EXPLOSION_START_RADIUS))
         .insert(Sensor)
         .insert(Explosion)
-        .insert(Animator::new(scale_animation))
+        .insert(Animator::new(build_explosion_scale_animation(radius)))
         .insert(AssetAnimator::new(fade_animation));
 
-    play_sound(audio_assets.explosion.clone(), EXPLOSION_VOLUME, commands);
+    play_sound(
+        audio_assets.explosion.clone(),
+        EXPLOSION_VOLUME,
+        volume_settings,
+        commands,
+    );
+}
+
+/// Builds the animation that grows an explosion out to the provided radius.
+/// The explosion's collider is scaled along with it, so it always covers what's shown.
+fn build_explosion_scale_animation(radius: f32) -> Tween<Transform> {
+    let end_scale = radius / EXPLOSION_START_RADIUS;
+    Tween::new(
+        EaseFunction::QuadraticOut,
+        EXPLOSION_DURATION,
+        TransformScaleLens {
+            start: Vec3::ONE,
+            end: Vec3::new(end_scale, end_scale, 1.0),
+        },
+    )
+}
+
+/// Zooms the camera in or out to keep most enemies in view
+fn auto_zoom(
+    mut zoom: ResMut<ZoomLevel>,
+    player_query: Query<&Transform, With<Player>>,
+    enemy_query: Query<&Transform, With<Enemy>>,
+    time: Res<Time>,
+) {
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+
+    // figure out how zoomed out the camera needs to be to see each enemy, since the camera is centered on the player
+    let mut required_zooms = enemy_query
+        .iter()
+        .map(|enemy_transform| {
+            let offset = (enemy_transform.translation - player_transform.translation).abs();
+            (offset.x / (WINDOW_WIDTH / 2.0)).max(offset.y / (WINDOW_HEIGHT / 2.0))
+        })
+        .collect::<Vec<f32>>();
+    required_zooms.sort_by(f32::total_cmp);
+
+    let target_zoom = if required_zooms.is_empty() {
+        STARTING_ZOOM_LEVEL
+    } else {
+        let index = ((required_zooms.len() as f32 * AUTO_ZOOM_ENEMY_FRACTION).ceil() as usize)
+            .clamp(1, required_zooms.len())
+            - 1;
+        (required_zooms[index] * AUTO_ZOOM_MARGIN).clamp(STARTING_ZOOM_LEVEL, MAX_ZOOM_LEVEL)
+    };
+
+    let lerp_amount = (AUTO_ZOOM_SPEED * time.delta_seconds()).min(1.0);
+    zoom.0 += (target_zoom - zoom.0) * lerp_amount;
+}
+
+/// Eases the zoom towards where leveling up has set it, so the view doesn't pop out all at once.
+/// Real time is used so the zoom keeps easing while the game is stopped for choosing a perk.
+fn ease_zoom_to_target(
+    mut zoom: ResMut<ZoomLevel>,
+    target_zoom: Res<TargetZoomLevel>,
+    time: Res<Time<Real>>,
+) {
+    if zoom.0 == target_zoom.0 {
+        return;
+    }
+
+    let catch_up = 1.0 - (-LEVEL_UP_ZOOM_RATE * time.delta_seconds()).exp();
+    zoom.0 += (target_zoom.0 - zoom.0) * catch_up;
+    // stop once it's close enough, so the zoom doesn't keep counting as changed forever
+    if (target_zoom.0 - zoom.0).abs() < 0.0001 {
+        zoom.0 = target_zoom.0;
+    }
 }
 
 /// Moves the camera to follow the player
@@ -1619,6 +4534,7 @@ fn move_camera(
 }
 
 /// Handles spawning enemies
+#[allow(clippy::too_many_arguments)]
 fn spawn_enemies(
     commands: Commands,
     mut spawn_timer: ResMut<SpawnTimer>,
@@ -1628,10 +4544,45 @@ fn spawn_enemies(
     time: Res<Time>,
     meshes: ResMut<Assets<Mesh>>,
     materials: ResMut<Assets<ColorMaterial>>,
+    shared_asset_handles: Res<SharedAssetHandles>,
+    settings: Res<Settings>,
+    mutators: Res<ActiveMutators>,
+    selected_difficulty: Res<SelectedDifficulty>,
+    dynamic_difficulty: Res<DynamicDifficulty>,
+    spawn_storm: Res<SpawnStorm>,
+    spawn_budget: Res<SpawnBudget>,
+    player_query: Query<(&Transform, &Velocity), With<Player>>,
 ) {
-    spawn_timer.0.tick(time.delta());
+    spawn_timer.0.tick(time.delta().mul_f32(
+        dynamic_difficulty.spawn_rate_multiplier
+            * spawn_storm.get_spawn_rate_multiplier()
+            * spawn_budget.spawn_rate_multiplier,
+    ));
     if spawn_timer.0.just_finished() {
-        spawn_random_enemy(commands, spawn_areas, spawn_weights, meshes, materials);
+        // avoid both where the player is and where they're about to be
+        let avoid_positions = player_query
+            .iter()
+            .flat_map(|(transform, velocity)| {
+                let position = transform.translation.xy();
+                [
+                    position,
+                    position + (velocity.linvel * SPAWN_AVOIDANCE_PREDICTION_TIME),
+                ]
+            })
+            .collect::<Vec<Vec2>>();
+
+        spawn_random_enemy(
+            commands,
+            &avoid_positions,
+            spawn_areas,
+            spawn_weights,
+            meshes,
+            materials,
+            &shared_asset_handles,
+            &settings,
+            &mutators,
+            selected_difficulty.0,
+        );
     }
 
     spawn_interval_change_timer.0.tick(time.delta());
@@ -1646,47 +4597,366 @@ fn spawn_enemies(
     }
 }
 
-/// Spawns a random enemy at a random location
+/// Spawns a random enemy at a random location, preferring locations that aren't near any of the provided positions
+#[allow(clippy::too_many_arguments)]
 fn spawn_random_enemy(
-    commands: Commands,
+    mut commands: Commands,
+    avoid_positions: &[Vec2],
     spawn_areas: Res<SpawnAreas>,
     spawn_weights: Res<SpawnWeights>,
-    meshes: ResMut<Assets<Mesh>>,
-    materials: ResMut<Assets<ColorMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    shared_asset_handles: &SharedAssetHandles,
+    settings: &Settings,
+    mutators: &ActiveMutators,
+    difficulty: Difficulty,
+) {
+    if let Some(spawn_point) = choose_spawn_point(avoid_positions, &spawn_areas) {
+        spawn_enemy_of_type(
+            spawn_weights.choose_random_enemy_type(),
+            spawn_point,
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            shared_asset_handles,
+            settings,
+            mutators,
+            difficulty,
+        );
+    }
+}
+
+/// Handles spawning enemies in waves, sending in a bigger wave once the current one has been mostly cleared out
+#[allow(clippy::too_many_arguments)]
+fn spawn_waves(
+    mut commands: Commands,
+    mut wave: ResMut<Wave>,
+    enemy_query: Query<(Entity, &Enemy)>,
+    entities_to_despawn: Res<EntitiesToDespawn>,
+    spawn_areas: Res<SpawnAreas>,
+    spawn_weights: Res<SpawnWeights>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    shared_asset_handles: Res<SharedAssetHandles>,
+    settings: Res<Settings>,
+    mutators: Res<ActiveMutators>,
+    selected_difficulty: Res<SelectedDifficulty>,
+    player_query: Query<&Transform, With<Player>>,
 ) {
+    if enemy_query.iter().count() > NEXT_WAVE_MAX_ENEMY_COUNT {
+        return;
+    }
+
+    // whatever's left of the last wave has to be cleared out along with the new one
+    let leftover_enemies = enemy_query
+        .iter()
+        .filter(|(entity, enemy)| {
+            !enemy.enemy_type.is_boss() && !entities_to_despawn.0.contains(entity)
+        })
+        .count() as u64;
+    wave.number += 1;
+    wave.size = leftover_enemies;
+    wave.killed = 0;
+
+    let avoid_positions = player_query
+        .iter()
+        .map(|transform| transform.translation.xy())
+        .collect::<Vec<Vec2>>();
+    for _ in 0..get_wave_size(wave.number, selected_difficulty.0) {
+        if let Some(spawn_point) = choose_spawn_point(&avoid_positions, &spawn_areas) {
+            wave.size += 1;
+            spawn_enemy_of_type(
+                spawn_weights.choose_random_enemy_type(),
+                spawn_point,
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                &shared_asset_handles,
+                &settings,
+                &mutators,
+                selected_difficulty.0,
+            );
+        }
+    }
+}
+
+/// Gets the number of enemies in the provided wave. Harder difficulties have bigger waves, just like they spawn enemies faster.
+fn get_wave_size(wave: u64, difficulty: Difficulty) -> u64 {
+    let base_size = FIRST_WAVE_SIZE + (wave.saturating_sub(1) * WAVE_SIZE_INCREASE);
+    let size = (base_size as f32 / difficulty.get_spawn_interval_multiplier()).round() as u64;
+    size.min(MAX_WAVE_SIZE)
+}
+
+/// Picks a random point in one of the spawn areas, preferring points that aren't near any of the provided positions
+fn choose_spawn_point(avoid_positions: &[Vec2], spawn_areas: &SpawnAreas) -> Option<Vec2> {
     let mut rng = rand::thread_rng();
-    if let Some(spawn_area) = spawn_areas.0.choose(&mut rng) {
-        let x_coord = rng.gen_range(spawn_area.min.x..=spawn_area.max.x);
-        let y_coord = rng.gen_range(spawn_area.min.y..=spawn_area.max.y);
+    let mut spawn_point = None;
+    for _ in 0..MAX_SPAWN_POINT_ATTEMPTS {
+        let Some(spawn_area) = spawn_areas.0.choose(&mut rng) else {
+            break;
+        };
+        let point = Vec2::new(
+            rng.gen_range(spawn_area.min.x..=spawn_area.max.x),
+            rng.gen_range(spawn_area.min.y..=spawn_area.max.y),
+        );
+        spawn_point = Some(point);
 
-        spawn_enemy(
-            commands,
-            Vec3::new(x_coord, y_coord, 0.0),
-            spawn_weights.choose_random_enemy_type().get_params(),
-            meshes,
-            materials,
+        let too_close = avoid_positions
+            .iter()
+            .any(|position| position.distance(point) < SPAWN_AVOIDANCE_RADIUS);
+        if !too_close {
+            break;
+        }
+    }
+
+    spawn_point
+}
+
+/// Spawns an enemy of the provided type at the provided point, adjusted for the current settings, mutators, and difficulty
+#[allow(clippy::too_many_arguments)]
+fn spawn_enemy_of_type(
+    enemy_type: EnemyType,
+    spawn_point: Vec2,
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+    shared_asset_handles: &SharedAssetHandles,
+    settings: &Settings,
+    mutators: &ActiveMutators,
+    difficulty: Difficulty,
+) {
+    let mut params = enemy_type.get_params();
+    params.scale_size(settings.enemy_size.get_multiplier());
+    if mutators.has(Mutator::Frenzy) {
+        params.xp_reward *= FRENZY_XP_MULTIPLIER;
+    }
+    params.xp_reward *= difficulty.get_xp_multiplier();
+
+    let color = params.color;
+    let enemy_entity = spawn_enemy(
+        commands,
+        enemy_type,
+        spawn_point.extend(0.0),
+        params,
+        meshes,
+        &shared_asset_handles.enemies[&enemy_type],
+        settings,
+    );
+    animate_enemy_spawn(enemy_entity, color, commands, materials, settings);
+}
+
+/// Spawns a boss whenever the player reaches a level that calls for one
+#[allow(clippy::too_many_arguments)]
+fn spawn_boss_on_level_up(
+    mut commands: Commands,
+    mut level_up_events: EventReader<LevelUp>,
+    spawn_areas: Res<SpawnAreas>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    shared_asset_handles: Res<SharedAssetHandles>,
+    settings: Res<Settings>,
+    mutators: Res<ActiveMutators>,
+    selected_difficulty: Res<SelectedDifficulty>,
+    mut announcement_queue: ResMut<AnnouncementQueue>,
+    player_query: Query<&Transform, With<Player>>,
+) {
+    for level_up in level_up_events.read() {
+        if level_up.new_level % BOSS_LEVEL_INTERVAL != 0 {
+            continue;
+        }
+
+        let avoid_positions = player_query
+            .iter()
+            .map(|transform| transform.translation.xy())
+            .collect::<Vec<Vec2>>();
+        if let Some(spawn_point) = choose_spawn_point(&avoid_positions, &spawn_areas) {
+            spawn_enemy_of_type(
+                EnemyType::Boss,
+                spawn_point,
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                &shared_asset_handles,
+                &settings,
+                &mutators,
+                selected_difficulty.0,
+            );
+            announce(BOSS_ANNOUNCEMENT, &mut announcement_queue);
+        }
+    }
+}
+
+/// Sends in the boss being practiced against whenever there isn't one in the arena
+#[allow(clippy::too_many_arguments)]
+fn spawn_practice_boss(
+    mut commands: Commands,
+    boss_practice: Res<BossPractice>,
+    mut respawn_timer: ResMut<BossPracticeRespawnTimer>,
+    enemy_query: Query<&Enemy>,
+    spawn_areas: Res<SpawnAreas>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    shared_asset_handles: Res<SharedAssetHandles>,
+    settings: Res<Settings>,
+    mutators: Res<ActiveMutators>,
+    selected_difficulty: Res<SelectedDifficulty>,
+    mut announcement_queue: ResMut<AnnouncementQueue>,
+    player_query: Query<&Transform, With<Player>>,
+    time: Res<Time>,
+) {
+    let Some(practice) = boss_practice.0 else {
+        return;
+    };
+
+    if enemy_query
+        .iter()
+        .any(|enemy| enemy.enemy_type == practice.boss_type)
+    {
+        respawn_timer.0.reset();
+        return;
+    }
+
+    respawn_timer.0.tick(time.delta());
+    if !respawn_timer.0.finished() {
+        return;
+    }
+
+    let avoid_positions = player_query
+        .iter()
+        .map(|transform| transform.translation.xy())
+        .collect::<Vec<Vec2>>();
+    if let Some(spawn_point) = choose_spawn_point(&avoid_positions, &spawn_areas) {
+        spawn_enemy_of_type(
+            practice.boss_type,
+            spawn_point,
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            &shared_asset_handles,
+            &settings,
+            &mutators,
+            selected_difficulty.0,
         );
+        announce(BOSS_ANNOUNCEMENT, &mut announcement_queue);
+        respawn_timer.0.reset();
+    }
+}
+
+/// Keeps the player at full health during boss practice with god mode on
+fn apply_boss_practice_god_mode(boss_practice: Res<BossPractice>, mut health: ResMut<Health>) {
+    let god_mode = boss_practice.0.is_some_and(|practice| practice.god_mode);
+    if god_mode && health.current_health < health.max_health {
+        health.current_health = health.max_health;
+    }
+}
+
+/// Goes back to normal runs once the player is back at the menu
+fn end_boss_practice(mut boss_practice: ResMut<BossPractice>) {
+    boss_practice.0 = None;
+}
+
+/// Shows the health of the current boss at the top of the screen, and hides it while there isn't one
+fn update_boss_health_bar(
+    enemy_query: Query<(&Enemy, &EnemyHealth)>,
+    mut health_bar_query: Query<&mut Visibility, With<BossHealthBar>>,
+    mut fill_query: Query<&mut Style, With<BossHealthBarFill>>,
+) {
+    let boss_health = enemy_query
+        .iter()
+        .find(|(enemy, _)| enemy.enemy_type == EnemyType::Boss)
+        .map(|(_, health)| health);
+
+    let visibility = if boss_health.is_some() {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+    for mut health_bar_visibility in health_bar_query.iter_mut() {
+        if *health_bar_visibility != visibility {
+            *health_bar_visibility = visibility;
+        }
+    }
+
+    if let Some(health) = boss_health {
+        for mut style in fill_query.iter_mut() {
+            style.width = Val::Percent(health.current as f32 / health.max as f32 * 100.0);
+        }
+    }
+}
+
+/// Makes a newly spawned enemy fade in, and grow to its full size unless motion is reduced.
+/// The enemy can move and collide with things while it's fading in, since it's short enough not to matter
+/// and enemies don't spawn right next to the player anyway.
+fn animate_enemy_spawn(
+    enemy_entity: Entity,
+    color: Color,
+    commands: &mut Commands,
+    materials: &mut Assets<ColorMaterial>,
+    settings: &Settings,
+) {
+    let fade_animation = Tween::new(
+        EaseFunction::QuadraticOut,
+        ENEMY_SPAWN_ANIMATION_DURATION,
+        ColorMaterialColorLens {
+            start: color.with_a(0.0),
+            end: color,
+        },
+    )
+    .with_completed_event(ENEMY_SPAWN_COMPLETE_EVENT_ID);
+
+    let mut enemy = commands.entity(enemy_entity);
+    // the enemy gets its own material while fading in, so it doesn't fade in every other enemy with it
+    enemy
+        .insert(materials.add(ColorMaterial::from(color.with_a(0.0))))
+        .insert(AssetAnimator::new(fade_animation));
+
+    if !settings.reduce_motion {
+        enemy.insert(Animator::new(Tween::new(
+            EaseFunction::QuadraticOut,
+            ENEMY_SPAWN_ANIMATION_DURATION,
+            TransformScaleLens {
+                start: Vec3::new(ENEMY_SPAWN_START_SCALE, ENEMY_SPAWN_START_SCALE, 1.0),
+                end: Vec3::ONE,
+            },
+        )));
     }
 }
 
 /// Spawns an enemy at the provided location
 fn spawn_enemy(
-    mut commands: Commands,
+    commands: &mut Commands,
+    enemy_type: EnemyType,
     location: Vec3,
     params: EnemyParams,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-) {
+    meshes: &mut Assets<Mesh>,
+    asset_handles: &EnemyAssetHandles,
+    settings: &Settings,
+) -> Entity {
     let mut rng = rand::thread_rng();
-    let size = rng.gen_range(params.size);
+    let size = rng.gen_range(params.size.clone());
 
-    commands
-        .spawn(MaterialMesh2dBundle {
-            mesh: meshes.add(shape::Circle::new(size).into()).into(),
-            material: materials.add(ColorMaterial::from(params.color)),
-            transform: Transform::from_translation(location),
-            ..default()
-        })
+    // the shared meshes only fit enemies of the size they were built for
+    let (mesh, threat_ring_mesh) = if size == asset_handles.size {
+        (
+            asset_handles.mesh.clone(),
+            asset_handles.threat_ring_mesh.clone(),
+        )
+    } else {
+        (
+            meshes.add(shape::Circle::new(size).into()),
+            meshes.add(shape::Circle::new(size + THREAT_RING_WIDTH).into()),
+        )
+    };
+
+    let damage_indicator_mesh = mesh.clone();
+    let shield_bubble_mesh = mesh.clone();
+    let mut enemy = commands.spawn(MaterialMesh2dBundle {
+        mesh: mesh.into(),
+        material: asset_handles.material.clone(),
+        transform: Transform::from_translation(location),
+        ..default()
+    });
+    enemy
         .insert(GameComponent)
         .insert(Collider::ball(size))
         .insert(ActiveEvents::COLLISION_EVENTS)
@@ -1705,18 +4975,492 @@ fn spawn_enemy(
         })
         .insert(GravityScale(0.0))
         .insert(Enemy {
+            enemy_type,
             damage: params.damage,
             xp_reward: params.xp_reward,
             max_speed: rng.gen_range(params.max_speed),
             size,
             color: params.color,
+            clings: params.clings,
+        })
+        .insert(EnemyHealth {
+            current: params.health,
+            max: params.health,
+            last_sword_swing: None,
+        })
+        .insert(EnemyTrailMaterial(asset_handles.trail_material.clone()))
+        .insert(SlowFieldOverlaps(HashSet::new()))
+        .with_children(|parent| {
+            if params.shielded {
+                parent
+                    .spawn(MaterialMesh2dBundle {
+                        mesh: shield_bubble_mesh.into(),
+                        material: asset_handles.shield_bubble_material.clone(),
+                        transform: Transform::from_translation(Vec3::new(
+                            0.0,
+                            0.0,
+                            SHIELD_BUBBLE_Z,
+                        ))
+                        .with_scale(Vec3::new(
+                            SHIELD_BUBBLE_SCALE,
+                            SHIELD_BUBBLE_SCALE,
+                            1.0,
+                        )),
+                        ..default()
+                    })
+                    .insert(ShieldBubble);
+            }
+
+            if params.health > 1 {
+                // starts out invisible, and grows as the enemy takes damage
+                parent
+                    .spawn(MaterialMesh2dBundle {
+                        mesh: damage_indicator_mesh.into(),
+                        material: asset_handles.damage_indicator_material.clone(),
+                        transform: Transform::from_translation(Vec3::new(
+                            0.0,
+                            0.0,
+                            ENEMY_DAMAGE_INDICATOR_Z,
+                        ))
+                        .with_scale(Vec3::ZERO),
+                        ..default()
+                    })
+                    .insert(EnemyDamageIndicator);
+            }
+
+            if settings.show_threat_rings {
+                // threat ring, drawn as a slightly larger circle behind the enemy
+                parent.spawn(MaterialMesh2dBundle {
+                    mesh: threat_ring_mesh.into(),
+                    material: asset_handles.threat_ring_material.clone(),
+                    transform: Transform::from_translation(Vec3::new(0.0, 0.0, THREAT_RING_Z)),
+                    ..default()
+                });
+            }
         });
-}
 
-/// Handles changing spawn weights over time
-fn change_spawn_weights(
+    if params.shielded {
+        enemy.insert(Shield {
+            timer: Timer::new(SHIELD_UP_DURATION, TimerMode::Once),
+            active: true,
+        });
+    }
+
+    if params.pulls_enemies {
+        enemy.insert(Magnet);
+    }
+
+    if params.shoots {
+        enemy.insert(Shooter(Timer::new(
+            SHOOTER_FIRE_INTERVAL,
+            TimerMode::Repeating,
+        )));
+    }
+
+    if params.blinks {
+        enemy.insert(Blink {
+            timer: Timer::new(BLINK_INTERVAL, TimerMode::Once),
+            target: None,
+        });
+    }
+
+    enemy.id()
+}
+
+/// Handles shooting enemies firing at the player once they're close enough
+fn enemy_shooting(
+    mut commands: Commands,
+    mut shooter_query: Query<(&Transform, &mut Shooter), Without<Player>>,
+    player_query: Query<&Transform, With<Player>>,
+    shared_asset_handles: Res<SharedAssetHandles>,
+    time: Res<Time>,
+) {
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+    let player_position = player_transform.translation.xy();
+
+    for (transform, mut shooter) in shooter_query.iter_mut() {
+        shooter.0.tick(time.delta());
+        if !shooter.0.just_finished() {
+            continue;
+        }
+
+        let position = transform.translation.xy();
+        let to_player = player_position - position;
+        if to_player.length() > SHOOTER_RANGE {
+            continue;
+        }
+        let Some(direction) = to_player.try_normalize() else {
+            continue;
+        };
+
+        commands
+            .spawn(MaterialMesh2dBundle {
+                mesh: shared_asset_handles.enemy_projectile_mesh.clone().into(),
+                material: shared_asset_handles.enemy_projectile_material.clone(),
+                transform: Transform::from_translation(position.extend(ENEMY_PROJECTILE_Z)),
+                ..default()
+            })
+            .insert(GameComponent)
+            .insert(Collider::ball(ENEMY_PROJECTILE_RADIUS))
+            .insert(Sensor)
+            .insert(EnemyProjectile {
+                velocity: direction * ENEMY_PROJECTILE_SPEED,
+                lifetime: Timer::new(ENEMY_PROJECTILE_LIFETIME, TimerMode::Once),
+            });
+    }
+}
+
+/// Moves enemy projectiles along and gets rid of ones that have traveled far enough
+fn move_enemy_projectiles(
+    mut projectile_query: Query<(Entity, &mut Transform, &mut EnemyProjectile)>,
+    mut entities_to_despawn: ResMut<EntitiesToDespawn>,
+    time: Res<Time>,
+) {
+    for (entity, mut transform, mut projectile) in projectile_query.iter_mut() {
+        transform.translation += (projectile.velocity * time.delta_seconds()).extend(0.0);
+
+        projectile.lifetime.tick(time.delta());
+        if projectile.lifetime.just_finished() && !entities_to_despawn.0.contains(&entity) {
+            entities_to_despawn.0.push(entity);
+        }
+    }
+}
+
+/// Switches shielded enemies between being invulnerable and vulnerable
+fn cycle_shields(
+    mut enemy_query: Query<(&mut Shield, &Children)>,
+    mut bubble_query: Query<&mut Visibility, With<ShieldBubble>>,
+    time: Res<Time>,
+) {
+    for (mut shield, children) in enemy_query.iter_mut() {
+        shield.timer.tick(time.delta());
+        if !shield.timer.just_finished() {
+            continue;
+        }
+
+        shield.active = !shield.active;
+        let next_phase_duration = if shield.active {
+            SHIELD_UP_DURATION
+        } else {
+            SHIELD_DOWN_DURATION
+        };
+        shield.timer = Timer::new(next_phase_duration, TimerMode::Once);
+
+        for child in children.iter() {
+            if let Ok(mut visibility) = bubble_query.get_mut(*child) {
+                *visibility = if shield.active {
+                    Visibility::Inherited
+                } else {
+                    Visibility::Hidden
+                };
+            }
+        }
+    }
+}
+
+/// Handles blinking enemies telegraphing blinks and then teleporting toward the player
+#[allow(clippy::too_many_arguments)]
+fn blink_enemies(
+    mut commands: Commands,
+    mut enemy_query: Query<
+        (
+            Entity,
+            &mut Transform,
+            &mut Blink,
+            &Mesh2dHandle,
+            &EnemyTrailMaterial,
+        ),
+        Without<Player>,
+    >,
+    player_query: Query<&Transform, With<Player>>,
+    marker_query: Query<(Entity, &BlinkMarker)>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    shared_asset_handles: Res<SharedAssetHandles>,
+    audio_assets: Res<AudioAssets>,
+    volume_settings: Res<VolumeSettings>,
+    mut entities_to_despawn: ResMut<EntitiesToDespawn>,
+    time: Res<Time>,
+) {
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+    let player_position = player_transform.translation.truncate();
+
+    for (entity, mut transform, mut blink, mesh, trail_material) in enemy_query.iter_mut() {
+        blink.timer.tick(time.delta());
+        if !blink.timer.just_finished() {
+            continue;
+        }
+
+        if let Some(target) = blink.target.take() {
+            // leave an afterimage behind so it's clear where the enemy came from
+            let shrink_animation = Tween::new(
+                EaseFunction::QuadraticIn,
+                ENEMY_TRAIL_DURATION,
+                TransformScaleLens {
+                    start: Vec3::ONE,
+                    end: Vec3::ZERO,
+                },
+            )
+            .with_completed_event(ENEMY_TRAIL_COMPLETE_EVENT_ID);
+            commands
+                .spawn(MaterialMesh2dBundle {
+                    mesh: mesh.clone(),
+                    material: trail_material.0.clone(),
+                    transform: Transform::from_translation(
+                        transform.translation.truncate().extend(ENEMY_TRAIL_Z),
+                    ),
+                    ..default()
+                })
+                .insert(GameComponent)
+                .insert(EnemyTrail)
+                .insert(Animator::new(shrink_animation));
+
+            teleport(
+                &mut commands,
+                &mut transform,
+                false,
+                0.0,
+                target,
+                &mut materials,
+                &shared_asset_handles,
+                &audio_assets,
+                &volume_settings,
+            );
+            blink.timer = Timer::new(BLINK_INTERVAL, TimerMode::Once);
+        } else {
+            let position = transform.translation.truncate();
+            let to_player = player_position - position;
+            let blink_distance =
+                (to_player.length() - BLINK_MIN_PLAYER_DISTANCE).clamp(0.0, BLINK_DISTANCE);
+            if blink_distance <= 0.0 {
+                // already close enough, so wait for the next chance
+                blink.timer = Timer::new(BLINK_INTERVAL, TimerMode::Once);
+                continue;
+            }
+
+            // the target is fixed once it's shown, so the player can get out of the way or be ready to swing
+            let target = position + to_player.normalize_or_zero() * blink_distance;
+            let grow_animation = Tween::new(
+                EaseFunction::QuadraticOut,
+                BLINK_TELEGRAPH_DURATION,
+                TransformScaleLens {
+                    start: Vec3::ZERO,
+                    end: Vec3::ONE,
+                },
+            );
+            commands
+                .spawn(MaterialMesh2dBundle {
+                    mesh: mesh.clone(),
+                    material: trail_material.0.clone(),
+                    transform: Transform::from_translation(target.extend(ENEMY_TRAIL_Z))
+                        .with_scale(Vec3::ZERO),
+                    ..default()
+                })
+                .insert(GameComponent)
+                .insert(BlinkMarker { owner: entity })
+                .insert(Animator::new(grow_animation));
+
+            blink.target = Some(target);
+            blink.timer = Timer::new(BLINK_TELEGRAPH_DURATION, TimerMode::Once);
+        }
+    }
+
+    // get rid of markers for blinks that have happened, or that never will because the enemy died
+    for (marker_entity, marker) in marker_query.iter() {
+        let still_telegraphing = enemy_query
+            .get(marker.owner)
+            .map(|(_, _, blink, _, _)| blink.target.is_some())
+            .unwrap_or(false);
+        if !still_telegraphing {
+            entities_to_despawn.0.push(marker_entity);
+        }
+    }
+}
+
+/// Periodically leaves fading copies of fast-moving enemies behind them
+fn spawn_enemy_trails(
+    mut commands: Commands,
+    mut trail_timer: ResMut<EnemyTrailTimer>,
+    enemy_query: Query<(&Transform, &Velocity, &Mesh2dHandle, &EnemyTrailMaterial), With<Enemy>>,
+    trail_query: Query<(), With<EnemyTrail>>,
+    time: Res<Time>,
+) {
+    trail_timer.0.tick(time.delta());
+    if !trail_timer.0.just_finished() {
+        return;
+    }
+
+    let fast_enemies = enemy_query
+        .iter()
+        .filter(|(_, velocity, _, _)| velocity.linvel.length() >= ENEMY_TRAIL_MIN_SPEED);
+    let trails_remaining = MAX_ENEMY_TRAILS.saturating_sub(trail_query.iter().count());
+    for (transform, _, mesh, trail_material) in fast_enemies.take(trails_remaining) {
+        let shrink_animation = Tween::new(
+            EaseFunction::QuadraticIn,
+            ENEMY_TRAIL_DURATION,
+            TransformScaleLens {
+                start: Vec3::ONE,
+                end: Vec3::ZERO,
+            },
+        )
+        .with_completed_event(ENEMY_TRAIL_COMPLETE_EVENT_ID);
+
+        commands
+            .spawn(MaterialMesh2dBundle {
+                mesh: mesh.clone(),
+                material: trail_material.0.clone(),
+                transform: Transform::from_translation(
+                    transform.translation.truncate().extend(ENEMY_TRAIL_Z),
+                ),
+                ..default()
+            })
+            .insert(GameComponent)
+            .insert(EnemyTrail)
+            .insert(Animator::new(shrink_animation));
+    }
+}
+
+/// Periodically leaves fading copies of the player behind them while they're moving, if they have a trail
+fn spawn_player_trail(
+    mut commands: Commands,
+    mut player_query: Query<(&Transform, &Velocity, &Mesh2dHandle, &mut LeavesTrail)>,
+    time: Res<Time>,
+) {
+    for (transform, velocity, mesh, mut leaves_trail) in player_query.iter_mut() {
+        leaves_trail.timer.tick(time.delta());
+        if !leaves_trail.timer.just_finished() || velocity.linvel.length() < PLAYER_TRAIL_MIN_SPEED
+        {
+            continue;
+        }
+
+        let shrink_animation = Tween::new(
+            EaseFunction::QuadraticIn,
+            PLAYER_TRAIL_DURATION,
+            TransformScaleLens {
+                start: Vec3::ONE,
+                end: Vec3::ZERO,
+            },
+        )
+        .with_completed_event(PLAYER_TRAIL_COMPLETE_EVENT_ID);
+
+        commands
+            .spawn(MaterialMesh2dBundle {
+                mesh: mesh.clone(),
+                material: leaves_trail.material.clone(),
+                transform: Transform::from_translation(
+                    transform.translation.truncate().extend(PLAYER_TRAIL_Z),
+                ),
+                ..default()
+            })
+            .insert(GameComponent)
+            .insert(PlayerTrailSegment)
+            .insert(Animator::new(shrink_animation));
+    }
+}
+
+/// Speeds up or slows down spawning based on how the player has been doing recently
+fn update_dynamic_difficulty(
+    mut dynamic_difficulty: ResMut<DynamicDifficulty>,
+    mut enemy_killed_events: EventReader<EnemyKilled>,
+    health: Res<Health>,
+    time: Res<Time>,
+) {
+    dynamic_difficulty.kills += enemy_killed_events.read().count();
+    dynamic_difficulty.health_lost += dynamic_difficulty
+        .last_health
+        .saturating_sub(health.current_health);
+    dynamic_difficulty.last_health = health.current_health;
+
+    dynamic_difficulty.timer.tick(time.delta());
+    if !dynamic_difficulty.timer.just_finished() {
+        return;
+    }
+
+    let interval_seconds = DYNAMIC_DIFFICULTY_INTERVAL.as_secs_f32();
+    let recent_health_loss_rate = dynamic_difficulty.health_lost as f32 / interval_seconds;
+    let recent_kill_rate = dynamic_difficulty.kills as f32 / interval_seconds;
+    dynamic_difficulty.health_lost = 0;
+    dynamic_difficulty.kills = 0;
+
+    dynamic_difficulty.health_loss_rate =
+        smooth_towards(dynamic_difficulty.health_loss_rate, recent_health_loss_rate);
+    dynamic_difficulty.kill_rate = smooth_towards(dynamic_difficulty.kill_rate, recent_kill_rate);
+
+    // killing lots of enemies means the player can handle more, losing lots of health means they need a break
+    let target_multiplier = (1.0
+        + (dynamic_difficulty.kill_rate * DYNAMIC_DIFFICULTY_KILL_RATE_WEIGHT)
+        - (dynamic_difficulty.health_loss_rate * DYNAMIC_DIFFICULTY_HEALTH_LOSS_RATE_WEIGHT))
+        .clamp(
+            DYNAMIC_DIFFICULTY_MIN_SPAWN_RATE_MULTIPLIER,
+            DYNAMIC_DIFFICULTY_MAX_SPAWN_RATE_MULTIPLIER,
+        );
+    dynamic_difficulty.spawn_rate_multiplier =
+        smooth_towards(dynamic_difficulty.spawn_rate_multiplier, target_multiplier);
+}
+
+/// Slows down spawning while frames are taking too long, and lets it recover once they speed back up
+fn update_spawn_budget(
+    mut spawn_budget: ResMut<SpawnBudget>,
+    diagnostics: Res<DiagnosticsStore>,
+    time: Res<Time<Real>>,
+) {
+    spawn_budget.timer.tick(time.delta());
+    if !spawn_budget.timer.just_finished() {
+        return;
+    }
+
+    let Some(frame_time_ms) = diagnostics
+        .get(FrameTimeDiagnosticsPlugin::FRAME_TIME)
+        .and_then(|frame_time| frame_time.smoothed())
+    else {
+        return;
+    };
+
+    // frame times between the two thresholds leave things as they are, so the multiplier doesn't flip back and forth
+    if frame_time_ms > SPAWN_BUDGET_MAX_FRAME_TIME_MS {
+        spawn_budget.spawn_rate_multiplier = (spawn_budget.spawn_rate_multiplier
+            - SPAWN_BUDGET_STEP)
+            .max(SPAWN_BUDGET_MIN_SPAWN_RATE_MULTIPLIER);
+    } else if frame_time_ms < SPAWN_BUDGET_RECOVERY_FRAME_TIME_MS {
+        spawn_budget.spawn_rate_multiplier =
+            (spawn_budget.spawn_rate_multiplier + SPAWN_BUDGET_STEP).min(1.0);
+    }
+}
+
+/// Moves a smoothed value part of the way towards a new value
+fn smooth_towards(current: f32, target: f32) -> f32 {
+    current + (target - current) * DYNAMIC_DIFFICULTY_SMOOTHING
+}
+
+/// Announces spawn storms when they're triggered, and moves them along from warning to storm to done.
+/// This runs with virtual time so storms don't progress while the game is paused or the perk chooser is up.
+fn update_spawn_storm(
+    mut start_spawn_storm_events: EventReader<StartSpawnStorm>,
+    mut spawn_storm: ResMut<SpawnStorm>,
+    mut announcement_queue: ResMut<AnnouncementQueue>,
+    time: Res<Time>,
+) {
+    if start_spawn_storm_events.read().count() > 0 {
+        spawn_storm.warning_timer.reset();
+        spawn_storm.duration_timer.reset();
+        announce(SPAWN_STORM_ANNOUNCEMENT, &mut announcement_queue);
+    }
+
+    if spawn_storm.warning_timer.finished() {
+        spawn_storm.duration_timer.tick(time.delta());
+    } else {
+        spawn_storm.warning_timer.tick(time.delta());
+    }
+}
+
+/// Handles changing spawn weights over time
+fn change_spawn_weights(
     mut spawn_weights_change_timer: ResMut<SpawnWeightsChangeTimer>,
     mut spawn_weights: ResMut<SpawnWeights>,
+    mut announcement_queue: ResMut<AnnouncementQueue>,
     time: Res<Time>,
 ) {
     spawn_weights_change_timer.0.tick(time.delta());
@@ -1733,62 +5477,212 @@ fn change_spawn_weights(
             .dist
             .update_weights(&[(weight_index, &new_weight)])
             .expect("weights should be valid");
+
+        if new_weight == 1 {
+            // this type of enemy couldn't spawn before now
+            announce(NEW_ENEMY_TYPE_ANNOUNCEMENT, &mut announcement_queue);
+        }
     }
 }
 
+type MovingEnemyTuple = (
+    &'static mut ExternalForce,
+    &'static mut Velocity,
+    &'static mut Transform,
+    &'static Enemy,
+    &'static SlowFieldOverlaps,
+    Option<&'static Shooter>,
+);
+
 /// Handles moving enemies
 fn move_enemies(
-    mut enemy_query: Query<
-        (&mut ExternalForce, &mut Velocity, &mut Transform, &Enemy),
-        Without<Player>,
-    >,
+    mut enemy_query: Query<MovingEnemyTuple, Without<Player>>,
     player_query: Query<&Transform, With<Player>>,
     speed_multiplier: Res<EnemySpeedMultiplier>,
+    mut grace_period: ResMut<GracePeriod>,
+    time: Res<Time>,
 ) {
+    // ease enemies into moving so they don't all rush the player the moment the run starts
+    grace_period.0.tick(time.delta());
+    let move_force = ENEMY_MOVE_FORCE * grace_period.0.percent().powi(2);
+
     if let Ok(player_transform) = player_query.get_single() {
-        for (mut force, mut velocity, mut transform, enemy) in &mut enemy_query {
-            // push enemy in direction of player
+        for (mut force, mut velocity, mut transform, enemy, slow_field_overlaps, shooter) in
+            &mut enemy_query
+        {
+            // push enemy in direction of player, unless it's close enough to shoot at them instead
             let player_direction = player_transform.translation - transform.translation;
-            let movement_force = player_direction.clamp_length(ENEMY_MOVE_FORCE, ENEMY_MOVE_FORCE);
-            force.force = Vec2::new(movement_force.x, movement_force.y);
+            if shooter.is_some() && player_direction.length() <= SHOOTER_RANGE {
+                force.force = Vec2::ZERO;
+            } else {
+                let movement_force = player_direction.clamp_length(move_force, move_force);
+                force.force = Vec2::new(movement_force.x, movement_force.y);
+            }
 
-            // rotate to face player
-            if let Some(to_player) = player_direction.try_normalize() {
-                let rotate_to_player = Quat::from_rotation_arc(Vec3::Y, to_player);
-                transform.rotation = rotate_to_player;
+            // turn to face player
+            if let Some(to_player) = player_direction.xy().try_normalize() {
+                let rotate_to_player = Quat::from_rotation_arc_2d(Vec2::Y, to_player);
+                transform.rotation = rotate_towards(
+                    transform.rotation,
+                    rotate_to_player,
+                    ENEMY_MAX_TURN_RATE * time.delta_seconds(),
+                );
             }
 
             // prevent enemies from spinning around on their own
             velocity.angvel = 0.0;
 
             // clamp speed
+            let slow_field_multiplier = if slow_field_overlaps.0.is_empty() {
+                1.0
+            } else {
+                SLOW_FIELD_SPEED_MULTIPLIER
+            };
             velocity.linvel = velocity
                 .linvel
-                .clamp_length_max(enemy.max_speed * speed_multiplier.0);
+                .clamp_length_max(enemy.max_speed * speed_multiplier.0 * slow_field_multiplier);
+        }
+    }
+}
+
+type NonMagnetEnemyTuple = (With<Enemy>, Without<Magnet>);
+
+/// Pulls enemies towards any magnetic enemies near them, on top of their normal movement
+fn pull_enemies_toward_magnets(
+    magnet_query: Query<&Transform, With<Magnet>>,
+    mut enemy_query: Query<(&mut ExternalForce, &Transform), NonMagnetEnemyTuple>,
+    grace_period: Res<GracePeriod>,
+) {
+    // magnets ease in along with everything else at the start of a run
+    let max_pull_force = ENEMY_MOVE_FORCE * MAGNET_PULL_STRENGTH * grace_period.0.percent().powi(2);
+
+    for magnet_transform in magnet_query.iter() {
+        let magnet_position = magnet_transform.translation.xy();
+        for (mut force, transform) in enemy_query.iter_mut() {
+            let to_magnet = magnet_position - transform.translation.xy();
+            let distance = to_magnet.length();
+            if distance >= MAGNET_PULL_RADIUS {
+                continue;
+            }
+
+            // pull harder the closer the enemy is
+            let pull_force = max_pull_force * (1.0 - (distance / MAGNET_PULL_RADIUS));
+            force.force += to_magnet.normalize_or_zero() * pull_force;
         }
     }
 }
 
+type CollidingPlayerTuple = (
+    &'static Player,
+    &'static Transform,
+    &'static mut ExternalImpulse,
+    &'static Retaliate,
+    Option<&'static Dashing>,
+    &'static CritChance,
+);
+
 /// Handles collisions between objects
+#[allow(clippy::too_many_arguments)]
 fn collisions(
     mut collision_events: EventReader<CollisionEvent>,
     mut entities_to_despawn: ResMut<EntitiesToDespawn>,
     mut level: ResMut<Level>,
     mut health: ResMut<Health>,
     mut slow_mo_timer: ResMut<SlowMoTimer>,
-    enemies_query: Query<(&Enemy, &Transform)>,
-    sword_query: Query<&Sword>,
-    mut player_query: Query<(&Player, &Transform, &mut ExternalImpulse, &Retaliate)>,
+    enemies_query: Query<(&Enemy, &Transform, Option<&Shield>)>,
+    (sword_query, sword_arc_query): (Query<&Sword>, Query<&SwordArc>),
+    (sword_beam_query, enemy_projectile_query, health_pickup_query, xp_orb_query): (
+        Query<&SwordBeamProjectile>,
+        Query<&EnemyProjectile>,
+        Query<&HealthPickup>,
+        Query<&XpOrb>,
+    ),
+    mut player_query: Query<CollidingPlayerTuple>,
     explosion_query: Query<&Explosion>,
     mut commands: Commands,
-    audio_assets: Res<AudioAssets>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
+    (audio_assets, volume_settings): (Res<AudioAssets>, Res<VolumeSettings>),
+    (mut meshes, mut materials): (ResMut<Assets<Mesh>>, ResMut<Assets<ColorMaterial>>),
+    (mut enemy_killed_events, mut player_hit_events): (
+        EventWriter<EnemyKilled>,
+        EventWriter<PlayerHit>,
+    ),
+    mut enemy_health_query: Query<&mut EnemyHealth>,
+    (shared_asset_handles, settings): (Res<SharedAssetHandles>, Res<Settings>),
 ) {
     for event in collision_events.read() {
         if let CollisionEvent::Started(a, b, _) = event {
-            if let Some((enemy, enemy_entity)) =
-                get_from_either::<Enemy, (&Enemy, &Transform)>(*a, *b, &enemies_query)
+            if let Some((_, projectile_entity)) = get_from_either::<EnemyProjectile, &EnemyProjectile>(
+                *a,
+                *b,
+                &enemy_projectile_query,
+            ) {
+                // an enemy projectile has hit something, but it only cares about hitting the player
+                let other_entity = if projectile_entity == *a { *b } else { *a };
+                if entities_to_despawn.0.contains(&projectile_entity)
+                    || !player_query.contains(other_entity)
+                    // dashing players can't be hurt, so shots go right through them
+                    || player_query.get_component::<Dashing>(other_entity).is_ok()
+                {
+                    continue;
+                }
+
+                entities_to_despawn.0.push(projectile_entity);
+                health.current_health = health
+                    .current_health
+                    .saturating_sub(ENEMY_PROJECTILE_DAMAGE);
+                player_hit_events.send(PlayerHit);
+                play_sound(
+                    audio_assets.player_hit.clone(),
+                    PLAYER_HIT_VOLUME,
+                    &volume_settings,
+                    &mut commands,
+                );
+                continue;
+            }
+
+            if let Some((xp_orb, orb_entity)) =
+                get_from_either::<XpOrb, &XpOrb>(*a, *b, &xp_orb_query)
+            {
+                // an XP orb has touched something, but only the player can collect it
+                let other_entity = if orb_entity == *a { *b } else { *a };
+                if entities_to_despawn.0.contains(&orb_entity)
+                    || !player_query.contains(other_entity)
+                {
+                    continue;
+                }
+
+                entities_to_despawn.0.push(orb_entity);
+                level.current_xp += xp_orb.xp;
+                continue;
+            }
+
+            if let Some((_, pickup_entity)) =
+                get_from_either::<HealthPickup, &HealthPickup>(*a, *b, &health_pickup_query)
+            {
+                // a health pickup has touched something, but only the player can pick it up
+                let other_entity = if pickup_entity == *a { *b } else { *a };
+                if entities_to_despawn.0.contains(&pickup_entity)
+                    || !player_query.contains(other_entity)
+                {
+                    continue;
+                }
+
+                entities_to_despawn.0.push(pickup_entity);
+                health.current_health =
+                    (health.current_health + HEALTH_PICKUP_AMOUNT).min(health.max_health);
+                play_sound(
+                    audio_assets.secondary_action_ready.clone(),
+                    HEALTH_PICKUP_VOLUME,
+                    &volume_settings,
+                    &mut commands,
+                );
+                continue;
+            }
+
+            if let Some((enemy, enemy_entity)) = get_from_either::<
+                Enemy,
+                (&Enemy, &Transform, Option<&Shield>),
+            >(*a, *b, &enemies_query)
             {
                 // an enemy has hit something
                 if entities_to_despawn.0.contains(&enemy_entity) {
@@ -1798,54 +5692,156 @@ fn collisions(
 
                 if let Ok(enemy_transform) = enemies_query.get_component::<Transform>(enemy_entity)
                 {
+                    // shielded enemies can't be hurt by the player's attacks while their shield is up
+                    let shielded = enemies_query
+                        .get_component::<Shield>(enemy_entity)
+                        .is_ok_and(|shield| shield.active);
+
                     if let Some((explosion, explosion_entity)) =
                         get_from_either::<Explosion, &Explosion>(*a, *b, &explosion_query)
                     {
                         // an enemy has hit an explosion
-                        kill_enemy(
-                            enemy,
-                            enemy_entity,
-                            enemy_transform.translation,
-                            &mut entities_to_despawn,
-                            &mut level,
-                            &mut commands,
-                            &audio_assets,
-                            &mut meshes,
-                            &mut materials,
-                        );
+                        if !shielded && damage_enemy(enemy_entity, None, 1, &mut enemy_health_query)
+                        {
+                            kill_enemy(
+                                enemy,
+                                enemy_entity,
+                                enemy_transform.translation,
+                                &mut entities_to_despawn,
+                                &mut commands,
+                                &audio_assets,
+                                &volume_settings,
+                                &mut meshes,
+                                &mut materials,
+                                &mut enemy_killed_events,
+                                &shared_asset_handles,
+                                false,
+                            );
+                        }
                     } else if let Some((sword, sword_entity)) =
                         get_from_either::<Sword, &Sword>(*a, *b, &sword_query)
                     {
                         // an enemy has hit the sword
-                        if sword.active {
+                        if sword.active && !shielded {
+                            let crit_chance = player_query
+                                .iter()
+                                .map(|(_, _, _, _, _, crit_chance)| crit_chance.0)
+                                .fold(0.0, f32::max);
+                            let critical = rand::thread_rng().gen_bool(crit_chance as f64);
+                            let damage = if critical { CRIT_DAMAGE } else { 1 };
+                            if damage_enemy(
+                                enemy_entity,
+                                Some(sword.swing),
+                                damage,
+                                &mut enemy_health_query,
+                            ) {
+                                kill_enemy(
+                                    enemy,
+                                    enemy_entity,
+                                    enemy_transform.translation,
+                                    &mut entities_to_despawn,
+                                    &mut commands,
+                                    &audio_assets,
+                                    &volume_settings,
+                                    &mut meshes,
+                                    &mut materials,
+                                    &mut enemy_killed_events,
+                                    &shared_asset_handles,
+                                    critical,
+                                );
+                            }
+
+                            slow_mo_timer.start(HIT_SLOW_MO_TIME_SCALE, HIT_SLOW_MO_TIME);
+                        }
+                    } else if let Some((sword_arc, _)) =
+                        get_from_either::<SwordArc, &SwordArc>(*a, *b, &sword_arc_query)
+                    {
+                        // an enemy has wandered into the arc left behind by a sword swing
+                        if !shielded
+                            && damage_enemy(
+                                enemy_entity,
+                                Some(sword_arc.swing),
+                                1,
+                                &mut enemy_health_query,
+                            )
+                        {
                             kill_enemy(
                                 enemy,
                                 enemy_entity,
                                 enemy_transform.translation,
                                 &mut entities_to_despawn,
-                                &mut level,
                                 &mut commands,
                                 &audio_assets,
+                                &volume_settings,
                                 &mut meshes,
                                 &mut materials,
+                                &mut enemy_killed_events,
+                                &shared_asset_handles,
+                                false,
                             );
+                        }
+                    } else if let Some((_, sword_beam_entity)) = get_from_either::<
+                        SwordBeamProjectile,
+                        &SwordBeamProjectile,
+                    >(
+                        *a, *b, &sword_beam_query
+                    ) {
+                        // an enemy has hit a sword beam
+                        if entities_to_despawn.0.contains(&sword_beam_entity) {
+                            // this beam already hit something
+                            continue;
+                        }
 
-                            slow_mo_timer.target_time_scale = HIT_SLOW_MO_TIME_SCALE;
-                            slow_mo_timer.timer.set_duration(HIT_SLOW_MO_TIME);
-                            slow_mo_timer.timer.reset();
-                            slow_mo_timer.timer.unpause();
+                        entities_to_despawn.0.push(sword_beam_entity);
+                        if !shielded && damage_enemy(enemy_entity, None, 1, &mut enemy_health_query)
+                        {
+                            kill_enemy(
+                                enemy,
+                                enemy_entity,
+                                enemy_transform.translation,
+                                &mut entities_to_despawn,
+                                &mut commands,
+                                &audio_assets,
+                                &volume_settings,
+                                &mut meshes,
+                                &mut materials,
+                                &mut enemy_killed_events,
+                                &shared_asset_handles,
+                                false,
+                            );
                         }
                     } else if let Some((player, player_entity)) =
                         get_from_either::<
                             Player,
-                            (&Player, &Transform, &mut ExternalImpulse, &Retaliate),
+                            (
+                                &Player,
+                                &Transform,
+                                &mut ExternalImpulse,
+                                &Retaliate,
+                                Option<&Dashing>,
+                                &CritChance,
+                            ),
                         >(*a, *b, &player_query)
                     {
+                        if player_query.get_component::<Dashing>(player_entity).is_ok() {
+                            // dashing players can't be hurt
+                            continue;
+                        }
+
                         // an enemy has hit the player
                         health.current_health = health.current_health.saturating_sub(enemy.damage);
+                        player_hit_events.send(PlayerHit);
+                        if enemy.clings {
+                            // inserting this again refreshes any existing slowdown
+                            commands.entity(player_entity).insert(Slowed {
+                                timer: Timer::new(CLING_SLOW_DURATION, TimerMode::Once),
+                                speed_multiplier: CLING_SPEED_MULTIPLIER,
+                            });
+                        }
                         play_sound(
                             audio_assets.player_hit.clone(),
                             PLAYER_HIT_VOLUME,
+                            &volume_settings,
                             &mut commands,
                         );
 
@@ -1858,11 +5854,14 @@ fn collisions(
                                     enemy_entity,
                                     enemy_transform.translation,
                                     &mut entities_to_despawn,
-                                    &mut level,
                                     &mut commands,
                                     &audio_assets,
+                                    &volume_settings,
                                     &mut meshes,
                                     &mut materials,
+                                    &mut enemy_killed_events,
+                                    &shared_asset_handles,
+                                    false,
                                 );
                             }
                         }
@@ -1873,12 +5872,23 @@ fn collisions(
                             // push the player back
                             let enemy_to_player =
                                 player_transform.translation - enemy_transform.translation;
-                            let hit_force = enemy_to_player.clamp_length(HIT_IMPULSE, HIT_IMPULSE);
+                            let hit_force = enemy_to_player.clamp_length(HIT_IMPULSE, HIT_IMPULSE)
+                                * settings.knockback.get_multiplier();
+                            let player_position = player_transform.translation.xy();
                             if let Ok(mut impulse) =
                                 player_query.get_component_mut::<ExternalImpulse>(player_entity)
                             {
                                 impulse.impulse = Vec2::new(hit_force.x, hit_force.y);
                             }
+
+                            spawn_knockback_indicator(
+                                player_position,
+                                hit_force.xy(),
+                                &mut commands,
+                                &shared_asset_handles,
+                                &mut materials,
+                                &settings,
+                            );
                         }
                     }
                 }
@@ -1887,6 +5897,72 @@ fn collisions(
     }
 }
 
+/// Spawns an arrow next to the player pointing in the direction they were knocked back
+fn spawn_knockback_indicator(
+    player_position: Vec2,
+    knockback_direction: Vec2,
+    commands: &mut Commands,
+    shared_asset_handles: &SharedAssetHandles,
+    materials: &mut Assets<ColorMaterial>,
+    settings: &Settings,
+) {
+    let Some(direction) = knockback_direction.try_normalize() else {
+        return;
+    };
+
+    let position = player_position + (direction * KNOCKBACK_INDICATOR_OFFSET);
+    let mut indicator = commands.spawn(MaterialMesh2dBundle {
+        mesh: shared_asset_handles.knockback_indicator_mesh.clone().into(),
+        // each indicator needs its own material, since it gets faded out
+        material: materials.add(ColorMaterial::from(KNOCKBACK_INDICATOR_COLOR)),
+        transform: Transform::from_translation(position.extend(KNOCKBACK_INDICATOR_Z))
+            .with_rotation(Quat::from_rotation_arc_2d(Vec2::Y, direction)),
+        ..default()
+    });
+    indicator
+        .insert(GameComponent)
+        .insert(KnockbackIndicator(Timer::new(
+            KNOCKBACK_INDICATOR_DURATION,
+            TimerMode::Once,
+        )));
+
+    // with reduced motion, the indicator just shows up steadily and then disappears
+    if !settings.reduce_motion {
+        indicator.insert(AssetAnimator::new(Tween::new(
+            EaseFunction::QuadraticIn,
+            KNOCKBACK_INDICATOR_DURATION,
+            ColorMaterialColorLens {
+                start: KNOCKBACK_INDICATOR_COLOR,
+                end: KNOCKBACK_INDICATOR_COLOR.with_a(0.0),
+            },
+        )));
+    }
+}
+
+/// Gets rid of knockback indicators once they've been shown for long enough
+fn update_knockback_indicators(
+    mut indicator_query: Query<(Entity, &mut KnockbackIndicator)>,
+    mut entities_to_despawn: ResMut<EntitiesToDespawn>,
+    time: Res<Time>,
+) {
+    for (entity, mut indicator) in indicator_query.iter_mut() {
+        indicator.0.tick(time.delta());
+        if indicator.0.just_finished() {
+            entities_to_despawn.0.push(entity);
+        }
+    }
+}
+
+/// Rotates `current` towards `target`, but by no more than `max_angle` radians
+fn rotate_towards(current: Quat, target: Quat, max_angle: f32) -> Quat {
+    let angle = current.angle_between(target);
+    if angle <= max_angle {
+        target
+    } else {
+        current.slerp(target, max_angle / angle)
+    }
+}
+
 fn get_from_either<'a, T: Component, Q: WorldQuery>(
     a: Entity,
     b: Entity,
@@ -1903,21 +5979,226 @@ fn get_from_either<'a, T: Component, Q: WorldQuery>(
     None
 }
 
+/// Takes the provided number of hit points away from an enemy, returning whether it has no health left.
+/// The sword and all its shadows can hit an enemy during the same swing, so each swing only damages an enemy once.
+fn damage_enemy(
+    enemy_entity: Entity,
+    sword_swing: Option<u64>,
+    damage: u64,
+    enemy_health_query: &mut Query<&mut EnemyHealth>,
+) -> bool {
+    let Ok(mut enemy_health) = enemy_health_query.get_mut(enemy_entity) else {
+        return true;
+    };
+
+    if let Some(swing) = sword_swing {
+        if enemy_health.last_sword_swing == Some(swing) {
+            return false;
+        }
+        enemy_health.last_sword_swing = Some(swing);
+    }
+
+    enemy_health.current = enemy_health.current.saturating_sub(damage);
+    enemy_health.current == 0
+}
+
+/// Grows enemies' damage indicators to match how much damage they've taken
+fn update_enemy_damage_indicators(
+    enemy_query: Query<(&EnemyHealth, &Children), Changed<EnemyHealth>>,
+    mut indicator_query: Query<&mut Transform, With<EnemyDamageIndicator>>,
+) {
+    for (enemy_health, children) in enemy_query.iter() {
+        let damage_fraction = 1.0 - (enemy_health.current as f32 / enemy_health.max as f32);
+        for child in children.iter() {
+            if let Ok(mut transform) = indicator_query.get_mut(*child) {
+                transform.scale = Vec3::new(damage_fraction, damage_fraction, 1.0);
+            }
+        }
+    }
+}
+
+/// Briefly flashes enemies that took damage but survived, so it's clear the hit landed.
+/// With reduced motion, the damage indicator is enough of a sign on its own.
+fn flash_damaged_enemies(
+    mut commands: Commands,
+    enemy_query: Query<(Entity, &Enemy, &EnemyHealth), Changed<EnemyHealth>>,
+    shared_asset_handles: Res<SharedAssetHandles>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    settings: Res<Settings>,
+) {
+    if settings.reduce_motion {
+        return;
+    }
+
+    for (enemy_entity, enemy, enemy_health) in enemy_query.iter() {
+        // newly spawned enemies count as changed too, but they haven't been hit
+        if enemy_health.current == enemy_health.max || enemy_health.current == 0 {
+            continue;
+        }
+
+        let Some(color) = materials
+            .get(&shared_asset_handles.enemies[&enemy.enemy_type].material)
+            .map(|material| material.color)
+        else {
+            continue;
+        };
+
+        // the enemy gets its own material while flashing, so it doesn't flash every other enemy with it
+        commands.entity(enemy_entity).insert((
+            materials.add(ColorMaterial::from(ENEMY_HIT_FLASH_COLOR)),
+            AssetAnimator::new(
+                Tween::new(
+                    EaseFunction::QuadraticIn,
+                    ENEMY_HIT_FLASH_DURATION,
+                    ColorMaterialColorLens {
+                        start: ENEMY_HIT_FLASH_COLOR,
+                        end: color,
+                    },
+                )
+                .with_completed_event(ENEMY_HIT_FLASH_COMPLETE_EVENT_ID),
+            ),
+        ));
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn kill_enemy(
     enemy: &Enemy,
     enemy_entity: Entity,
     enemy_translation: Vec3,
     entities_to_despawn: &mut EntitiesToDespawn,
-    level: &mut Level,
     commands: &mut Commands,
     audio_assets: &AudioAssets,
+    volume_settings: &VolumeSettings,
     meshes: &mut Assets<Mesh>,
     materials: &mut Assets<ColorMaterial>,
+    enemy_killed_events: &mut EventWriter<EnemyKilled>,
+    shared_asset_handles: &SharedAssetHandles,
+    critical: bool,
 ) {
     entities_to_despawn.0.push(enemy_entity);
-    level.current_xp += enemy.xp_reward;
-    play_sound(audio_assets.hit.clone(), HIT_VOLUME, commands);
+    let xp_reward = if critical {
+        enemy.xp_reward * CRIT_XP_MULTIPLIER
+    } else {
+        enemy.xp_reward
+    };
+    spawn_xp_orb(
+        enemy_translation.xy(),
+        xp_reward,
+        commands,
+        shared_asset_handles,
+    );
+    enemy_killed_events.send(EnemyKilled {
+        enemy_type: enemy.enemy_type,
+        position: enemy_translation.truncate(),
+        xp_reward,
+        critical,
+    });
+    play_varied_sound(
+        audio_assets.hit.clone(),
+        HIT_VOLUME,
+        HIT_SPEED_VARIATION,
+        volume_settings,
+        commands,
+    );
+    let death_animation_size = if critical {
+        enemy.size * CRIT_DEATH_ANIMATION_SIZE_MULTIPLIER
+    } else {
+        enemy.size
+    };
+    spawn_death_animation(
+        death_animation_size,
+        enemy_translation,
+        commands,
+        meshes,
+        materials,
+    );
+
+    if enemy.enemy_type.always_drops_health_pickup()
+        || rand::thread_rng().gen_bool(HEALTH_PICKUP_DROP_CHANCE)
+    {
+        spawn_health_pickup(enemy_translation.xy(), commands, shared_asset_handles);
+    }
+}
+
+/// Drops an XP orb worth the provided amount of XP at the provided position
+fn spawn_xp_orb(
+    location: Vec2,
+    xp: u64,
+    commands: &mut Commands,
+    shared_asset_handles: &SharedAssetHandles,
+) {
+    commands
+        .spawn(MaterialMesh2dBundle {
+            mesh: shared_asset_handles.xp_orb_mesh.clone().into(),
+            material: shared_asset_handles.xp_orb_material.clone(),
+            transform: Transform::from_translation(location.extend(XP_ORB_Z)),
+            ..default()
+        })
+        .insert(GameComponent)
+        .insert(Collider::ball(XP_ORB_RADIUS))
+        .insert(Sensor)
+        .insert(XpOrb {
+            xp,
+            velocity: Vec2::ZERO,
+        });
+}
+
+/// Pulls XP orbs near the player in toward them, if they have the magnet perk
+fn move_xp_orbs(
+    player_query: Query<(&Transform, &Perks), With<Player>>,
+    mut orb_query: Query<(&mut Transform, &mut XpOrb), Without<Player>>,
+    time: Res<Time>,
+) {
+    let Some((player_transform, _)) = player_query
+        .iter()
+        .find(|(_, perks)| perks.0.contains(&PerkType::XpMagnet))
+    else {
+        return;
+    };
+    let player_position = player_transform.translation.xy();
+
+    for (mut transform, mut orb) in orb_query.iter_mut() {
+        let to_player = player_position - transform.translation.xy();
+        if to_player.length() > XP_MAGNET_RADIUS {
+            // the player got away, so the orb loses its momentum
+            orb.velocity = Vec2::ZERO;
+            continue;
+        }
+
+        orb.velocity +=
+            to_player.normalize_or_zero() * XP_MAGNET_ACCELERATION * time.delta_seconds();
+        transform.translation += (orb.velocity * time.delta_seconds()).extend(0.0);
+    }
+}
+
+/// Drops a health pickup at the provided position
+fn spawn_health_pickup(
+    location: Vec2,
+    commands: &mut Commands,
+    shared_asset_handles: &SharedAssetHandles,
+) {
+    commands
+        .spawn(MaterialMesh2dBundle {
+            mesh: shared_asset_handles.health_pickup_mesh.clone().into(),
+            material: shared_asset_handles.health_pickup_material.clone(),
+            transform: Transform::from_translation(location.extend(HEALTH_PICKUP_Z)),
+            ..default()
+        })
+        .insert(GameComponent)
+        .insert(Collider::ball(HEALTH_PICKUP_RADIUS))
+        .insert(Sensor)
+        .insert(HealthPickup);
+}
 
+/// Spawns the animation for an enemy dying
+fn spawn_death_animation(
+    size: f32,
+    translation: Vec3,
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+) {
     let scale_animation = Tween::new(
         EaseFunction::QuadraticOut,
         DEATH_ANIMATION_DURATION,
@@ -1939,9 +6220,9 @@ fn kill_enemy(
 
     commands
         .spawn(MaterialMesh2dBundle {
-            mesh: meshes.add(shape::Circle::new(enemy.size).into()).into(),
+            mesh: meshes.add(shape::Circle::new(size).into()).into(),
             material: materials.add(ColorMaterial::from(Color::WHITE)),
-            transform: Transform::from_translation(enemy_translation),
+            transform: Transform::from_translation(translation),
             ..default()
         })
         .insert(GameComponent)
@@ -1980,6 +6261,40 @@ fn update_level_display(
     }
 }
 
+/// Moves the XP bar towards how far the player is through the current level.
+/// It fills gradually so several kills at once don't make it jump, but empties right away on a level up.
+fn update_xp_bar(
+    level: Res<Level>,
+    mut fill_query: Query<(&mut Style, &mut XpBarFill)>,
+    time: Res<Time<Real>>,
+) {
+    let xp_this_level = level.current_xp.saturating_sub(level.previous_xp_needed);
+    let xp_needed_this_level = level.xp_needed.saturating_sub(level.previous_xp_needed);
+    let target_fraction = if xp_needed_this_level == 0 {
+        0.0
+    } else {
+        (xp_this_level as f32 / xp_needed_this_level as f32).min(1.0)
+    };
+
+    for (mut style, mut fill) in fill_query.iter_mut() {
+        if target_fraction < fill.shown_fraction {
+            fill.shown_fraction = target_fraction;
+        } else {
+            let catch_up = 1.0 - (-XP_BAR_FILL_RATE * time.delta_seconds()).exp();
+            fill.shown_fraction += (target_fraction - fill.shown_fraction) * catch_up;
+        }
+
+        style.width = Val::Percent(fill.shown_fraction * 100.0);
+    }
+}
+
+/// Keeps the window title showing the current level
+fn update_window_title(level: Res<Level>, mut window_query: Query<&mut Window>) {
+    for mut window in window_query.iter_mut() {
+        window.title = format!("{WINDOW_TITLE} — Level {}", level.current_level);
+    }
+}
+
 /// Keeps the health display up to date
 fn update_health_display(
     health: Res<Health>,
@@ -1990,6 +6305,32 @@ fn update_health_display(
     }
 }
 
+/// Keeps the run clock up to date
+fn update_run_clock_display(
+    run_stats: Res<RunStats>,
+    mut run_clock_text_query: Query<&mut Text, With<RunClockText>>,
+) {
+    for mut text in run_clock_text_query.iter_mut() {
+        text.sections[0].value = format_survival_time(run_stats.survival_time);
+    }
+}
+
+/// Keeps the wave display up to date
+fn update_wave_display(wave: Res<Wave>, mut wave_text_query: Query<&mut Text, With<WaveText>>) {
+    for mut text in wave_text_query.iter_mut() {
+        text.sections[0].value = format!("Wave {} - {} left", wave.number, wave.get_remaining());
+    }
+}
+
+/// Keeps track of how many enemies in the current wave have been killed
+fn count_wave_kills(mut enemy_killed_events: EventReader<EnemyKilled>, mut wave: ResMut<Wave>) {
+    for event in enemy_killed_events.read() {
+        if !event.enemy_type.is_boss() {
+            wave.killed += 1;
+        }
+    }
+}
+
 /// Keeps the enemy count display up to date
 fn update_enemy_count_display(
     enemy_query: Query<&Enemy>,
@@ -2001,52 +6342,416 @@ fn update_enemy_count_display(
     }
 }
 
-/// Handles making the game go in slow motion temporarily
-fn slow_mo(mut timer: ResMut<SlowMoTimer>, mut time: ResMut<Time<Virtual>>) {
-    timer.timer.tick(time.delta());
-    if timer.timer.paused() || timer.timer.finished() {
-        time.set_relative_speed(1.0);
+/// Slows things down for a moment when the player clears out the arena after it was full of enemies
+fn celebrate_clearing_enemies(
+    enemy_query: Query<(), With<Enemy>>,
+    mut busy_stretch: ResMut<BusyStretch>,
+    mut slow_mo_timer: ResMut<SlowMoTimer>,
+    mut announcement_queue: ResMut<AnnouncementQueue>,
+) {
+    let enemy_count = enemy_query.iter().count();
+    if enemy_count >= CLEARED_BUSY_ENEMY_COUNT {
+        busy_stretch.0 = true;
+    } else if busy_stretch.0 && enemy_count <= CLEARED_MAX_ENEMY_COUNT {
+        busy_stretch.0 = false;
+        slow_mo_timer.start(CLEARED_SLOW_MO_TIME_SCALE, CLEARED_SLOW_MO_TIME);
+        announce(CLEARED_ANNOUNCEMENT, &mut announcement_queue);
+    }
+}
+
+/// Periodically remembers the state of the game, so it can be rewound to later
+fn record_rewind_snapshot(
+    mut rewind_history: ResMut<RewindHistory>,
+    player_query: Query<&Transform, With<Player>>,
+    enemy_query: Query<(&Transform, &Enemy, &EnemyHealth)>,
+    health: Res<Health>,
+    level: Res<Level>,
+    time: Res<Time>,
+) {
+    rewind_history.timer.tick(time.delta());
+    if !rewind_history.timer.just_finished() {
+        return;
+    }
+
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+
+    let enemies = enemy_query
+        .iter()
+        .map(|(transform, enemy, enemy_health)| EnemySnapshot {
+            enemy_type: enemy.enemy_type,
+            transform: *transform,
+            damage: enemy.damage,
+            xp_reward: enemy.xp_reward,
+            max_speed: enemy.max_speed,
+            size: enemy.size,
+            clings: enemy.clings,
+            current_health: enemy_health.current,
+            max_health: enemy_health.max,
+        })
+        .collect();
+
+    rewind_history.snapshots.push_back(RewindSnapshot {
+        player_transform: *player_transform,
+        health: health.clone(),
+        level: level.clone(),
+        enemies,
+    });
+    if rewind_history.snapshots.len() > MAX_REWIND_SNAPSHOTS {
+        rewind_history.snapshots.pop_front();
+    }
+}
+
+/// Restores the oldest remembered state of the game, replacing all the current enemies with the ones from back then
+#[allow(clippy::too_many_arguments)]
+fn rewind(
+    mut commands: Commands,
+    mut rewind_history: ResMut<RewindHistory>,
+    mut player_query: Query<(&mut Transform, &mut Velocity), With<Player>>,
+    enemy_query: Query<Entity, With<Enemy>>,
+    mut health: ResMut<Health>,
+    mut level: ResMut<Level>,
+    mut entities_to_despawn: ResMut<EntitiesToDespawn>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    shared_asset_handles: Res<SharedAssetHandles>,
+    settings: Res<Settings>,
+) {
+    let Some(snapshot) = rewind_history.snapshots.pop_front() else {
+        info!("nothing to rewind to yet");
+        return;
+    };
+    info!(
+        "rewinding {:.2} seconds",
+        (rewind_history.snapshots.len() + 1) as f32 * REWIND_SNAPSHOT_INTERVAL.as_secs_f32()
+    );
+    // anything newer than the restored snapshot never happened now
+    rewind_history.snapshots.clear();
+    rewind_history.timer.reset();
+
+    for (mut transform, mut velocity) in player_query.iter_mut() {
+        *transform = snapshot.player_transform;
+        *velocity = Velocity::zero();
+    }
+    *health = snapshot.health;
+    *level = snapshot.level;
+
+    entities_to_despawn.0.extend(enemy_query.iter());
+    for enemy in snapshot.enemies {
+        let mut params = enemy.enemy_type.get_params();
+        params.damage = enemy.damage;
+        params.xp_reward = enemy.xp_reward;
+        params.max_speed = enemy.max_speed..=enemy.max_speed;
+        params.size = enemy.size..=enemy.size;
+        params.clings = enemy.clings;
+        params.health = enemy.max_health;
+
+        let enemy_entity = spawn_enemy(
+            &mut commands,
+            enemy.enemy_type,
+            enemy.transform.translation,
+            params,
+            &mut meshes,
+            &shared_asset_handles.enemies[&enemy.enemy_type],
+            &settings,
+        );
+        commands
+            .entity(enemy_entity)
+            .insert(enemy.transform)
+            .insert(EnemyHealth {
+                current: enemy.current_health,
+                max: enemy.max_health,
+                last_sword_swing: None,
+            });
+    }
+}
+
+/// Draws outlines around the play area and the areas enemies spawn in
+fn draw_spawn_areas(mut gizmos: Gizmos, spawn_areas: Res<SpawnAreas>) {
+    gizmos.rect_2d(Vec2::ZERO, 0.0, PLAY_AREA_SIZE, PLAY_AREA_OVERLAY_COLOR);
+
+    for spawn_area in spawn_areas.0.iter() {
+        gizmos.rect_2d(
+            spawn_area.center(),
+            0.0,
+            spawn_area.size(),
+            SPAWN_AREA_OVERLAY_COLOR,
+        );
+    }
+}
+
+/// Logs a warning whenever a frame takes longer than it should, along with what was going on that frame
+fn log_frame_time_spikes(
+    diagnostics: Res<DiagnosticsStore>,
+    enemy_query: Query<&Enemy>,
+    new_explosion_query: Query<(), Added<Explosion>>,
+    mut level_up_events: EventReader<LevelUp>,
+    mut show_perk_chooser_events: EventReader<ShowPerkChooser>,
+) {
+    // always drain the events, so old ones aren't counted towards a later spike
+    let leveled_up = level_up_events.read().count() > 0;
+    let showed_perk_chooser = show_perk_chooser_events.read().count() > 0;
+
+    let Some(frame_time_ms) = diagnostics
+        .get(FrameTimeDiagnosticsPlugin::FRAME_TIME)
+        .and_then(|frame_time| frame_time.value())
+    else {
+        return;
+    };
+
+    if frame_time_ms < FRAME_TIME_SPIKE_THRESHOLD_MS {
+        return;
+    }
+
+    warn!(
+        frame_time_ms,
+        enemies = enemy_query.iter().count(),
+        explosions_spawned = new_explosion_query.iter().count(),
+        leveled_up,
+        showed_perk_chooser,
+        "frame time spike"
+    );
+}
+
+/// Handles making the game go in slow motion.
+/// This is the only system that should set the speed of virtual time, so all sources of slow motion are combined here.
+fn slow_mo(
+    mut timer: ResMut<SlowMoTimer>,
+    bullet_time: Res<BulletTime>,
+    aiming: Res<AimingSecondaryAction>,
+    mut time: ResMut<Time<Virtual>>,
+    real_time: Res<Time<Real>>,
+) {
+    // the game being paused shouldn't eat into the slow-mo
+    if !time.is_paused() {
+        timer.timer.tick(real_time.delta());
+    }
+
+    let mut time_scale = 1.0;
+    if !timer.timer.paused() && !timer.timer.finished() {
+        time_scale = timer.target_time_scale;
+    }
+
+    if bullet_time.active {
+        time_scale = time_scale.min(BULLET_TIME_TIME_SCALE);
+    }
+
+    if aiming.0 {
+        time_scale = time_scale.min(AIM_SLOW_MO_TIME_SCALE);
+    }
+
+    time.set_relative_speed(time_scale);
+}
+
+/// Shows or hides the debug overlay
+fn toggle_debug_overlay(mut overlay_query: Query<&mut Visibility, With<DebugOverlayText>>) {
+    for mut visibility in overlay_query.iter_mut() {
+        *visibility = match *visibility {
+            Visibility::Hidden => Visibility::Inherited,
+            _ => Visibility::Hidden,
+        };
+    }
+}
+
+/// Keeps the debug overlay up to date with information about the player
+fn update_debug_overlay(
+    mut overlay_query: Query<(&mut Text, &Visibility), With<DebugOverlayText>>,
+    player_query: Query<(&Transform, &Velocity, &MaxSpeed, &Perks), With<Player>>,
+    spawn_timer: Res<SpawnTimer>,
+) {
+    let Ok((transform, velocity, max_speed, perks)) = player_query.get_single() else {
+        return;
+    };
+
+    for (mut text, visibility) in overlay_query.iter_mut() {
+        if *visibility == Visibility::Hidden {
+            continue;
+        }
+
+        text.sections[0].value = format!(
+            "Position: ({:.1}, {:.1})\nSpeed: {:.1}/{:.1}\nPerks: {}\nSpawn interval: {}ms",
+            transform.translation.x,
+            transform.translation.y,
+            velocity.linvel.length(),
+            max_speed.0,
+            perks.0.len(),
+            spawn_timer.0.duration().as_millis(),
+        );
+    }
+}
+
+/// Handles the player turning bullet time on and off
+fn toggle_bullet_time(mut bullet_time: ResMut<BulletTime>) {
+    if bullet_time.active {
+        bullet_time.active = false;
+    } else if bullet_time.charge > 0.0 {
+        bullet_time.active = true;
+    }
+}
+
+/// Handles charging and draining bullet time
+fn update_bullet_time(
+    mut bullet_time: ResMut<BulletTime>,
+    mut enemy_killed_events: EventReader<EnemyKilled>,
+    real_time: Res<Time<Real>>,
+) {
+    let kills = enemy_killed_events.read().count();
+
+    // real time is used so bullet time doesn't drain more slowly because of its own slow motion
+    let delta_seconds = real_time.delta_seconds();
+    let new_charge = if bullet_time.active {
+        bullet_time.charge - (BULLET_TIME_DRAIN_PER_SECOND * delta_seconds)
     } else {
-        time.set_relative_speed(timer.target_time_scale);
+        bullet_time.charge
+            + (BULLET_TIME_CHARGE_PER_SECOND * delta_seconds)
+            + (BULLET_TIME_CHARGE_PER_KILL * kills as f32)
+    };
+
+    bullet_time.charge = new_charge.clamp(0.0, BULLET_TIME_MAX_CHARGE);
+    if bullet_time.charge <= 0.0 {
+        bullet_time.active = false;
+    }
+}
+
+/// Keeps the bullet time meter up to date
+fn update_bullet_time_display(
+    bullet_time: Res<BulletTime>,
+    mut meter_query: Query<&mut Style, With<BulletTimeMeterFill>>,
+) {
+    for mut style in meter_query.iter_mut() {
+        style.width = Val::Percent(bullet_time.charge / BULLET_TIME_MAX_CHARGE * 100.0);
     }
 }
 
 /// Handles doing things when the player levels up
+#[allow(clippy::too_many_arguments)]
 fn level_up(
     mut level_up_events: EventReader<LevelUp>,
-    mut zoom: ResMut<ZoomLevel>,
-    mut time: ResMut<Time<Virtual>>,
-    mut player_query: Query<&Perks, With<Player>>,
-    mut perk_chooser_query: Query<&mut Visibility, (With<PerkChooser>, Without<ChoosePerkButton>)>,
-    mut perk_chooser_button_query: Query<&mut Visibility, With<ChoosePerkButton>>,
-    mut perk_text_query: Query<(&mut Text, &PerkText)>,
-    mut available_perks: ResMut<AvailablePerks>,
-    mut perk_chooser_timers: ResMut<PerkChooserDelayTimers>,
-    health: Res<Health>,
+    mut show_perk_chooser_events: EventWriter<ShowPerkChooser>,
+    mut target_zoom: ResMut<TargetZoomLevel>,
+    settings: Res<Settings>,
     mut commands: Commands,
     audio_assets: Res<AudioAssets>,
+    volume_settings: Res<VolumeSettings>,
+    mutators: Res<ActiveMutators>,
+    mut start_spawn_storm_events: EventWriter<StartSpawnStorm>,
+    spawn_mode: Res<SelectedSpawnMode>,
+    player_query: Query<&Transform, With<Player>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    shared_asset_handles: Res<SharedAssetHandles>,
 ) {
-    for _ in level_up_events.read() {
+    for level_up in level_up_events.read() {
         // play level up sound
         play_sound(
             audio_assets.level_up.clone(),
             LEVEL_UP_VOLUME,
+            &volume_settings,
             &mut commands,
         );
 
-        // zoom out a bit
-        let new_zoom = MAX_ZOOM_LEVEL.min(zoom.0 * ZOOM_LEVEL_MULTIPLIER);
-        zoom.0 = new_zoom;
+        if !settings.reduce_motion {
+            for player_transform in player_query.iter() {
+                spawn_level_up_burst(
+                    player_transform.translation.truncate(),
+                    &mut commands,
+                    &mut materials,
+                    &shared_asset_handles,
+                );
+            }
+        }
 
-        // pause the game
-        time.pause();
+        // zoom out a bit, unless zoom is being handled automatically
+        if !settings.auto_zoom {
+            target_zoom.0 = MAX_ZOOM_LEVEL.min(target_zoom.0 * ZOOM_LEVEL_MULTIPLIER);
+        }
+
+        // purist runs keep going without stopping to choose a perk
+        if !mutators.has(Mutator::Purist) {
+            show_perk_chooser_events.send(ShowPerkChooser);
+        }
+
+        // waves already come in bursts, so storms only happen with continuous spawning
+        if spawn_mode.0 == SpawnMode::Continuous
+            && level_up.new_level % SPAWN_STORM_LEVEL_INTERVAL == 0
+        {
+            start_spawn_storm_events.send(StartSpawnStorm);
+        }
+    }
+}
+
+/// Creates a harmless ring that expands out from the provided position and fades away, like an explosion
+fn spawn_level_up_burst(
+    location: Vec2,
+    commands: &mut Commands,
+    materials: &mut Assets<ColorMaterial>,
+    shared_asset_handles: &SharedAssetHandles,
+) {
+    let end_scale = LEVEL_UP_BURST_RADIUS / EXPLOSION_START_RADIUS;
+    let scale_animation = Tween::new(
+        EaseFunction::QuadraticOut,
+        LEVEL_UP_BURST_DURATION,
+        TransformScaleLens {
+            start: Vec3::ONE,
+            end: Vec3::new(end_scale, end_scale, 1.0),
+        },
+    );
+
+    let fade_animation = Tween::new(
+        EaseFunction::QuadraticIn,
+        LEVEL_UP_BURST_DURATION,
+        ColorMaterialColorLens {
+            start: LEVEL_UP_BURST_COLOR,
+            end: LEVEL_UP_BURST_COLOR.with_a(0.0),
+        },
+    )
+    .with_completed_event(LEVEL_UP_BURST_COMPLETE_EVENT_ID);
+
+    commands
+        .spawn(MaterialMesh2dBundle {
+            mesh: shared_asset_handles.explosion_mesh.clone().into(),
+            // needs its own material, since it gets faded out
+            material: materials.add(ColorMaterial::from(LEVEL_UP_BURST_COLOR)),
+            transform: Transform::from_translation(location.extend(LEVEL_UP_BURST_Z)),
+            ..default()
+        })
+        .insert(GameComponent)
+        .insert(LevelUpBurst)
+        .insert(Animator::new(scale_animation))
+        .insert(AssetAnimator::new(fade_animation));
+}
+
+/// Pauses the game and shows the perk chooser with a fresh set of perks
+#[allow(clippy::too_many_arguments)]
+fn show_perk_chooser(
+    mut show_perk_chooser_events: EventReader<ShowPerkChooser>,
+    mut time: ResMut<Time<Virtual>>,
+    mut player_query: Query<(&Perks, &CritChance), With<Player>>,
+    mut perk_chooser_query: Query<&mut Visibility, (With<PerkChooser>, Without<ChoosePerkButton>)>,
+    mut perk_chooser_button_query: Query<&mut Visibility, With<ChoosePerkButton>>,
+    mut perk_text_query: Query<(&mut Text, &PerkText)>,
+    mut available_perks: ResMut<AvailablePerks>,
+    mut perk_chooser_timers: ResMut<PerkChooserDelayTimers>,
+    mut idle_timer: ResMut<PerkChooserIdleTimer>,
+    health: Res<Health>,
+    mutators: Res<ActiveMutators>,
+    key_bindings: Res<KeyBindings>,
+) {
+    for _ in show_perk_chooser_events.read() {
+        // pause the game
+        time.pause();
 
         // display perk chooser
-        for perks in player_query.iter_mut() {
-            available_perks.0 =
-                PerkType::choose_random_perk_types(NUM_PERK_CHOICES, &perks.0, &health);
+        for (perks, crit_chance) in player_query.iter_mut() {
+            available_perks.0 = PerkType::choose_random_perk_types(
+                NUM_PERK_CHOICES,
+                &perks.0,
+                &health,
+                crit_chance,
+                &mutators,
+            );
             for (mut text, perk_text) in perk_text_query.iter_mut() {
-                let (name, desc) = available_perks.0[perk_text.0].get_name_and_description();
+                let (name, desc) =
+                    available_perks.0[perk_text.0].get_name_and_description(&key_bindings);
                 text.sections[0].value = name;
                 text.sections[2].value = desc;
             }
@@ -2064,31 +6769,615 @@ fn level_up(
         for timer in perk_chooser_timers.button_delays.iter_mut() {
             timer.reset();
         }
+
+        idle_timer.0.reset();
+    }
+}
+
+/// Destroys all enemies when the player levels up if they have the cleanse perk.
+/// XP is awarded for the destroyed enemies, but not enough to cause another level up (and another cleanse).
+#[allow(clippy::too_many_arguments)]
+fn cleanse_on_level_up(
+    mut level_up_events: EventReader<LevelUp>,
+    player_query: Query<&Perks, With<Player>>,
+    enemy_query: Query<(Entity, &Enemy, &Transform)>,
+    mut level: ResMut<Level>,
+    mut entities_to_despawn: ResMut<EntitiesToDespawn>,
+    mut commands: Commands,
+    audio_assets: Res<AudioAssets>,
+    volume_settings: Res<VolumeSettings>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    if level_up_events.read().count() == 0 {
+        return;
     }
+
+    if !player_query
+        .iter()
+        .any(|perks| perks.0.contains(&PerkType::Cleanse))
+    {
+        return;
+    }
+
+    let mut xp_gained = 0;
+    for (entity, enemy, transform) in enemy_query.iter() {
+        if entities_to_despawn.0.contains(&entity) {
+            continue;
+        }
+
+        entities_to_despawn.0.push(entity);
+        xp_gained += enemy.xp_reward;
+        spawn_death_animation(
+            enemy.size,
+            transform.translation,
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+        );
+    }
+
+    let max_xp = level.current_xp.max(level.xp_needed.saturating_sub(1));
+    level.current_xp = (level.current_xp + xp_gained).min(max_xp);
+
+    play_sound(
+        audio_assets.explosion.clone(),
+        EXPLOSION_VOLUME,
+        &volume_settings,
+        &mut commands,
+    );
+}
+
+/// Sets the player's attack combo back when they get hit, so they can't just tank hits to keep it going
+fn break_combo_on_hit(
+    mut commands: Commands,
+    mut player_hit_events: EventReader<PlayerHit>,
+    mut player_query: Query<(&mut AttackCombo, &Transform), With<Player>>,
+    settings: Res<Settings>,
+    asset_server: Res<AssetServer>,
+) {
+    if player_hit_events.read().count() == 0 {
+        return;
+    }
+
+    let penalty = if settings.lenient_combos {
+        LENIENT_COMBO_HIT_PENALTY
+    } else {
+        COMBO_HIT_PENALTY
+    };
+
+    for (mut combo, transform) in player_query.iter_mut() {
+        let new_next_swing = penalty.apply(combo.next_swing);
+        if new_next_swing == combo.next_swing {
+            // nothing was lost
+            continue;
+        }
+        combo.next_swing = new_next_swing;
+
+        let fade_out = Tween::new(
+            EaseFunction::QuadraticIn,
+            COMBO_LOST_DURATION,
+            TextColorLens {
+                start: COMBO_LOST_COLOR,
+                end: COMBO_LOST_COLOR.with_a(0.0),
+                section: 0,
+            },
+        )
+        .with_completed_event(COMBO_LOST_COMPLETE_EVENT_ID);
+
+        let position = transform.translation.xy() + Vec2::new(0.0, COMBO_LOST_OFFSET);
+        commands
+            .spawn(Text2dBundle {
+                text: Text::from_section(
+                    COMBO_LOST_TEXT,
+                    TextStyle {
+                        font: asset_server.load(MAIN_FONT),
+                        font_size: COMBO_LOST_FONT_SIZE,
+                        color: COMBO_LOST_COLOR,
+                    },
+                ),
+                transform: Transform::from_translation(position.extend(COMBO_LOST_Z))
+                    .with_scale(Vec3::splat(COMBO_LOST_SCALE)),
+                ..default()
+            })
+            .insert(GameComponent)
+            .insert(ComboLostText)
+            .insert(Animator::new(fade_out));
+    }
+}
+
+/// Shows how much XP each killed enemy was worth, floating up from where it died
+fn spawn_xp_popups(
+    mut commands: Commands,
+    mut enemy_killed_events: EventReader<EnemyKilled>,
+    settings: Res<Settings>,
+    asset_server: Res<AssetServer>,
+) {
+    for event in enemy_killed_events.read() {
+        let color = if event.critical {
+            CRIT_XP_POPUP_COLOR
+        } else {
+            XP_POPUP_COLOR
+        };
+        let fade_out = Tween::new(
+            EaseFunction::QuadraticIn,
+            XP_POPUP_DURATION,
+            TextColorLens {
+                start: color,
+                end: color.with_a(0.0),
+                section: 0,
+            },
+        )
+        .with_completed_event(XP_POPUP_COMPLETE_EVENT_ID);
+
+        let start = event.position.extend(XP_POPUP_Z);
+        let mut popup = commands.spawn(Text2dBundle {
+            text: Text::from_section(
+                format!("+{}", event.xp_reward),
+                TextStyle {
+                    font: asset_server.load(MAIN_FONT),
+                    font_size: XP_POPUP_FONT_SIZE,
+                    color,
+                },
+            ),
+            transform: Transform::from_translation(start).with_scale(Vec3::splat(XP_POPUP_SCALE)),
+            ..default()
+        });
+        popup
+            .insert(GameComponent)
+            .insert(XpPopup)
+            .insert(Animator::new(fade_out));
+
+        // with reduced motion, the popup just fades out where it is
+        if !settings.reduce_motion {
+            popup.insert(Animator::new(Tween::new(
+                EaseFunction::QuadraticOut,
+                XP_POPUP_DURATION,
+                TransformPositionLens {
+                    start,
+                    end: start + Vec3::new(0.0, XP_POPUP_RISE, 0.0),
+                },
+            )));
+        }
+    }
+}
+
+/// Shows feedback when the player gets hit
+fn show_damage_indicator(
+    mut player_hit_events: EventReader<PlayerHit>,
+    mut indicator_query: Query<(Entity, &mut DamageIndicator, &mut BorderColor)>,
+    settings: Res<Settings>,
+    time: Res<Time<Real>>,
+    mut commands: Commands,
+) {
+    let was_hit = player_hit_events.read().count() > 0;
+    for (entity, mut indicator, mut border_color) in indicator_query.iter_mut() {
+        if was_hit {
+            if settings.reduce_motion {
+                // show a steady border instead of a flash
+                indicator.border_timer.reset();
+            } else {
+                commands.entity(entity).insert(Animator::new(Tween::new(
+                    EaseFunction::QuadraticIn,
+                    DAMAGE_FLASH_DURATION,
+                    UiBackgroundColorLens {
+                        start: DAMAGE_FLASH_COLOR,
+                        end: DAMAGE_FLASH_COLOR.with_a(0.0),
+                    },
+                )));
+            }
+        }
+
+        indicator.border_timer.tick(time.delta());
+        if indicator.border_timer.finished() {
+            border_color.0 = Color::NONE;
+        } else {
+            border_color.0 = DAMAGE_BORDER_COLOR;
+        }
+    }
+}
+
+/// Shows the next queued announcement once the current one is done
+fn show_announcements(
+    mut commands: Commands,
+    mut announcement_queue: ResMut<AnnouncementQueue>,
+    announcements_query: Query<(), With<Announcement>>,
+    settings: Res<Settings>,
+    asset_server: Res<AssetServer>,
+) {
+    if !announcements_query.is_empty() {
+        return;
+    }
+
+    let Some(text) = announcement_queue.0.pop_front() else {
+        return;
+    };
+
+    let fade_in = Tween::new(
+        EaseFunction::QuadraticOut,
+        ANNOUNCEMENT_FADE_DURATION,
+        TextColorLens {
+            start: ANNOUNCEMENT_COLOR.with_a(0.0),
+            end: ANNOUNCEMENT_COLOR,
+            section: 0,
+        },
+    );
+    let fade_out = Tween::new(
+        EaseFunction::QuadraticIn,
+        ANNOUNCEMENT_FADE_DURATION,
+        TextColorLens {
+            start: ANNOUNCEMENT_COLOR,
+            end: ANNOUNCEMENT_COLOR.with_a(0.0),
+            section: 0,
+        },
+    )
+    .with_completed_event(ANNOUNCEMENT_COMPLETE_EVENT_ID);
+
+    let mut entity_commands = commands.spawn(
+        TextBundle::from_section(
+            text,
+            TextStyle {
+                font: asset_server.load(TITLE_FONT),
+                font_size: ANNOUNCEMENT_FONT_SIZE,
+                color: ANNOUNCEMENT_COLOR.with_a(0.0),
+            },
+        )
+        .with_text_alignment(TextAlignment::Center)
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(ANNOUNCEMENT_END_TOP),
+            margin: UiRect {
+                left: Val::Auto,
+                right: Val::Auto,
+                ..default()
+            },
+            ..default()
+        }),
+    );
+    entity_commands
+        .insert(GameComponent)
+        .insert(Announcement)
+        .insert(Animator::new(
+            fade_in
+                .then(Delay::new(ANNOUNCEMENT_HOLD_DURATION))
+                .then(fade_out),
+        ));
+
+    if !settings.reduce_motion {
+        entity_commands.insert(Animator::new(Tween::new(
+            EaseFunction::QuadraticOut,
+            ANNOUNCEMENT_FADE_DURATION,
+            UiPositionLens {
+                start: UiRect {
+                    top: Val::Percent(ANNOUNCEMENT_START_TOP),
+                    ..UiRect::all(Val::Auto)
+                },
+                end: UiRect {
+                    top: Val::Percent(ANNOUNCEMENT_END_TOP),
+                    ..UiRect::all(Val::Auto)
+                },
+            },
+        )));
+    }
+}
+
+/// Checks if the player is dead, and ends the game or knocks them down if they are
+fn check_for_death(
+    mut next_state: ResMut<NextState<GameState>>,
+    health: Res<Health>,
+    mut downed: ResMut<Downed>,
+    selected_difficulty: Res<SelectedDifficulty>,
+    mut announcement_queue: ResMut<AnnouncementQueue>,
+) {
+    if health.current_health > 0 || downed.active {
+        return;
+    }
+
+    if selected_difficulty.0.allows_downed() && !downed.used {
+        downed.active = true;
+        downed.used = true;
+        downed.timer.reset();
+        announce(DOWNED_ANNOUNCEMENT, &mut announcement_queue);
+    } else {
+        next_state.set(GameState::GameOver);
+    }
+}
+
+/// Once the player has been downed for long enough, gets them back up if they've cleared the enemies around them, or ends the game if they haven't.
+/// Healing while downed gets the player back up right away.
+fn update_downed(
+    mut downed: ResMut<Downed>,
+    mut health: ResMut<Health>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut announcement_queue: ResMut<AnnouncementQueue>,
+    player_query: Query<&Transform, With<Player>>,
+    enemy_query: Query<&Transform, With<Enemy>>,
+    time: Res<Time>,
+) {
+    if !downed.active {
+        return;
+    }
+
+    downed.timer.tick(time.delta());
+    if !downed.timer.finished() && health.current_health == 0 {
+        return;
+    }
+
+    let area_cleared = player_query.iter().all(|player_transform| {
+        enemy_query.iter().all(|enemy_transform| {
+            enemy_transform
+                .translation
+                .xy()
+                .distance(player_transform.translation.xy())
+                > DOWNED_REVIVE_RADIUS
+        })
+    });
+
+    if area_cleared || health.current_health > 0 {
+        downed.active = false;
+        let revive_health =
+            (health.max_health as f64 * DOWNED_REVIVE_HEALTH_FRACTION).round() as u64;
+        health.current_health = health.current_health.max(revive_health).max(1);
+        announce(REVIVED_ANNOUNCEMENT, &mut announcement_queue);
+    } else {
+        next_state.set(GameState::GameOver);
+    }
+}
+
+/// Handles pausing and unpausing the game
+fn toggle_pause(
+    mut time: ResMut<Time<Virtual>>,
+    perk_chooser_query: Query<&Visibility, With<PerkChooser>>,
+) {
+    // the perk chooser has its own pause, which only choosing a perk should end
+    let choosing_perk = perk_chooser_query
+        .iter()
+        .any(|visibility| *visibility != Visibility::Hidden);
+    if choosing_perk {
+        return;
+    }
+
+    if time.is_paused() {
+        time.unpause();
+    } else {
+        time.pause();
+    }
+}
+
+/// Spawns a button in the pause menu
+fn spawn_pause_menu_button(
+    text: &str,
+    marker: impl Component,
+    parent: &mut ChildBuilder,
+    theme: &Theme,
+    asset_server: &AssetServer,
+) {
+    parent
+        .spawn(ButtonBundle {
+            style: Style {
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                padding: UiRect::all(Val::Px(10.0)),
+                margin: UiRect::vertical(Val::Px(10.0)),
+                ..default()
+            },
+            background_color: theme.normal_button.into(),
+            ..default()
+        })
+        .insert(marker)
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                text,
+                TextStyle {
+                    font: asset_server.load(MAIN_FONT),
+                    font_size: 30.0,
+                    color: theme.button_text,
+                },
+            ));
+        });
+}
+
+type InteractedResumeButtonTuple = (Changed<Interaction>, With<ResumeButton>);
+
+/// Handles the resume button in the pause menu.
+fn resume_button_system(
+    interaction_query: Query<&Interaction, InteractedResumeButtonTuple>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    for interaction in interaction_query.iter() {
+        if *interaction == Interaction::Pressed {
+            time.unpause();
+        }
+    }
+}
+
+type InteractedQuitToMenuButtonTuple = (Changed<Interaction>, With<QuitToMenuButton>);
+
+/// Handles the quit to menu button in the pause menu.
+fn quit_to_menu_button_system(
+    interaction_query: Query<&Interaction, InteractedQuitToMenuButtonTuple>,
+    mut time: ResMut<Time<Virtual>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for interaction in interaction_query.iter() {
+        if *interaction == Interaction::Pressed {
+            // don't leave time stopped for whatever comes next
+            time.unpause();
+            next_state.set(GameState::Menu);
+        }
+    }
+}
+
+/// Shows the pause menu while the game is paused, unless it's paused for the perk chooser
+fn update_pause_menu(
+    time: Res<Time<Virtual>>,
+    mut pause_menu_query: Query<&mut Visibility, (With<PauseMenu>, Without<PerkChooser>)>,
+    perk_chooser_query: Query<&Visibility, With<PerkChooser>>,
+) {
+    let choosing_perk = perk_chooser_query
+        .iter()
+        .any(|visibility| *visibility != Visibility::Hidden);
+    for mut visibility in pause_menu_query.iter_mut() {
+        *visibility = if time.is_paused() && !choosing_perk {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+type InteractedRespecButtonTuple = (Changed<Interaction>, With<RespecButton>);
+
+type RespeccingPlayerTuple = (
+    &'static BaseStats,
+    &'static mut AttackCooldown,
+    &'static mut MaxSpeed,
+    &'static mut HealthRegen,
+    &'static mut SecondaryAction,
+    &'static mut Retaliate,
+    &'static mut SwordBeam,
+    &'static mut LingeringArc,
+    &'static mut CritChance,
+    &'static mut Perks,
+);
+
+/// Handles the respec button in the pause menu.
+/// All the player's perks are undone in exchange for their progress towards the next level, and the perk choices they've made are offered to them again.
+#[allow(clippy::too_many_arguments)]
+fn respec_button_system(
+    interaction_query: Query<&Interaction, InteractedRespecButtonTuple>,
+    mut time: ResMut<Time<Virtual>>,
+    mut player_query: Query<RespeccingPlayerTuple, With<Player>>,
+    mut sword_pivot_query: Query<
+        (
+            &BaseSwordAnimationParams,
+            &mut SwordAnimationParams,
+            &mut Animator<Transform>,
+        ),
+        With<SwordPivot>,
+    >,
+    mut health: ResMut<Health>,
+    mut enemy_speed_multiplier: ResMut<EnemySpeedMultiplier>,
+    mut level: ResMut<Level>,
+    mut pending_perk_choices: ResMut<PendingPerkChoices>,
+    boss_perk_choices: Res<BossPerkChoices>,
+) {
+    if !interaction_query
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        return;
+    }
+
+    // a perk is chosen on every level up and every boss kill, so there's nothing to refund before the first one
+    let earned_perk_choices = level.current_level - 1 + boss_perk_choices.0;
+    if earned_perk_choices == 0 {
+        return;
+    }
+
+    for (
+        base_stats,
+        mut cooldown,
+        mut max_speed,
+        mut health_regen,
+        mut secondary_action,
+        mut retaliate,
+        mut sword_beam,
+        mut lingering_arc,
+        mut crit_chance,
+        mut perks,
+    ) in player_query.iter_mut()
+    {
+        cooldown.0.set_duration(base_stats.attack_cooldown);
+        max_speed.0 = base_stats.max_speed;
+        secondary_action.0 = base_stats.secondary_action.clone();
+        *health_regen = base_stats.health_regen.clone();
+        retaliate.0 = base_stats.retaliate;
+        sword_beam.0 = base_stats.sword_beam;
+        lingering_arc.0 = base_stats.lingering_arc;
+        crit_chance.0 = base_stats.crit_chance;
+        perks.0 = base_stats.perks.clone();
+
+        health.max_health = base_stats.max_health;
+        health.current_health = health.current_health.min(health.max_health);
+        enemy_speed_multiplier.0 = base_stats.enemy_speed_multiplier;
+    }
+
+    for (base_params, mut swing_params, mut animator) in sword_pivot_query.iter_mut() {
+        *swing_params = base_params.0.clone();
+        *animator = Animator::new(build_sword_animation(&swing_params)).with_state(animator.state);
+    }
+
+    level.current_xp = level.previous_xp_needed;
+    // choices that are still pending were never taken, so only refund the rest
+    pending_perk_choices.0 += earned_perk_choices.saturating_sub(pending_perk_choices.0);
+
+    time.unpause();
 }
 
-/// Checks if the player is dead, and ends the game if they are
-fn check_for_death(mut next_state: ResMut<NextState<GameState>>, health: Res<Health>) {
-    if health.current_health == 0 {
-        next_state.set(GameState::GameOver);
+/// Offers the player one of their pending perk choices, if there are any left
+fn offer_pending_perk_choice(
+    mut pending_perk_choices: ResMut<PendingPerkChoices>,
+    mut show_perk_chooser_events: EventWriter<ShowPerkChooser>,
+    mutators: Res<ActiveMutators>,
+) {
+    // purist runs don't get perks from anywhere
+    if mutators.has(Mutator::Purist) {
+        return;
+    }
+
+    if pending_perk_choices.0 > 0 {
+        pending_perk_choices.0 -= 1;
+        show_perk_chooser_events.send(ShowPerkChooser);
     }
 }
 
-/// Handles pausing and unpausing the game
-fn toggle_pause(mut time: ResMut<Time<Virtual>>) {
-    if time.is_paused() {
-        time.unpause();
-    } else {
-        time.pause();
+/// Gives the player an extra perk choice for every boss they kill
+fn grant_perk_choice_for_boss_kills(
+    mut enemy_killed_events: EventReader<EnemyKilled>,
+    mut pending_perk_choices: ResMut<PendingPerkChoices>,
+    mut boss_perk_choices: ResMut<BossPerkChoices>,
+    mutators: Res<ActiveMutators>,
+) {
+    for event in enemy_killed_events.read() {
+        // purist runs don't get perks from anywhere
+        if event.enemy_type == EnemyType::Boss && !mutators.has(Mutator::Purist) {
+            pending_perk_choices.0 += 1;
+            boss_perk_choices.0 += 1;
+        }
     }
 }
 
-/// Handles showing the perk chooser buttons after a delay
+/// Handles showing the perk chooser buttons after a delay, or right away if the player presses something
 fn show_perk_chooser_buttons(
     time: Res<Time<Real>>,
     mut perk_chooser_timers: ResMut<PerkChooserDelayTimers>,
     mut perk_chooser_button_query: Query<(&mut Visibility, &ChoosePerkButton)>,
+    perk_chooser_query: Query<&Visibility, (With<PerkChooser>, Without<ChoosePerkButton>)>,
+    keycode: Res<Input<KeyCode>>,
+    mouse_buttons: Res<Input<MouseButton>>,
 ) {
+    let chooser_visible = perk_chooser_query
+        .iter()
+        .any(|visibility| *visibility != Visibility::Hidden);
+    let skip_requested = keycode.get_just_pressed().next().is_some()
+        || mouse_buttons.get_just_pressed().next().is_some();
+    if chooser_visible && skip_requested {
+        // the player doesn't want to wait, so finish all the delays and show every button right away
+        let remaining = perk_chooser_timers.initial_delay.remaining();
+        perk_chooser_timers.initial_delay.tick(remaining);
+        for timer in perk_chooser_timers.button_delays.iter_mut() {
+            let remaining = timer.remaining();
+            timer.tick(remaining);
+        }
+
+        for (mut visibility, _) in perk_chooser_button_query.iter_mut() {
+            *visibility = Visibility::Inherited;
+        }
+
+        return;
+    }
+
     perk_chooser_timers.initial_delay.tick(time.delta());
     if perk_chooser_timers.initial_delay.finished() {
         let mut to_become_visible = Vec::new();
@@ -2107,84 +7396,405 @@ fn show_perk_chooser_buttons(
     }
 }
 
-/// Handles interactions with the perk chooser buttons.
+/// Keeps track of how long the player has gone without touching anything while the perk chooser is open
+fn update_perk_chooser_idle_timer(
+    time: Res<Time<Real>>,
+    mut idle_timer: ResMut<PerkChooserIdleTimer>,
+    perk_chooser_query: Query<&Visibility, (With<PerkChooser>, Without<ChoosePerkButton>)>,
+    keycode: Res<Input<KeyCode>>,
+    mouse_buttons: Res<Input<MouseButton>>,
+    mut cursor_moved_events: EventReader<CursorMoved>,
+) {
+    let cursor_moved = cursor_moved_events.read().count() > 0;
+    let chooser_hidden = perk_chooser_query
+        .iter()
+        .all(|visibility| *visibility == Visibility::Hidden);
+
+    if chooser_hidden
+        || cursor_moved
+        || keycode.get_pressed().next().is_some()
+        || mouse_buttons.get_pressed().next().is_some()
+    {
+        idle_timer.0.reset();
+        return;
+    }
+
+    idle_timer.0.tick(time.delta());
+}
+
+/// Pulses the prompt on the perk chooser, and warns the player if a perk is about to be chosen for them
+fn update_perk_chooser_prompt(
+    time: Res<Time<Real>>,
+    idle_timer: Res<PerkChooserIdleTimer>,
+    settings: Res<Settings>,
+    mut prompt_query: Query<&mut Text, With<PerkChooserPrompt>>,
+) {
+    let alpha = if settings.reduce_motion {
+        1.0
+    } else {
+        let phase = time.elapsed_seconds() / PERK_CHOOSER_PROMPT_PULSE_PERIOD.as_secs_f32();
+        let pulse = (1.0 + (phase * 2.0 * PI).cos()) / 2.0;
+        PERK_CHOOSER_PROMPT_MIN_ALPHA + (1.0 - PERK_CHOOSER_PROMPT_MIN_ALPHA) * pulse
+    };
+
+    let remaining = idle_timer.0.remaining();
+    let prompt = if settings.auto_choose_perk && remaining <= PERK_CHOOSER_IDLE_WARNING_TIME {
+        format!(
+            "Choose a perk (the first one will be chosen in {} seconds)",
+            remaining.as_secs_f32().ceil()
+        )
+    } else {
+        PERK_CHOOSER_PROMPT.to_string()
+    };
+
+    for mut text in prompt_query.iter_mut() {
+        text.sections[0].style.color = PERK_CHOOSER_PROMPT_COLOR.with_a(alpha);
+        if text.sections[0].value != prompt {
+            text.sections[0].value = prompt.clone();
+        }
+    }
+}
+
+type PerkChoosingPlayerTuple = (
+    &'static mut AttackCooldown,
+    &'static mut MaxSpeed,
+    &'static mut HealthRegen,
+    &'static mut SecondaryAction,
+    &'static mut Retaliate,
+    &'static mut SwordBeam,
+    &'static mut LingeringArc,
+    &'static mut CritChance,
+    &'static mut Perks,
+);
+
+/// Handles interactions with the perk chooser buttons, and choosing a perk automatically if the player is idle.
+#[allow(clippy::too_many_arguments)]
 fn choose_perk(
     mut time: ResMut<Time<Virtual>>,
     interaction_query: Query<(&Interaction, &ChoosePerkButton), Changed<Interaction>>,
+    keycode: Res<Input<KeyCode>>,
     mut perk_chooser_query: Query<&mut Visibility, With<PerkChooser>>,
     available_perks: Res<AvailablePerks>,
-    mut player_query: Query<
-        (
-            &mut AttackCooldown,
-            &mut MaxSpeed,
-            &mut HealthRegen,
-            &mut SecondaryAction,
-            &mut Retaliate,
-            &mut Perks,
-        ),
-        With<Player>,
-    >,
+    mut player_query: Query<PerkChoosingPlayerTuple, With<Player>>,
     mut sword_pivot_query: Query<
         (&mut SwordAnimationParams, &mut Animator<Transform>),
         With<SwordPivot>,
     >,
     mut health: ResMut<Health>,
     mut enemy_speed_multiplier: ResMut<EnemySpeedMultiplier>,
+    idle_timer: Res<PerkChooserIdleTimer>,
+    settings: Res<Settings>,
 ) {
-    for (interaction, button) in interaction_query.iter() {
-        if *interaction == Interaction::Pressed {
-            let chosen_perk = available_perks.0[button.0];
-
-            for (
-                mut cooldown,
-                mut max_speed,
-                mut health_regen,
-                mut secondary_action,
-                mut retaliate,
-                mut perks,
-            ) in player_query.iter_mut()
-            {
-                match chosen_perk {
-                    PerkType::LongerSword => activate_longer_sword(&mut sword_pivot_query),
-                    PerkType::WiderSwordSwing => activate_wider_sword_swing(&mut sword_pivot_query),
-                    PerkType::ShorterAttackCooldown => {
-                        activate_shorter_attack_cooldown(&mut cooldown)
-                    }
-                    PerkType::HigherMaxSpeed => activate_higher_max_speed(&mut max_speed),
-                    PerkType::HigherMaxHealth => activate_higher_max_health(&mut health),
-                    PerkType::Heal => activate_heal(&mut health),
-                    PerkType::UnlockGrenade => activate_unlock_grenade(&mut secondary_action),
-                    PerkType::LargerGrenadeExplosion => {
-                        activate_larger_grenade_explosion(&mut secondary_action)
-                    }
-                    PerkType::ShorterGrenadeCooldown => {
-                        activate_shorter_grenade_cooldown(&mut secondary_action)
-                    }
-                    PerkType::UnlockTeleport => activate_unlock_teleport(&mut secondary_action),
-                    PerkType::ShorterTeleportCooldown => {
-                        activate_shorter_teleport_cooldown(&mut secondary_action)
-                    }
-                    PerkType::UnlockTeleportExplosion => {
-                        activate_unlock_teleport_explosion(&mut secondary_action)
-                    }
-                    PerkType::LargerTeleportExplosion => {
-                        activate_larger_teleport_explosion(&mut secondary_action)
-                    }
-                    PerkType::UnlockHealthRegen => activate_unlock_health_regen(&mut health_regen),
-                    PerkType::FasterHealthRegen => activate_faster_health_regen(&mut health_regen),
-                    PerkType::Retaliate => activate_retaliate(&mut retaliate),
-                    PerkType::SlowerEnemies => activate_slower_enemies(&mut enemy_speed_multiplier),
-                }
+    let pressed_index = interaction_query
+        .iter()
+        .find(|(interaction, _)| **interaction == Interaction::Pressed)
+        .map(|(_, button)| button.0);
+    let chooser_visible = perk_chooser_query
+        .iter()
+        .any(|visibility| *visibility != Visibility::Hidden);
+    let key_pressed_index = PERK_CHOICE_INPUTS
+        .iter()
+        .position(|input| keycode.just_pressed(*input))
+        .filter(|index| chooser_visible && *index < available_perks.0.len());
+    // if the player has wandered off, pick the first perk for them if they've asked for that
+    let auto_chosen_index =
+        (settings.auto_choose_perk && idle_timer.0.just_finished()).then_some(0);
+
+    if let Some(chosen_index) = pressed_index.or(key_pressed_index).or(auto_chosen_index) {
+        let chosen_perk = available_perks.0[chosen_index];
+
+        for (
+            mut cooldown,
+            mut max_speed,
+            mut health_regen,
+            mut secondary_action,
+            mut retaliate,
+            mut sword_beam,
+            mut lingering_arc,
+            mut crit_chance,
+            mut perks,
+        ) in player_query.iter_mut()
+        {
+            activate_perk(
+                chosen_perk,
+                &mut cooldown,
+                &mut max_speed,
+                &mut health_regen,
+                &mut secondary_action,
+                &mut retaliate,
+                &mut sword_beam,
+                &mut lingering_arc,
+                &mut crit_chance,
+                &mut sword_pivot_query,
+                &mut health,
+                &mut enemy_speed_multiplier,
+            );
+            perks.0.insert(chosen_perk);
+        }
 
-                perks.0.insert(chosen_perk);
-            }
+        for mut visibility in perk_chooser_query.iter_mut() {
+            *visibility = Visibility::Hidden;
+        }
+
+        time.unpause();
+    }
+}
+
+/// Applies the effects of a perk to the player
+#[allow(clippy::too_many_arguments)]
+fn activate_perk(
+    perk: PerkType,
+    cooldown: &mut AttackCooldown,
+    max_speed: &mut MaxSpeed,
+    health_regen: &mut HealthRegen,
+    secondary_action: &mut SecondaryAction,
+    retaliate: &mut Retaliate,
+    sword_beam: &mut SwordBeam,
+    lingering_arc: &mut LingeringArc,
+    crit_chance: &mut CritChance,
+    sword_pivot_query: &mut Query<
+        (&mut SwordAnimationParams, &mut Animator<Transform>),
+        With<SwordPivot>,
+    >,
+    health: &mut Health,
+    enemy_speed_multiplier: &mut EnemySpeedMultiplier,
+) {
+    match perk {
+        PerkType::LongerSword => activate_longer_sword(sword_pivot_query),
+        PerkType::WiderSwordSwing => activate_wider_sword_swing(sword_pivot_query),
+        PerkType::ShorterAttackCooldown => activate_shorter_attack_cooldown(cooldown),
+        PerkType::HigherMaxSpeed => activate_higher_max_speed(max_speed),
+        PerkType::HigherMaxHealth => activate_higher_max_health(health),
+        PerkType::Heal => activate_heal(health),
+        PerkType::UnlockGrenade => activate_unlock_grenade(secondary_action),
+        PerkType::LargerGrenadeExplosion => activate_larger_grenade_explosion(secondary_action),
+        PerkType::ShorterGrenadeCooldown => activate_shorter_grenade_cooldown(secondary_action),
+        PerkType::UnlockTeleport => activate_unlock_teleport(secondary_action),
+        PerkType::ShorterTeleportCooldown => activate_shorter_teleport_cooldown(secondary_action),
+        PerkType::UnlockTeleportExplosion => activate_unlock_teleport_explosion(secondary_action),
+        PerkType::LargerTeleportExplosion => activate_larger_teleport_explosion(secondary_action),
+        PerkType::UnlockHealthRegen => activate_unlock_health_regen(health_regen),
+        PerkType::FasterHealthRegen => activate_faster_health_regen(health_regen),
+        PerkType::Retaliate => activate_retaliate(retaliate),
+        PerkType::SlowerEnemies => activate_slower_enemies(enemy_speed_multiplier),
+        // handled when leveling up
+        PerkType::Cleanse => (),
+        PerkType::SwordBeam => activate_sword_beam(sword_beam),
+        PerkType::UnlockSlowField => activate_unlock_slow_field(secondary_action),
+        PerkType::LingeringArc => activate_lingering_arc(lingering_arc),
+        PerkType::UnlockDash => activate_unlock_dash(secondary_action),
+        // handled when moving XP orbs
+        PerkType::XpMagnet => (),
+        PerkType::CriticalHits => activate_critical_hits(crit_chance),
+    }
+}
+
+type BuildImportingPlayerTuple = (
+    &'static mut BaseStats,
+    &'static mut AttackCooldown,
+    &'static mut MaxSpeed,
+    &'static mut HealthRegen,
+    &'static mut SecondaryAction,
+    &'static mut Retaliate,
+    &'static mut SwordBeam,
+    &'static mut LingeringArc,
+    &'static mut CritChance,
+    &'static mut Perks,
+);
+
+type SwordPivotAnimationTuple = (
+    &'static mut SwordAnimationParams,
+    &'static mut Animator<Transform>,
+);
+
+type BaseSwordAnimationTuple = (
+    &'static mut BaseSwordAnimationParams,
+    &'static SwordAnimationParams,
+);
+
+type SwordAnimationQueries<'w, 's> = ParamSet<
+    'w,
+    's,
+    (
+        Query<'static, 'static, SwordPivotAnimationTuple, With<SwordPivot>>,
+        Query<'static, 'static, BaseSwordAnimationTuple>,
+    ),
+>;
+
+/// Gives the player the perks from an imported build at the start of a run.
+/// The perks count as part of the player's starting stats, so respeccing doesn't take them away.
+fn apply_imported_build(
+    mut imported_build: ResMut<ImportedBuild>,
+    mut imported_build_run: ResMut<ImportedBuildRun>,
+    mutators: Res<ActiveMutators>,
+    mut player_query: Query<BuildImportingPlayerTuple, With<Player>>,
+    mut sword_pivot_queries: SwordAnimationQueries,
+    mut health: ResMut<Health>,
+    mut enemy_speed_multiplier: ResMut<EnemySpeedMultiplier>,
+) {
+    // the build is only used for the run right after it's imported
+    let Some(build) = imported_build.0.take() else {
+        return;
+    };
 
-            for mut visibility in perk_chooser_query.iter_mut() {
-                *visibility = Visibility::Hidden;
+    if mutators.has(Mutator::Purist) {
+        // purist runs don't get perks from anywhere
+        return;
+    }
+
+    imported_build_run.0 = true;
+
+    for (
+        mut base_stats,
+        mut cooldown,
+        mut max_speed,
+        mut health_regen,
+        mut secondary_action,
+        mut retaliate,
+        mut sword_beam,
+        mut lingering_arc,
+        mut crit_chance,
+        mut perks,
+    ) in player_query.iter_mut()
+    {
+        // go in declaration order so perks that unlock something come before the perks that upgrade it
+        for perk in PerkType::iter() {
+            let is_secondary_action_unlock = perk == PerkType::UnlockGrenade
+                || perk == PerkType::UnlockTeleport
+                || perk == PerkType::UnlockSlowField
+                || perk == PerkType::UnlockDash;
+            if !build.perks.contains(&perk)
+                || perks.0.contains(&perk)
+                || (is_secondary_action_unlock && mutators.has(Mutator::Bladesworn))
+            {
+                continue;
             }
 
-            time.unpause();
+            activate_perk(
+                perk,
+                &mut cooldown,
+                &mut max_speed,
+                &mut health_regen,
+                &mut secondary_action,
+                &mut retaliate,
+                &mut sword_beam,
+                &mut lingering_arc,
+                &mut crit_chance,
+                &mut sword_pivot_queries.p0(),
+                &mut health,
+                &mut enemy_speed_multiplier,
+            );
+            perks.0.insert(perk);
         }
+
+        *base_stats = BaseStats {
+            attack_cooldown: cooldown.0.duration(),
+            max_speed: max_speed.0,
+            max_health: health.max_health,
+            enemy_speed_multiplier: enemy_speed_multiplier.0,
+            secondary_action: secondary_action.0.clone(),
+            health_regen: health_regen.clone(),
+            retaliate: retaliate.0,
+            sword_beam: sword_beam.0,
+            lingering_arc: lingering_arc.0,
+            crit_chance: crit_chance.0,
+            perks: perks.0.clone(),
+        };
+    }
+
+    for (mut base_params, swing_params) in sword_pivot_queries.p1().iter_mut() {
+        base_params.0 = swing_params.clone();
+    }
+}
+
+/// Remembers how intense spawning got in the run that's ending, so it can be shown on the game over screen
+fn record_last_run_spawn_stats(
+    mut commands: Commands,
+    spawn_timer: Res<SpawnTimer>,
+    spawn_weights: Res<SpawnWeights>,
+) {
+    let total_weight = spawn_weights.weights.iter().sum::<u32>() as f32;
+    let mut weighted_types = spawn_weights
+        .types
+        .iter()
+        .zip(spawn_weights.weights.iter())
+        .filter(|(_, weight)| **weight > 0)
+        .map(|(enemy_type, weight)| (enemy_type.get_name(), *weight as f32 / total_weight))
+        .collect::<Vec<_>>();
+    weighted_types.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+    weighted_types.truncate(NUM_LAST_RUN_ENEMY_TYPES);
+
+    commands.insert_resource(LastRunSpawnStats {
+        spawn_interval: spawn_timer.0.duration(),
+        top_enemy_types: weighted_types,
+    });
+}
+
+/// Keeps track of how the current run is going.
+/// Survival time goes by real time, so slow motion doesn't make runs look longer than they felt.
+fn update_run_stats(
+    mut run_stats: ResMut<RunStats>,
+    mut enemy_killed_events: EventReader<EnemyKilled>,
+    time: Res<Time<Real>>,
+) {
+    run_stats.kills += enemy_killed_events.read().count() as u64;
+    run_stats.survival_time += time.delta();
+}
+
+/// Adds the results of the run that's ending to the player's lifetime stats, which can unlock new cosmetics
+fn record_lifetime_stats(
+    run_stats: Res<RunStats>,
+    level: Res<Level>,
+    mut cosmetics: ResMut<Cosmetics>,
+    current_profile: Res<CurrentProfile>,
+) {
+    cosmetics
+        .lifetime_stats
+        .record_run(level.current_level, level.current_xp, run_stats.kills);
+    save_profile_cosmetics(&current_profile, &cosmetics);
+}
+
+/// Adds a summary of the run that's ending to the run log
+fn log_run(
+    run_stats: Res<RunStats>,
+    level: Res<Level>,
+    difficulty: Res<SelectedDifficulty>,
+    perks_query: Query<&Perks, With<Player>>,
+    current_profile: Res<CurrentProfile>,
+    key_bindings: Res<KeyBindings>,
+) {
+    let mut perks = perks_query
+        .iter()
+        .flat_map(|perks| perks.0.iter())
+        .map(|perk| perk.get_name_and_description(&key_bindings).0)
+        .collect::<Vec<String>>();
+    perks.sort();
+
+    let entry = RunLogEntry {
+        timestamp: persistence::get_timestamp(),
+        difficulty: difficulty.0.get_name(),
+        level: level.current_level,
+        xp: level.current_xp,
+        kills: run_stats.kills,
+        survival_seconds: run_stats.survival_time.as_secs_f32(),
+        perks,
+    };
+    persistence::append_to_log_for_profile(&current_profile, RUN_LOG_KEY, &entry);
+}
+
+/// Remembers the build from the run that's ending, so it can be shown on the game over screen
+fn record_last_run_build(
+    mut commands: Commands,
+    perks_query: Query<&Perks, With<Player>>,
+    selected_loadout: Res<SelectedLoadout>,
+    mutators: Res<ActiveMutators>,
+) {
+    for perks in perks_query.iter() {
+        commands.insert_resource(LastRunBuild(Build {
+            loadout: selected_loadout.0,
+            mutators: mutators.0.clone(),
+            perks: perks.0.clone(),
+        }));
     }
 }
 
@@ -2206,49 +7816,194 @@ fn health_regen(
 }
 
 /// Starts playing the background music
-fn start_background_music(mut commands: Commands /* TODO audio_assets: Res<AudioAssets> */) {
+fn start_background_music(
+    mut commands: Commands, /* TODO audio_assets: Res<AudioAssets>, volume_settings: Res<VolumeSettings> */
+) {
     /* TODO
     commands.spawn((
         AudioBundle {
             source: audio_assets.background_music.clone(),
             settings: PlaybackSettings::LOOP
-                .with_volume(Volume::Relative(VolumeLevel::new(BG_MUSIC_VOLUME))),
+                .with_volume(Volume::Relative(VolumeLevel::new(BG_MUSIC_VOLUME * volume_settings.get_music_volume()))),
         },
         BackgroundMusic,
     ));
     */
+    commands.insert_resource(AudioDucking(1.0));
+}
+
+/// Keeps the background music at the volume the player has chosen, and turns it and any playing sound effects down while choosing a perk
+fn update_audio_volume(
+    volume_settings: Res<VolumeSettings>,
+    music_controller: Query<&AudioSink, With<BackgroundMusic>>,
+    sound_effect_query: Query<(&AudioSink, &SoundEffect)>,
+    mut ducking: ResMut<AudioDucking>,
+    perk_chooser_query: Query<&Visibility, With<PerkChooser>>,
+    time: Res<Time<Real>>,
+) {
+    // turn everything down while the game is stopped for choosing a perk
+    let choosing_perk = perk_chooser_query
+        .iter()
+        .any(|visibility| *visibility != Visibility::Hidden);
+    let target_ducking = if choosing_perk {
+        PERK_CHOOSER_VOLUME_MULTIPLIER
+    } else {
+        1.0
+    };
+    let max_ducking_change = AUDIO_DUCKING_CHANGE_SPEED * time.delta_seconds();
+    ducking.0 += (target_ducking - ducking.0).clamp(-max_ducking_change, max_ducking_change);
+
+    for sink in music_controller.iter() {
+        sink.set_volume(BG_MUSIC_VOLUME * volume_settings.get_music_volume() * ducking.0);
+    }
+
+    for (sink, sound_effect) in sound_effect_query.iter() {
+        sink.set_volume(sound_effect.volume * ducking.0);
+    }
 }
 
 /// Stops playing the background music
 fn stop_background_music(music_controller: Query<&AudioSink, With<BackgroundMusic>>) {
-    if let Ok(sink) = music_controller.get_single() {
+    for sink in music_controller.iter() {
         sink.stop();
     }
 }
 
+/// Gets the direction the player is aiming in from the provided position.
+/// Aiming with a gamepad takes priority over the mouse while the aiming stick is pushed, and gets aim assist applied.
+fn get_aim_direction(
+    player_position: Vec2,
+    cursor_world_position: Option<Vec2>,
+    gamepads: &Gamepads,
+    gamepad_axes: &Axis<GamepadAxis>,
+    enemy_positions: impl Iterator<Item = Vec2>,
+    settings: &Settings,
+) -> Option<Vec2> {
+    if let Some(stick_direction) = get_gamepad_aim_direction(gamepads, gamepad_axes) {
+        return Some(apply_aim_assist(
+            stick_direction,
+            player_position,
+            enemy_positions,
+            settings.aim_assist.get_strength(),
+        ));
+    }
+
+    (cursor_world_position? - player_position).try_normalize()
+}
+
+/// Gets the direction the aiming stick on a gamepad is pushed in, if it's pushed far enough
+fn get_gamepad_aim_direction(
+    gamepads: &Gamepads,
+    gamepad_axes: &Axis<GamepadAxis>,
+) -> Option<Vec2> {
+    gamepads.iter().find_map(|gamepad| {
+        let x = gamepad_axes.get(GamepadAxis::new(gamepad, GamepadAxisType::RightStickX))?;
+        let y = gamepad_axes.get(GamepadAxis::new(gamepad, GamepadAxisType::RightStickY))?;
+        let stick = Vec2::new(x, y);
+        if stick.length() < GAMEPAD_AIM_DEADZONE {
+            return None;
+        }
+
+        stick.try_normalize()
+    })
+}
+
+/// Pulls the provided aim direction part of the way towards the enemy closest to it, if there's one close enough.
+/// A strength of 0 leaves the aim alone, and a strength of 1 aims right at the enemy.
+fn apply_aim_assist(
+    aim_direction: Vec2,
+    player_position: Vec2,
+    enemy_positions: impl Iterator<Item = Vec2>,
+    strength: f32,
+) -> Vec2 {
+    if strength <= 0.0 {
+        return aim_direction;
+    }
+
+    let closest_angle = enemy_positions
+        .map(|enemy_position| enemy_position - player_position)
+        .filter(|to_enemy| to_enemy.length() <= AIM_ASSIST_RANGE)
+        .map(|to_enemy| aim_direction.angle_between(to_enemy))
+        .filter(|angle| angle.is_finite() && angle.abs() <= AIM_ASSIST_MAX_ANGLE)
+        .min_by(|a, b| a.abs().total_cmp(&b.abs()));
+
+    match closest_angle {
+        Some(angle) => Vec2::from_angle(angle * strength).rotate(aim_direction),
+        None => aim_direction,
+    }
+}
+
+/// Run condition that's true while the provided button is held on any gamepad
+fn gamepad_button_pressed(
+    button_type: GamepadButtonType,
+) -> impl FnMut(Res<Gamepads>, Res<Input<GamepadButton>>) -> bool + Clone {
+    move |gamepads: Res<Gamepads>, gamepad_buttons: Res<Input<GamepadButton>>| {
+        gamepads
+            .iter()
+            .any(|gamepad| gamepad_buttons.pressed(GamepadButton::new(gamepad, button_type)))
+    }
+}
+
 /// Gets the cursor's position in world coordinates
 fn get_cursor_world_position(
     camera_query: &Query<(&Camera, &GlobalTransform)>,
     window_query: &Query<&Window>,
 ) -> Option<Vec2> {
     let (camera, camera_transform) = camera_query.single();
-    let Some(cursor_position) = window_query.single().cursor_position() else {
+    let cursor_position = window_query.single().cursor_position()?;
+
+    // the cursor position is relative to the window, but the viewport may be letterboxed inside it
+    let viewport_rect = camera.logical_viewport_rect()?;
+    if !viewport_rect.contains(cursor_position) {
         return None;
-    };
+    }
 
-    camera.viewport_to_world_2d(camera_transform, cursor_position)
+    camera.viewport_to_world_2d(camera_transform, cursor_position - viewport_rect.min)
 }
 
 /// Plays a sound
-fn play_sound(sound: Handle<AudioSource>, volume: f32, commands: &mut Commands) {
-    commands.spawn(AudioBundle {
-        source: sound,
-        settings: PlaybackSettings {
-            mode: PlaybackMode::Despawn,
-            volume: Volume::new_relative(volume),
-            ..default()
+fn play_sound(
+    sound: Handle<AudioSource>,
+    volume: f32,
+    volume_settings: &VolumeSettings,
+    commands: &mut Commands,
+) {
+    play_sound_at_speed(sound, volume, 1.0, volume_settings, commands);
+}
+
+/// Plays a sound at a slightly random speed, which also changes its pitch.
+/// The speed will be at most `speed_variation` away from normal speed in either direction.
+fn play_varied_sound(
+    sound: Handle<AudioSource>,
+    volume: f32,
+    speed_variation: f32,
+    volume_settings: &VolumeSettings,
+    commands: &mut Commands,
+) {
+    let speed = 1.0 + rand::thread_rng().gen_range(-speed_variation..=speed_variation);
+    play_sound_at_speed(sound, volume, speed, volume_settings, commands);
+}
+
+fn play_sound_at_speed(
+    sound: Handle<AudioSource>,
+    volume: f32,
+    speed: f32,
+    volume_settings: &VolumeSettings,
+    commands: &mut Commands,
+) {
+    let volume = volume * volume_settings.get_sfx_volume();
+    commands.spawn((
+        AudioBundle {
+            source: sound,
+            settings: PlaybackSettings {
+                mode: PlaybackMode::Despawn,
+                volume: Volume::new_relative(volume),
+                speed,
+                ..default()
+            },
         },
-    });
+        SoundEffect { volume },
+    ));
 }
 
 //
@@ -2280,9 +8035,17 @@ fn activate_wider_sword_swing(
     }
 }
 
+/// Changes the duration of a cooldown timer without changing how far through the cooldown it is, so a ready cooldown stays ready
+/// and a partially elapsed one doesn't suddenly finish or end up with more time remaining than it should
+fn set_cooldown_duration(timer: &mut Timer, duration: Duration) {
+    let fraction_elapsed = timer.percent();
+    timer.set_duration(duration);
+    timer.set_elapsed(duration.mul_f32(fraction_elapsed));
+}
+
 fn activate_shorter_attack_cooldown(cooldown: &mut AttackCooldown) {
     let new_duration = cooldown.0.duration().mul_f32(0.9);
-    cooldown.0.set_duration(new_duration);
+    set_cooldown_duration(&mut cooldown.0, new_duration);
 }
 
 fn activate_higher_max_speed(max_speed: &mut MaxSpeed) {
@@ -2329,7 +8092,7 @@ fn activate_shorter_grenade_cooldown(secondary_action: &mut SecondaryAction) {
     } = &mut secondary_action.0
     {
         let new_cooldown = cooldown_timer.duration().mul_f32(0.9);
-        cooldown_timer.set_duration(new_cooldown);
+        set_cooldown_duration(cooldown_timer, new_cooldown);
     }
 }
 
@@ -2351,7 +8114,28 @@ fn activate_shorter_teleport_cooldown(secondary_action: &mut SecondaryAction) {
     } = &mut secondary_action.0
     {
         let new_cooldown = cooldown_timer.duration().mul_f32(0.9);
-        cooldown_timer.set_duration(new_cooldown);
+        set_cooldown_duration(cooldown_timer, new_cooldown);
+    }
+}
+
+fn activate_unlock_slow_field(secondary_action: &mut SecondaryAction) {
+    let mut cooldown_timer = Timer::new(SLOW_FIELD_COOLDOWN, TimerMode::Once);
+    cooldown_timer.set_elapsed(cooldown_timer.duration());
+
+    secondary_action.0 = SecondaryActionType::SlowField {
+        cooldown_timer,
+        radius: SLOW_FIELD_RADIUS,
+        duration: SLOW_FIELD_DURATION,
+    }
+}
+
+fn activate_unlock_dash(secondary_action: &mut SecondaryAction) {
+    let mut cooldown_timer = Timer::new(DASH_COOLDOWN, TimerMode::Once);
+    cooldown_timer.set_elapsed(cooldown_timer.duration());
+
+    secondary_action.0 = SecondaryActionType::Dash {
+        cooldown_timer,
+        distance: DASH_DISTANCE,
     }
 }
 
@@ -2384,7 +8168,7 @@ fn activate_unlock_health_regen(health_regen: &mut HealthRegen) {
 
 fn activate_faster_health_regen(health_regen: &mut HealthRegen) {
     let new_cooldown = health_regen.timer.duration().mul_f32(0.9);
-    health_regen.timer.set_duration(new_cooldown);
+    set_cooldown_duration(&mut health_regen.timer, new_cooldown);
 }
 
 fn activate_retaliate(retaliate: &mut Retaliate) {
@@ -2395,3 +8179,275 @@ fn activate_slower_enemies(speed_multiplier: &mut EnemySpeedMultiplier) {
     let new_multiplier = 0.1_f32.max(speed_multiplier.0 * 0.9);
     speed_multiplier.0 = new_multiplier;
 }
+
+fn activate_sword_beam(sword_beam: &mut SwordBeam) {
+    sword_beam.0 = true;
+}
+
+fn activate_lingering_arc(lingering_arc: &mut LingeringArc) {
+    lingering_arc.0 = true;
+}
+
+fn activate_critical_hits(crit_chance: &mut CritChance) {
+    crit_chance.0 = MAX_CRIT_CHANCE.min(crit_chance.0 + CRIT_CHANCE_PER_PERK);
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::{
+        event::{Events, ManualEventReader},
+        system::RunSystemOnce,
+    };
+    use bevy_tweening::{Targetable, TweenState, Tweenable};
+
+    use super::*;
+
+    struct TestTarget(Transform);
+
+    impl Targetable<Transform> for TestTarget {
+        fn target_mut(&mut self) -> &mut Transform {
+            &mut self.0
+        }
+    }
+
+    #[test]
+    fn main_sword_swing_completes_without_shadows() {
+        let mut world = World::new();
+        world.init_resource::<Events<TweenCompleted>>();
+        let entity = world.spawn_empty().id();
+
+        let mut animation =
+            build_sword_animation(&build_main_sword_animation_params(SWORD_END_SCALE));
+        let mut target = TestTarget(Transform::default());
+        world.resource_scope(|_, mut events: Mut<Events<TweenCompleted>>| {
+            let mut state = TweenState::Active;
+            for _ in 0..1000 {
+                state = animation.tick(Duration::from_millis(10), &mut target, entity, &mut events);
+                if state == TweenState::Completed {
+                    break;
+                }
+            }
+            assert_eq!(TweenState::Completed, state);
+        });
+
+        let events = world.resource::<Events<TweenCompleted>>();
+        let event_ids = events
+            .get_reader()
+            .read(events)
+            .map(|event| event.user_data)
+            .collect::<Vec<u64>>();
+        assert_eq!(
+            vec![SWORD_SWING_COMPLETE_EVENT_ID, ATTACK_DONE_EVENT_ID],
+            event_ids
+        );
+    }
+
+    #[test]
+    fn gameplay_inactive_while_choosing_perk() {
+        let mut world = World::new();
+        world.insert_resource(Time::<Virtual>::default());
+        let perk_chooser = world.spawn((PerkChooser, Visibility::Hidden)).id();
+        assert!(world.run_system_once(gameplay_active));
+
+        *world.get_mut::<Visibility>(perk_chooser).unwrap() = Visibility::Inherited;
+        assert!(!world.run_system_once(gameplay_active));
+
+        *world.get_mut::<Visibility>(perk_chooser).unwrap() = Visibility::Hidden;
+        world.resource_mut::<Time<Virtual>>().pause();
+        assert!(!world.run_system_once(gameplay_active));
+    }
+
+    /// Runs the slow motion system at the provided frame time until slow motion started with the provided time scale ends,
+    /// and returns how much real time that took
+    fn measure_slow_mo_duration(frame_time: Duration, time_scale: f32) -> Duration {
+        let mut world = World::new();
+        world.insert_resource(BulletTime {
+            charge: 0.0,
+            active: false,
+        });
+        world.insert_resource(AimingSecondaryAction(false));
+        world.insert_resource(Time::<Virtual>::default());
+        let mut real_time = Time::<Real>::default();
+        real_time.update_with_duration(Duration::ZERO);
+        world.insert_resource(real_time);
+
+        let mut slow_mo_timer = SlowMoTimer {
+            target_time_scale: 1.0,
+            timer: Timer::new(HIT_SLOW_MO_TIME, TimerMode::Once),
+        };
+        slow_mo_timer.start(time_scale, HIT_SLOW_MO_TIME);
+        world.insert_resource(slow_mo_timer);
+
+        let mut elapsed = Duration::ZERO;
+        for _ in 0..1000 {
+            world
+                .resource_mut::<Time<Real>>()
+                .update_with_duration(frame_time);
+            elapsed += frame_time;
+            world.run_system_once(slow_mo);
+            if world.resource::<Time<Virtual>>().relative_speed() == 1.0 {
+                return elapsed;
+            }
+        }
+
+        panic!("slow motion never ended");
+    }
+
+    #[test]
+    fn slow_mo_lasts_the_same_at_any_frame_rate() {
+        for fps in [30, 60, 144] {
+            let frame_time = Duration::from_secs(1) / fps;
+            let duration = measure_slow_mo_duration(frame_time, HIT_SLOW_MO_TIME_SCALE);
+            assert!(
+                duration >= HIT_SLOW_MO_TIME && duration < HIT_SLOW_MO_TIME + frame_time,
+                "slow motion lasted {duration:?} at {fps} FPS"
+            );
+        }
+    }
+
+    #[test]
+    fn slow_mo_ends_after_its_duration_in_real_time() {
+        let frame_time = Duration::from_secs(1) / 60;
+        for time_scale in [0.1, HIT_SLOW_MO_TIME_SCALE, 0.9] {
+            let duration = measure_slow_mo_duration(frame_time, time_scale);
+            assert!(
+                duration >= HIT_SLOW_MO_TIME && duration < HIT_SLOW_MO_TIME + frame_time,
+                "slow motion lasted {duration:?} at time scale {time_scale}"
+            );
+        }
+    }
+
+    #[test]
+    fn explosion_hits_enemies_out_to_its_full_radius() {
+        let radius = EXPLOSION_START_RADIUS * 5.0;
+        let enemy_size = 1.0;
+        let enemy_distance = radius - enemy_size / 2.0;
+        assert!(enemy_distance - enemy_size > EXPLOSION_START_RADIUS);
+
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            TransformPlugin,
+            HierarchyPlugin,
+            AssetPlugin::default(),
+            RapierPhysicsPlugin::<NoUserData>::default(),
+        ))
+        .init_asset::<Mesh>()
+        .init_asset::<Scene>()
+        .add_event::<TweenCompleted>();
+
+        let mut animation = build_explosion_scale_animation(radius);
+        let mut target = TestTarget(Transform::default());
+        let entity = app.world.spawn_empty().id();
+        app.world
+            .resource_scope(|_, mut events: Mut<Events<TweenCompleted>>| {
+                animation.tick(EXPLOSION_DURATION, &mut target, entity, &mut events);
+            });
+
+        let explosion = app
+            .world
+            .spawn(TransformBundle::from_transform(target.0))
+            .insert(Collider::ball(EXPLOSION_START_RADIUS))
+            .insert(Sensor)
+            .id();
+        let enemy = app
+            .world
+            .spawn(TransformBundle::from_transform(Transform::from_xyz(
+                enemy_distance,
+                0.0,
+                0.0,
+            )))
+            .insert(Collider::ball(enemy_size))
+            .insert(ActiveEvents::COLLISION_EVENTS)
+            .insert(RigidBody::Dynamic)
+            .insert(GravityScale(0.0))
+            .id();
+
+        let mut hit = false;
+        let mut reader = ManualEventReader::<CollisionEvent>::default();
+        for _ in 0..5 {
+            app.update();
+            let collision_events = app.world.resource::<Events<CollisionEvent>>();
+            hit |= reader.read(collision_events).any(|event| match event {
+                CollisionEvent::Started(a, b, _) => {
+                    (*a == explosion && *b == enemy) || (*a == enemy && *b == explosion)
+                }
+                CollisionEvent::Stopped(..) => false,
+            });
+        }
+        assert!(hit);
+    }
+
+    #[test]
+    fn survival_time_formatting() {
+        assert_eq!("00:00", format_survival_time(Duration::ZERO));
+        assert_eq!("00:59", format_survival_time(Duration::from_millis(59_999)));
+        assert_eq!("01:05", format_survival_time(Duration::from_secs(65)));
+        assert_eq!(
+            "123:45",
+            format_survival_time(Duration::from_secs(123 * 60 + 45))
+        );
+    }
+
+    #[test]
+    fn wave_sizes() {
+        assert_eq!(FIRST_WAVE_SIZE, get_wave_size(1, Difficulty::Normal));
+        assert_eq!(
+            FIRST_WAVE_SIZE + WAVE_SIZE_INCREASE,
+            get_wave_size(2, Difficulty::Normal)
+        );
+
+        // harder difficulties have bigger waves
+        assert!(get_wave_size(3, Difficulty::Easy) < get_wave_size(3, Difficulty::Normal));
+        assert!(get_wave_size(3, Difficulty::Hard) > get_wave_size(3, Difficulty::Normal));
+
+        // waves stop growing eventually
+        assert_eq!(MAX_WAVE_SIZE, get_wave_size(1000, Difficulty::Hard));
+    }
+
+    #[test]
+    fn bosses_are_not_part_of_waves() {
+        let mut world = World::new();
+        world.init_resource::<Events<EnemyKilled>>();
+        world.insert_resource(Wave {
+            number: 1,
+            size: 3,
+            killed: 0,
+        });
+
+        for enemy_type in [EnemyType::Regular, EnemyType::Boss, EnemyType::Shooter] {
+            world.send_event(EnemyKilled {
+                enemy_type,
+                position: Vec2::ZERO,
+                xp_reward: 1,
+                critical: false,
+            });
+        }
+        world.run_system_once(count_wave_kills);
+
+        assert_eq!(1, world.resource::<Wave>().get_remaining());
+    }
+
+    #[test]
+    fn shortening_cooldown_keeps_progress() {
+        let mut timer = Timer::new(Duration::from_secs(10), TimerMode::Once);
+        timer.tick(Duration::from_secs(4));
+
+        set_cooldown_duration(&mut timer, Duration::from_secs(5));
+
+        assert_eq!(Duration::from_secs(5), timer.duration());
+        assert_eq!(Duration::from_secs(2), timer.elapsed());
+        assert!(!timer.finished());
+    }
+
+    #[test]
+    fn shortening_finished_cooldown_keeps_it_finished() {
+        let mut timer = Timer::new(Duration::from_secs(10), TimerMode::Once);
+        timer.tick(Duration::from_secs(10));
+
+        set_cooldown_duration(&mut timer, Duration::from_secs(9));
+
+        assert_eq!(Duration::from_secs(9), timer.elapsed());
+        assert!(timer.finished());
+    }
+}
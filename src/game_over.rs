@@ -1,5 +1,11 @@
+use std::time::Duration;
+
 use crate::*;
 
+/// How long the restart button can't be pressed for, so a leftover click from the end of the run doesn't skip the game over screen
+const RESTART_DELAY: Duration = Duration::from_millis(500);
+const DISABLED_BUTTON_COLOR: Color = Color::rgb(0.15, 0.15, 0.15);
+
 pub struct GameOverPlugin;
 
 impl Plugin for GameOverPlugin {
@@ -9,7 +15,16 @@ impl Plugin for GameOverPlugin {
                 OnExit(GameState::GameOver),
                 despawn_components_system::<GameOverComponent>,
             )
-            .add_systems(Update, restart_button_system);
+            .add_systems(
+                Update,
+                (
+                    enable_restart_button.run_if(in_state(GameState::GameOver)),
+                    restart_button_system.after(enable_restart_button),
+                ),
+            );
+
+        #[cfg(target_arch = "wasm32")]
+        app.add_systems(Update, download_run_log_button_system);
     }
 }
 
@@ -19,7 +34,25 @@ struct GameOverComponent;
 #[derive(Component)]
 struct RestartButton;
 
-fn game_over_setup(mut commands: Commands, level: Res<Level>, asset_server: Res<AssetServer>) {
+#[derive(Component)]
+struct DownloadRunLogButton;
+
+/// Counts down to the restart button being able to be pressed
+#[derive(Resource)]
+struct RestartDelayTimer(Timer);
+
+#[allow(clippy::too_many_arguments)]
+fn game_over_setup(
+    mut commands: Commands,
+    level: Res<Level>,
+    run_stats: Res<RunStats>,
+    last_run_build: Option<Res<LastRunBuild>>,
+    last_run_spawn_stats: Option<Res<LastRunSpawnStats>>,
+    settings: Res<Settings>,
+    cosmetics: Res<Cosmetics>,
+    theme: Res<Theme>,
+    asset_server: Res<AssetServer>,
+) {
     let legacy_message = if level.current_level < 4 {
         "You will be forgotten."
     } else if level.current_level < 8 {
@@ -30,6 +63,11 @@ fn game_over_setup(mut commands: Commands, level: Res<Level>, asset_server: Res<
         "Your heroic feats will be remembered for all time."
     };
 
+    commands.insert_resource(RestartDelayTimer(Timer::new(
+        RESTART_DELAY,
+        TimerMode::Once,
+    )));
+
     commands
         .spawn(NodeBundle {
             style: Style {
@@ -48,8 +86,11 @@ fn game_over_setup(mut commands: Commands, level: Res<Level>, asset_server: Res<
             parent.spawn(
                 TextBundle::from_section(
                     format!(
-                        "You perished at level {} with {} XP.\n{}",
-                        level.current_level, level.current_xp, legacy_message
+                        "You perished at level {} with {} XP after {}.\n{}",
+                        level.current_level,
+                        level.current_xp,
+                        format_survival_time(run_stats.survival_time),
+                        legacy_message
                     ),
                     TextStyle {
                         font: asset_server.load(MAIN_FONT),
@@ -64,7 +105,75 @@ fn game_over_setup(mut commands: Commands, level: Res<Level>, asset_server: Res<
                 }),
             );
 
-            // restart button
+            // the lifetime stats already include the run that just ended
+            let best_stats = &cosmetics.lifetime_stats;
+            parent.spawn(
+                TextBundle::from_section(
+                    format!(
+                        "Best: level {} with {} XP",
+                        best_stats.best_level, best_stats.best_level_xp
+                    ),
+                    TextStyle {
+                        font: asset_server.load(MAIN_FONT),
+                        font_size: 25.0,
+                        color: Color::GRAY,
+                    },
+                )
+                .with_text_alignment(TextAlignment::Center)
+                .with_style(Style {
+                    margin: UiRect::bottom(Val::Px(10.0)),
+                    ..default()
+                }),
+            );
+
+            // how hard things got
+            if let Some(last_run_spawn_stats) = &last_run_spawn_stats {
+                parent.spawn(
+                    TextBundle::from_section(
+                        get_spawn_stats_description(last_run_spawn_stats),
+                        TextStyle {
+                            font: asset_server.load(MAIN_FONT),
+                            font_size: 25.0,
+                            color: Color::GRAY,
+                        },
+                    )
+                    .with_text_alignment(TextAlignment::Center)
+                    .with_style(Style {
+                        margin: UiRect::bottom(Val::Px(10.0)),
+                        ..default()
+                    }),
+                );
+            }
+
+            // build code, so the run's build can be shared
+            if let Some(last_run_build) = &last_run_build {
+                parent.spawn(
+                    TextBundle::from_section(
+                        format!(
+                            "{}{}\nBuild code: {}",
+                            if last_run_build.0.mutators.contains(&Mutator::Purist) {
+                                "Purist run (no perks)\n"
+                            } else {
+                                ""
+                            },
+                            last_run_build.0.get_summary(),
+                            last_run_build.0.to_code()
+                        ),
+                        TextStyle {
+                            font: asset_server.load(MONO_FONT),
+                            font_size: 25.0,
+                            color: Color::GRAY,
+                        },
+                    )
+                    .with_text_alignment(TextAlignment::Center)
+                    .with_style(Style {
+                        margin: UiRect::bottom(Val::Px(15.0)),
+                        ..default()
+                    }),
+                );
+            }
+
+            // buttons
             parent
                 .spawn(NodeBundle {
                     style: Style {
@@ -77,42 +186,128 @@ fn game_over_setup(mut commands: Commands, level: Res<Level>, asset_server: Res<
                     ..default()
                 })
                 .with_children(|parent| {
-                    parent
-                        .spawn(ButtonBundle {
-                            style: Style {
-                                justify_content: JustifyContent::Center,
-                                align_items: AlignItems::Center,
-                                padding: UiRect::all(Val::Px(10.0)),
-                                ..default()
-                            },
-                            background_color: NORMAL_BUTTON.into(),
-                            ..default()
-                        })
-                        .insert(RestartButton)
-                        .with_children(|parent| {
-                            parent.spawn(TextBundle::from_section(
-                                "Again",
-                                TextStyle {
-                                    font: asset_server.load(MAIN_FONT),
-                                    font_size: 40.0,
-                                    color: NORMAL_BUTTON_TEXT_COLOR,
-                                },
-                            ));
-                        });
+                    spawn_game_over_button(
+                        "Again",
+                        (RestartButton, DisabledButton),
+                        parent,
+                        &theme,
+                        &asset_server,
+                    );
+
+                    // browsers can't write the run log to a file, so it's offered as a download instead
+                    if cfg!(target_arch = "wasm32") && settings.log_runs {
+                        spawn_game_over_button(
+                            "Download run log",
+                            DownloadRunLogButton,
+                            parent,
+                            &theme,
+                            &asset_server,
+                        );
+                    }
                 });
         });
 }
 
-type InteractedRestartButtonTuple = (Changed<Interaction>, With<RestartButton>);
+/// Spawns a button on the game over screen
+fn spawn_game_over_button(
+    text: &str,
+    marker: impl Bundle,
+    parent: &mut ChildBuilder,
+    theme: &Theme,
+    asset_server: &Res<AssetServer>,
+) {
+    parent
+        .spawn(ButtonBundle {
+            style: Style {
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                padding: UiRect::all(Val::Px(10.0)),
+                margin: UiRect::horizontal(Val::Px(10.0)),
+                ..default()
+            },
+            background_color: theme.normal_button.into(),
+            ..default()
+        })
+        .insert(marker)
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                text,
+                TextStyle {
+                    font: asset_server.load(MAIN_FONT),
+                    font_size: 40.0,
+                    color: theme.button_text,
+                },
+            ));
+        });
+}
+
+/// Gets the user-facing description of how intense spawning got by the end of the run
+fn get_spawn_stats_description(spawn_stats: &LastRunSpawnStats) -> String {
+    let spawns_per_second = 1.0 / spawn_stats.spawn_interval.as_secs_f32();
+    let top_enemy_types = spawn_stats
+        .top_enemy_types
+        .iter()
+        .map(|(name, fraction)| format!("{name} ({:.0}%)", fraction * 100.0))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(
+        "Enemies were arriving at {spawns_per_second:.1} per second.\nMost common enemies: {top_enemy_types}"
+    )
+}
+
+type DisabledRestartButtonTuple = (With<RestartButton>, With<DisabledButton>);
+
+/// Keeps the restart button greyed out until it's been long enough since the run ended
+fn enable_restart_button(
+    mut commands: Commands,
+    mut timer: ResMut<RestartDelayTimer>,
+    mut button_query: Query<(Entity, &mut BackgroundColor), DisabledRestartButtonTuple>,
+    theme: Res<Theme>,
+    time: Res<Time<Real>>,
+) {
+    timer.0.tick(time.delta());
+    for (entity, mut color) in button_query.iter_mut() {
+        if timer.0.finished() {
+            *color = theme.normal_button.into();
+            commands.entity(entity).remove::<DisabledButton>();
+        } else {
+            *color = DISABLED_BUTTON_COLOR.into();
+        }
+    }
+}
+
+type InteractedRestartButtonTuple = (
+    Changed<Interaction>,
+    With<RestartButton>,
+    Without<DisabledButton>,
+);
 
 /// Handles interactions with the restart button.
 fn restart_button_system(
     mut next_state: ResMut<NextState<GameState>>,
     interaction_query: Query<&Interaction, InteractedRestartButtonTuple>,
+    image_assets: Option<Res<ImageAssets>>,
+    audio_assets: Option<Res<AudioAssets>>,
+) {
+    for interaction in interaction_query.iter() {
+        if *interaction == Interaction::Pressed {
+            next_state.set(get_game_start_state(&image_assets, &audio_assets));
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+type InteractedDownloadRunLogButtonTuple = (Changed<Interaction>, With<DownloadRunLogButton>);
+
+/// Handles interactions with the button for downloading the run log.
+#[cfg(target_arch = "wasm32")]
+fn download_run_log_button_system(
+    interaction_query: Query<&Interaction, InteractedDownloadRunLogButtonTuple>,
+    current_profile: Res<CurrentProfile>,
 ) {
     for interaction in interaction_query.iter() {
         if *interaction == Interaction::Pressed {
-            next_state.set(GameState::GameLoading);
+            persistence::download_log_for_profile(&current_profile, RUN_LOG_KEY);
         }
     }
 }
@@ -0,0 +1,489 @@
+use serde::{Deserialize, Serialize};
+use strum::{EnumIter, IntoEnumIterator};
+
+use crate::*;
+
+const KEY_BINDINGS_KEY: &str = "key-bindings";
+/// Stops waiting for a new binding without changing anything
+const CANCEL_REBIND_INPUT: KeyCode = KeyCode::Escape;
+const REBIND_FEEDBACK_COLOR: Color = Color::ORANGE_RED;
+
+pub struct KeyBindingsPlugin;
+
+impl Plugin for KeyBindingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(persistence::load::<KeyBindings>(KEY_BINDINGS_KEY).unwrap_or_default())
+            .insert_resource(RebindingAction(None))
+            .add_systems(OnEnter(GameState::KeyBindings), key_bindings_setup)
+            .add_systems(
+                OnExit(GameState::KeyBindings),
+                (
+                    despawn_components_system::<KeyBindingsComponent>,
+                    stop_rebinding,
+                ),
+            )
+            .add_systems(
+                Update,
+                (
+                    rebind_button_system,
+                    // runs after the buttons so the click that starts rebinding isn't captured as the new binding
+                    capture_rebind_input.after(rebind_button_system),
+                    key_bindings_back_button_system,
+                )
+                    .run_if(in_state(GameState::KeyBindings)),
+            );
+    }
+}
+
+/// Replaces the current key bindings with the ones saved for the provided profile
+pub fn load_profile_key_bindings(profile: &CurrentProfile, commands: &mut Commands) {
+    commands.insert_resource(
+        persistence::load_for_profile::<KeyBindings>(profile, KEY_BINDINGS_KEY).unwrap_or_default(),
+    );
+}
+
+/// Which inputs control the player
+#[derive(Resource, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyBindings {
+    pub move_left: KeyCode,
+    pub move_right: KeyCode,
+    pub move_up: KeyCode,
+    pub move_down: KeyCode,
+    pub attack: MouseButton,
+    pub secondary_action: KeyCode,
+    pub pause: KeyCode,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings {
+            move_left: KeyCode::A,
+            move_right: KeyCode::D,
+            move_up: KeyCode::W,
+            move_down: KeyCode::S,
+            attack: MouseButton::Left,
+            secondary_action: KeyCode::Space,
+            pause: KeyCode::P,
+        }
+    }
+}
+
+/// Something the player does that can have its input changed
+#[derive(Clone, Copy, PartialEq, Eq, EnumIter)]
+enum BindableAction {
+    MoveLeft,
+    MoveRight,
+    MoveUp,
+    MoveDown,
+    Attack,
+    SecondaryAction,
+    Pause,
+}
+
+impl BindableAction {
+    /// Gets the user-facing name of this action
+    fn get_name(&self) -> &'static str {
+        match self {
+            BindableAction::MoveLeft => "Move left",
+            BindableAction::MoveRight => "Move right",
+            BindableAction::MoveUp => "Move up",
+            BindableAction::MoveDown => "Move down",
+            BindableAction::Attack => "Attack",
+            BindableAction::SecondaryAction => "Secondary action",
+            BindableAction::Pause => "Pause",
+        }
+    }
+
+    /// Gets the key this action is bound to, or `None` if it's bound to a mouse button instead
+    fn get_key_mut<'a>(&self, key_bindings: &'a mut KeyBindings) -> Option<&'a mut KeyCode> {
+        match self {
+            BindableAction::MoveLeft => Some(&mut key_bindings.move_left),
+            BindableAction::MoveRight => Some(&mut key_bindings.move_right),
+            BindableAction::MoveUp => Some(&mut key_bindings.move_up),
+            BindableAction::MoveDown => Some(&mut key_bindings.move_down),
+            BindableAction::Attack => None,
+            BindableAction::SecondaryAction => Some(&mut key_bindings.secondary_action),
+            BindableAction::Pause => Some(&mut key_bindings.pause),
+        }
+    }
+
+    /// Gets the key this action is bound to, or `None` if it's bound to a mouse button instead
+    fn get_key(&self, key_bindings: &KeyBindings) -> Option<KeyCode> {
+        match self {
+            BindableAction::MoveLeft => Some(key_bindings.move_left),
+            BindableAction::MoveRight => Some(key_bindings.move_right),
+            BindableAction::MoveUp => Some(key_bindings.move_up),
+            BindableAction::MoveDown => Some(key_bindings.move_down),
+            BindableAction::Attack => None,
+            BindableAction::SecondaryAction => Some(key_bindings.secondary_action),
+            BindableAction::Pause => Some(key_bindings.pause),
+        }
+    }
+
+    /// Gets the user-facing text describing what this action is bound to
+    fn get_label(&self, key_bindings: &KeyBindings, rebinding: bool) -> String {
+        let binding = if rebinding {
+            match self {
+                BindableAction::Attack => "press a mouse button...".to_string(),
+                _ => "press a key...".to_string(),
+            }
+        } else {
+            match self {
+                BindableAction::MoveLeft => format!("{:?}", key_bindings.move_left),
+                BindableAction::MoveRight => format!("{:?}", key_bindings.move_right),
+                BindableAction::MoveUp => format!("{:?}", key_bindings.move_up),
+                BindableAction::MoveDown => format!("{:?}", key_bindings.move_down),
+                BindableAction::Attack => format!("{:?} mouse button", key_bindings.attack),
+                BindableAction::SecondaryAction => format!("{:?}", key_bindings.secondary_action),
+                BindableAction::Pause => format!("{:?}", key_bindings.pause),
+            }
+        };
+        format!("{}: {binding}", self.get_name())
+    }
+}
+
+/// Determines whether the provided key already does something that can't be rebound
+fn is_reserved_key(key: KeyCode) -> bool {
+    key == CANCEL_REBIND_INPUT
+        || key == INSPECTOR_TOGGLE_INPUT
+        || key == BULLET_TIME_INPUT
+        || key == DEBUG_OVERLAY_INPUT
+        || key == SPAWN_AREA_OVERLAY_INPUT
+        || key == REWIND_INPUT
+        || PERK_CHOICE_INPUTS.contains(&key)
+}
+
+/// Gets the user-facing reason the provided key can't be bound to the provided action, or `None` if it can be
+fn get_rebind_conflict(
+    action: BindableAction,
+    key: KeyCode,
+    key_bindings: &KeyBindings,
+) -> Option<String> {
+    if is_reserved_key(key) {
+        return Some(format!("{key:?} is reserved and can't be bound"));
+    }
+
+    BindableAction::iter()
+        .filter(|other_action| *other_action != action)
+        .find(|other_action| other_action.get_key(key_bindings) == Some(key))
+        .map(|other_action| format!("{key:?} is already bound to {}", other_action.get_name()))
+}
+
+/// The action waiting for the player to press its new input, if any
+#[derive(Resource)]
+struct RebindingAction(Option<BindableAction>);
+
+#[derive(Component)]
+struct KeyBindingsComponent;
+
+#[derive(Component)]
+struct RebindButton(BindableAction);
+
+#[derive(Component)]
+struct BindingText(BindableAction);
+
+/// Explains why the last key pressed while rebinding wasn't accepted
+#[derive(Component)]
+struct RebindFeedbackText;
+
+#[derive(Component)]
+struct KeyBindingsBackButton;
+
+fn key_bindings_setup(
+    mut commands: Commands,
+    key_bindings: Res<KeyBindings>,
+    theme: Res<Theme>,
+    asset_server: Res<AssetServer>,
+) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .insert(KeyBindingsComponent)
+        .with_children(|parent| {
+            // title
+            parent.spawn(
+                TextBundle::from_section(
+                    "Key Bindings",
+                    TextStyle {
+                        font: asset_server.load(TITLE_FONT),
+                        font_size: 60.0,
+                        color: Color::WHITE,
+                    },
+                )
+                .with_text_alignment(TextAlignment::Center)
+                .with_style(Style {
+                    margin: UiRect::bottom(Val::Px(15.0)),
+                    ..default()
+                }),
+            );
+
+            // one button per action
+            for action in BindableAction::iter() {
+                parent
+                    .spawn(ButtonBundle {
+                        style: Style {
+                            width: Val::Px(400.0),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            padding: UiRect::all(Val::Px(6.0)),
+                            margin: UiRect::top(Val::Px(4.0)),
+                            ..default()
+                        },
+                        background_color: theme.normal_button.into(),
+                        ..default()
+                    })
+                    .insert(RebindButton(action))
+                    .with_children(|parent| {
+                        parent
+                            .spawn(TextBundle::from_section(
+                                action.get_label(&key_bindings, false),
+                                TextStyle {
+                                    font: asset_server.load(MAIN_FONT),
+                                    font_size: 25.0,
+                                    color: theme.button_text,
+                                },
+                            ))
+                            .insert(BindingText(action));
+                    });
+            }
+
+            parent.spawn(
+                TextBundle::from_section(
+                    format!("Press {CANCEL_REBIND_INPUT:?} to cancel rebinding"),
+                    TextStyle {
+                        font: asset_server.load(MAIN_FONT),
+                        font_size: 20.0,
+                        color: Color::GRAY,
+                    },
+                )
+                .with_text_alignment(TextAlignment::Center)
+                .with_style(Style {
+                    margin: UiRect::top(Val::Px(10.0)),
+                    ..default()
+                }),
+            );
+
+            parent
+                .spawn(
+                    TextBundle::from_section(
+                        "",
+                        TextStyle {
+                            font: asset_server.load(MAIN_FONT),
+                            font_size: 20.0,
+                            color: REBIND_FEEDBACK_COLOR,
+                        },
+                    )
+                    .with_text_alignment(TextAlignment::Center)
+                    .with_style(Style {
+                        margin: UiRect::top(Val::Px(5.0)),
+                        ..default()
+                    }),
+                )
+                .insert(RebindFeedbackText);
+
+            // back button
+            parent
+                .spawn(ButtonBundle {
+                    style: Style {
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        padding: UiRect::all(Val::Px(10.0)),
+                        margin: UiRect::top(Val::Px(15.0)),
+                        ..default()
+                    },
+                    background_color: theme.normal_button.into(),
+                    ..default()
+                })
+                .insert(KeyBindingsBackButton)
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "Back",
+                        TextStyle {
+                            font: asset_server.load(MAIN_FONT),
+                            font_size: 40.0,
+                            color: theme.button_text,
+                        },
+                    ));
+                });
+        });
+}
+
+/// Updates the text on every rebind button to match the current bindings
+fn update_binding_labels(
+    key_bindings: &KeyBindings,
+    rebinding: &RebindingAction,
+    binding_text_query: &mut Query<(&mut Text, &BindingText)>,
+) {
+    for (mut text, binding_text) in binding_text_query.iter_mut() {
+        text.sections[0].value = binding_text
+            .0
+            .get_label(key_bindings, rebinding.0 == Some(binding_text.0));
+    }
+}
+
+/// Handles interactions with the buttons for rebinding actions.
+fn rebind_button_system(
+    mut rebinding: ResMut<RebindingAction>,
+    key_bindings: Res<KeyBindings>,
+    interaction_query: Query<(&Interaction, &RebindButton), Changed<Interaction>>,
+    mut binding_text_query: Query<(&mut Text, &BindingText)>,
+) {
+    // while waiting for a new binding, clicks are potential bindings rather than button presses
+    if rebinding.0.is_some() {
+        return;
+    }
+
+    for (interaction, button) in interaction_query.iter() {
+        if *interaction == Interaction::Pressed {
+            rebinding.0 = Some(button.0);
+            update_binding_labels(&key_bindings, &rebinding, &mut binding_text_query);
+        }
+    }
+}
+
+type RebindFeedbackTextTuple = (With<RebindFeedbackText>, Without<BindingText>);
+
+/// Binds the next input the player presses to the action waiting for a new binding
+fn capture_rebind_input(
+    mut rebinding: ResMut<RebindingAction>,
+    mut key_bindings: ResMut<KeyBindings>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mouse_input: Res<Input<MouseButton>>,
+    current_profile: Res<CurrentProfile>,
+    mut binding_text_query: Query<(&mut Text, &BindingText)>,
+    mut feedback_text_query: Query<&mut Text, RebindFeedbackTextTuple>,
+) {
+    // rebinding just started this frame, so whatever was just pressed was meant for the button
+    if rebinding.is_changed() {
+        return;
+    }
+    let Some(action) = rebinding.0 else {
+        return;
+    };
+
+    if keyboard_input.just_pressed(CANCEL_REBIND_INPUT) {
+        rebinding.0 = None;
+        update_binding_labels(&key_bindings, &rebinding, &mut binding_text_query);
+        set_rebind_feedback("", &mut feedback_text_query);
+        return;
+    }
+
+    if action.get_key(&key_bindings).is_some() {
+        let Some(pressed) = keyboard_input.get_just_pressed().next().copied() else {
+            return;
+        };
+        if let Some(conflict) = get_rebind_conflict(action, pressed, &key_bindings) {
+            // keep waiting so the player can pick a different key
+            set_rebind_feedback(&conflict, &mut feedback_text_query);
+            return;
+        }
+        if let Some(key) = action.get_key_mut(&mut key_bindings) {
+            *key = pressed;
+        }
+    } else {
+        let Some(pressed) = mouse_input.get_just_pressed().next() else {
+            return;
+        };
+        key_bindings.attack = *pressed;
+    }
+
+    rebinding.0 = None;
+    persistence::save_for_profile(&current_profile, KEY_BINDINGS_KEY, &*key_bindings);
+    update_binding_labels(&key_bindings, &rebinding, &mut binding_text_query);
+    set_rebind_feedback("", &mut feedback_text_query);
+}
+
+/// Shows the provided message below the rebind buttons
+fn set_rebind_feedback(
+    message: &str,
+    feedback_text_query: &mut Query<&mut Text, RebindFeedbackTextTuple>,
+) {
+    for mut text in feedback_text_query.iter_mut() {
+        text.sections[0].value = message.to_string();
+    }
+}
+
+/// Stops waiting for a new binding, so leaving the screen doesn't leave an action half rebound
+fn stop_rebinding(mut rebinding: ResMut<RebindingAction>) {
+    rebinding.0 = None;
+}
+
+type InteractedKeyBindingsBackButtonTuple = (Changed<Interaction>, With<KeyBindingsBackButton>);
+
+/// Handles interactions with the back button on the key bindings screen.
+fn key_bindings_back_button_system(
+    mut next_state: ResMut<NextState<GameState>>,
+    interaction_query: Query<&Interaction, InteractedKeyBindingsBackButtonTuple>,
+) {
+    for interaction in interaction_query.iter() {
+        if *interaction == Interaction::Pressed {
+            next_state.set(GameState::Settings);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_bound_to_other_actions_are_rejected() {
+        let key_bindings = KeyBindings::default();
+
+        assert!(get_rebind_conflict(
+            BindableAction::MoveLeft,
+            key_bindings.move_right,
+            &key_bindings
+        )
+        .is_some());
+        assert!(get_rebind_conflict(
+            BindableAction::Pause,
+            key_bindings.secondary_action,
+            &key_bindings
+        )
+        .is_some());
+
+        // rebinding an action to the key it already has changes nothing, so it's allowed
+        assert_eq!(
+            None,
+            get_rebind_conflict(
+                BindableAction::MoveLeft,
+                key_bindings.move_left,
+                &key_bindings
+            )
+        );
+        assert_eq!(
+            None,
+            get_rebind_conflict(BindableAction::MoveLeft, KeyCode::Left, &key_bindings)
+        );
+    }
+
+    #[test]
+    fn reserved_keys_are_rejected() {
+        let key_bindings = KeyBindings::default();
+
+        for key in [
+            BULLET_TIME_INPUT,
+            DEBUG_OVERLAY_INPUT,
+            REWIND_INPUT,
+            CANCEL_REBIND_INPUT,
+        ]
+        .into_iter()
+        .chain(PERK_CHOICE_INPUTS)
+        {
+            assert!(
+                get_rebind_conflict(BindableAction::SecondaryAction, key, &key_bindings).is_some()
+            );
+        }
+    }
+}
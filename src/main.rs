@@ -3,6 +3,7 @@ use bevy::{
     diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin},
     input::common_conditions::input_toggle_active,
     prelude::*,
+    render::camera::Viewport,
     window::{WindowResized, WindowResolution},
 };
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
@@ -20,10 +21,41 @@ use game::*;
 mod game_over;
 use game_over::*;
 
+mod settings;
+use settings::*;
+
+mod mutators;
+use mutators::*;
+
+mod profiles;
+use profiles::*;
+
+mod build_code;
+use build_code::*;
+
+mod cosmetics;
+use cosmetics::*;
+
+mod codex;
+use codex::*;
+
+mod key_bindings;
+use key_bindings::*;
+
+mod persistence;
+use persistence::CurrentProfile;
+
 const DEV_MODE: bool = false;
+/// Whether to log a warning whenever a frame takes unusually long
+const LOG_FRAME_TIME_SPIKES: bool = false;
+/// Launch argument that enables the world inspector outside of dev mode, for debugging reported issues
+const INSPECTOR_ARG: &str = "--inspector";
+const INSPECTOR_TOGGLE_INPUT: KeyCode = KeyCode::Equals;
 
+const WINDOW_TITLE: &str = "Last Stand";
 const WINDOW_WIDTH: f32 = 1280.0;
 const WINDOW_HEIGHT: f32 = 720.0;
+const MIN_WINDOW_DIMENSION: f32 = 1.0;
 
 const NORMAL_BUTTON_TEXT_COLOR: Color = Color::rgb(0.9, 0.9, 0.9);
 
@@ -31,6 +63,12 @@ const NORMAL_BUTTON: Color = Color::rgb(0.25, 0.25, 0.25);
 const HOVERED_BUTTON: Color = Color::rgb(0.35, 0.35, 0.35);
 const PRESSED_BUTTON: Color = Color::rgb(0.35, 0.75, 0.35);
 
+const HIGH_CONTRAST_BUTTON_TEXT_COLOR: Color = Color::BLACK;
+
+const HIGH_CONTRAST_NORMAL_BUTTON: Color = Color::WHITE;
+const HIGH_CONTRAST_HOVERED_BUTTON: Color = Color::YELLOW;
+const HIGH_CONTRAST_PRESSED_BUTTON: Color = Color::rgb(0.0, 1.0, 0.0);
+
 const TITLE_FONT: &str = "fonts/SyneMono-Regular.ttf";
 const MAIN_FONT: &str = "fonts/SyneMono-Regular.ttf";
 const MONO_FONT: &str = "fonts/SyneMono-Regular.ttf";
@@ -41,7 +79,12 @@ const STARTING_ZOOM_LEVEL: f32 = 0.33;
 #[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States)]
 pub enum GameState {
     #[default]
+    Profiles,
     Menu,
+    Settings,
+    Mutators,
+    Codex,
+    KeyBindings,
     GameLoading,
     Game,
     GameOver,
@@ -56,6 +99,36 @@ pub struct ZoomLevel(pub f32);
 #[derive(Component)]
 pub struct DisabledButton;
 
+/// The colors used to draw buttons
+#[derive(Resource)]
+pub struct Theme {
+    pub normal_button: Color,
+    pub hovered_button: Color,
+    pub pressed_button: Color,
+    pub button_text: Color,
+}
+
+impl Theme {
+    /// Gets the theme to use with the provided settings
+    pub fn from_settings(settings: &Settings) -> Theme {
+        if settings.high_contrast {
+            Theme {
+                normal_button: HIGH_CONTRAST_NORMAL_BUTTON,
+                hovered_button: HIGH_CONTRAST_HOVERED_BUTTON,
+                pressed_button: HIGH_CONTRAST_PRESSED_BUTTON,
+                button_text: HIGH_CONTRAST_BUTTON_TEXT_COLOR,
+            }
+        } else {
+            Theme {
+                normal_button: NORMAL_BUTTON,
+                hovered_button: HOVERED_BUTTON,
+                pressed_button: PRESSED_BUTTON,
+                button_text: NORMAL_BUTTON_TEXT_COLOR,
+            }
+        }
+    }
+}
+
 fn main() {
     let mut app = App::new();
     app.insert_resource(AssetMetaCheck::Never)
@@ -65,7 +138,7 @@ fn main() {
         .insert_resource(ZoomLevel(STARTING_ZOOM_LEVEL))
         .add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
-                title: "Last Stand".into(),
+                title: WINDOW_TITLE.into(),
                 resolution: WindowResolution::new(WINDOW_WIDTH, WINDOW_HEIGHT),
                 // Tells wasm to resize the window according to the available canvas
                 fit_canvas_to_parent: true,
@@ -85,16 +158,40 @@ fn main() {
         .add_plugins(LookTransformPlugin)
         .add_state::<GameState>()
         .add_systems(Startup, setup)
-        .add_plugins((MenuPlugin, GamePlugin, GameOverPlugin))
-        .add_systems(Update, (zoom_based_on_window_size, button_color_system));
+        .add_plugins((
+            ProfilesPlugin,
+            MenuPlugin,
+            SettingsPlugin,
+            MutatorsPlugin,
+            GamePlugin,
+            GameOverPlugin,
+            CosmeticsPlugin,
+            CodexPlugin,
+            KeyBindingsPlugin,
+        ))
+        .add_systems(
+            Update,
+            (
+                zoom_based_on_window_size,
+                update_theme.run_if(resource_changed::<Settings>()),
+                button_color_system.after(update_theme),
+            ),
+        )
+        .add_systems(OnEnter(GameState::Menu), reset_window_title)
+        .add_systems(OnEnter(GameState::GameOver), reset_window_title);
+
+    // frame times are also used to keep spawning from bogging down slow machines, so they're always measured
+    app.add_plugins(FrameTimeDiagnosticsPlugin);
 
     if DEV_MODE {
         app.add_systems(Update, bevy::window::close_on_esc)
-            .add_plugins(LogDiagnosticsPlugin::default())
-            .add_plugins(FrameTimeDiagnosticsPlugin)
-            .add_plugins(
-                WorldInspectorPlugin::new().run_if(input_toggle_active(false, KeyCode::Equals)),
-            );
+            .add_plugins(LogDiagnosticsPlugin::default());
+    }
+
+    if DEV_MODE || std::env::args().any(|arg| arg == INSPECTOR_ARG) {
+        app.add_plugins(
+            WorldInspectorPlugin::new().run_if(input_toggle_active(false, INSPECTOR_TOGGLE_INPUT)),
+        );
     }
 
     app.run();
@@ -110,37 +207,122 @@ fn setup(mut commands: Commands) {
         .insert(MainCamera);
 }
 
-/// Adjusts the camera zoom when the window is resized
+/// Adjusts the camera zoom and viewport when the window is resized, or when the zoom level or settings change
 fn zoom_based_on_window_size(
-    mut camera_query: Query<&mut OrthographicProjection, With<MainCamera>>,
+    mut camera_query: Query<(&mut OrthographicProjection, &mut Camera), With<MainCamera>>,
     window_query: Query<&Window>,
     zoom_level: Res<ZoomLevel>,
+    settings: Res<Settings>,
     mut resize_reader: EventReader<WindowResized>,
 ) {
-    let mut projection = camera_query.single_mut();
+    let resized = resize_reader.read().count() > 0;
+    if !resized && !zoom_level.is_changed() && !settings.is_changed() {
+        return;
+    }
+
+    let (mut projection, mut camera) = camera_query.single_mut();
 
-    let mut base_scale = (WINDOW_WIDTH / window_query.single().width())
-        .min(WINDOW_HEIGHT / window_query.single().height());
-    /*
-    for event in resize_reader.read() {
-        base_scale = (WINDOW_WIDTH / event.width).max(WINDOW_HEIGHT / event.height);
+    let window = window_query.single();
+    if window.width() < MIN_WINDOW_DIMENSION || window.height() < MIN_WINDOW_DIMENSION {
+        // the window is minimized or otherwise too small to be worth zooming for, and zooming based on it would produce a nonsense scale
+        return;
     }
-    */
+
+    let viewport = if settings.lock_aspect_ratio {
+        Some(build_letterboxed_viewport(window))
+    } else {
+        None
+    };
+    // only touch the camera when the viewport actually changes, to avoid triggering change detection every frame
+    let viewport_bounds = |viewport: &Option<Viewport>| {
+        viewport
+            .as_ref()
+            .map(|viewport| (viewport.physical_position, viewport.physical_size))
+    };
+    if viewport_bounds(&camera.viewport) != viewport_bounds(&viewport) {
+        camera.viewport = viewport;
+    }
+
+    let base_scale = if let Some(viewport) = &camera.viewport {
+        // the viewport always has the base aspect ratio, so its width alone determines how much of the arena fits in it
+        let viewport_width = viewport.physical_size.x as f32 / window.scale_factor() as f32;
+        WINDOW_WIDTH / viewport_width
+    } else {
+        // zoom out far enough in whichever direction the window is short on, so the whole base area stays in view
+        (WINDOW_WIDTH / window.width()).max(WINDOW_HEIGHT / window.height())
+    };
 
     projection.scale = base_scale * zoom_level.0;
 }
 
+/// Builds a viewport with the base aspect ratio that fits as much of the provided window as possible, centered in it
+fn build_letterboxed_viewport(window: &Window) -> Viewport {
+    let target_aspect_ratio = WINDOW_WIDTH / WINDOW_HEIGHT;
+    let window_size = Vec2::new(
+        window.physical_width() as f32,
+        window.physical_height() as f32,
+    );
+
+    let viewport_size = if window_size.x / window_size.y > target_aspect_ratio {
+        // window is wider than the target, so add bars on the sides
+        Vec2::new(window_size.y * target_aspect_ratio, window_size.y)
+    } else {
+        // window is taller than the target, so add bars on the top and bottom
+        Vec2::new(window_size.x, window_size.x / target_aspect_ratio)
+    };
+    let viewport_position = (window_size - viewport_size) / 2.0;
+
+    Viewport {
+        physical_position: viewport_position.as_uvec2(),
+        physical_size: viewport_size.as_uvec2().max(UVec2::ONE),
+        ..default()
+    }
+}
+
+/// Sets the window title back to the base title
+fn reset_window_title(mut window_query: Query<&mut Window>) {
+    for mut window in window_query.iter_mut() {
+        window.title = WINDOW_TITLE.to_string();
+    }
+}
+
+type EnabledButtonTuple = (With<Button>, Without<DisabledButton>);
+
+/// Keeps the theme in line with the settings, and recolors any existing buttons to match it
+fn update_theme(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    mut button_query: Query<(&mut BackgroundColor, &Children), EnabledButtonTuple>,
+    mut text_query: Query<&mut Text>,
+) {
+    let theme = Theme::from_settings(&settings);
+
+    for (mut color, children) in button_query.iter_mut() {
+        *color = theme.normal_button.into();
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                for section in text.sections.iter_mut() {
+                    section.style.color = theme.button_text;
+                }
+            }
+        }
+    }
+
+    commands.insert_resource(theme);
+}
+
 type InteractedButtonTuple = (Changed<Interaction>, With<Button>, Without<DisabledButton>);
 
 /// Handles changing button colors when they're interacted with.
 fn button_color_system(
     mut interaction_query: Query<(&Interaction, &mut BackgroundColor), InteractedButtonTuple>,
+    theme: Res<Theme>,
 ) {
     for (interaction, mut color) in interaction_query.iter_mut() {
         *color = match *interaction {
-            Interaction::Pressed => PRESSED_BUTTON.into(),
-            Interaction::Hovered => HOVERED_BUTTON.into(),
-            Interaction::None => NORMAL_BUTTON.into(),
+            Interaction::Pressed => theme.pressed_button.into(),
+            Interaction::Hovered => theme.hovered_button.into(),
+            Interaction::None => theme.normal_button.into(),
         }
     }
 }
@@ -158,3 +340,99 @@ fn despawn_components<T: Component>(to_despawn: Query<Entity, With<T>>, commands
         commands.entity(entity).despawn_recursive();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    #[test]
+    fn minimized_window_does_not_break_zoom() {
+        let mut world = World::new();
+        world.init_resource::<Events<WindowResized>>();
+        world.insert_resource(ZoomLevel(1.0));
+        world.insert_resource(Settings::default());
+        let window = world
+            .spawn(Window {
+                resolution: WindowResolution::new(0.0, 0.0),
+                ..default()
+            })
+            .id();
+        let camera = world
+            .spawn((
+                Camera::default(),
+                OrthographicProjection::default(),
+                MainCamera,
+            ))
+            .id();
+
+        // minimize
+        world.send_event(WindowResized {
+            window,
+            width: 0.0,
+            height: 0.0,
+        });
+        world.run_system_once(zoom_based_on_window_size);
+        let scale = world.get::<OrthographicProjection>(camera).unwrap().scale;
+        assert_eq!(1.0, scale);
+
+        // restore
+        world
+            .get_mut::<Window>(window)
+            .unwrap()
+            .resolution
+            .set(WINDOW_WIDTH, WINDOW_HEIGHT);
+        world.send_event(WindowResized {
+            window,
+            width: WINDOW_WIDTH,
+            height: WINDOW_HEIGHT,
+        });
+        world.run_system_once(zoom_based_on_window_size);
+        let scale = world.get::<OrthographicProjection>(camera).unwrap().scale;
+        assert_eq!(1.0, scale);
+    }
+
+    #[test]
+    fn game_systems_run_before_the_game_is_set_up() {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin::default(),
+            WindowPlugin {
+                primary_window: None,
+                ..default()
+            },
+            bevy::input::InputPlugin,
+            TweeningPlugin,
+        ))
+        .init_asset::<Font>()
+        .init_asset::<Image>()
+        .init_asset::<ColorMaterial>()
+        .init_asset::<AudioSource>()
+        .add_state::<GameState>()
+        .add_systems(Startup, setup)
+        .add_plugins((
+            ProfilesPlugin,
+            MenuPlugin,
+            SettingsPlugin,
+            MutatorsPlugin,
+            GamePlugin,
+            GameOverPlugin,
+            CosmeticsPlugin,
+            CodexPlugin,
+            KeyBindingsPlugin,
+        ));
+        app.world.spawn(Window::default());
+
+        // the app starts out on the profiles screen, so none of the game-only resources exist yet
+        for _ in 0..3 {
+            app.update();
+        }
+
+        assert_eq!(
+            GameState::Profiles,
+            *app.world.resource::<State<GameState>>().get()
+        );
+    }
+}
@@ -1,25 +1,267 @@
+#[cfg(not(target_arch = "wasm32"))]
+use bevy::app::AppExit;
+use bevy::window::ReceivedCharacter;
+use strum::EnumIter;
+
 use crate::*;
 
+const MAX_BUILD_CODE_LENGTH: usize = 24;
+
 pub struct MenuPlugin;
 
 impl Plugin for MenuPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::Menu), menu_setup)
+        app.insert_resource(SelectedLoadout(Loadout::Standard))
+            .insert_resource(SelectedDifficulty(Difficulty::Normal))
+            .insert_resource(SelectedSpawnMode(SpawnMode::Continuous))
+            .insert_resource(ImportedBuild(None))
+            .insert_resource(BuildCodeInput(String::new()))
+            .add_systems(OnEnter(GameState::Menu), menu_setup)
             .add_systems(
                 OnExit(GameState::Menu),
                 despawn_components_system::<MenuComponent>,
             )
-            .add_systems(Update, start_button_system);
+            .add_systems(
+                Update,
+                (
+                    start_button_system,
+                    difficulty_button_system,
+                    spawn_mode_button_system,
+                    loadout_button_system,
+                    options_button_system,
+                    codex_button_system,
+                    profile_button_system,
+                    build_code_input_system.run_if(in_state(GameState::Menu)),
+                    import_build_button_system,
+                ),
+            );
+
+        // there's no way to close the browser tab from inside the game
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_systems(Update, quit_button_system);
+    }
+}
+
+/// A set of upgrades the player starts a run with
+#[derive(PartialEq, Eq, Clone, Copy, Debug, EnumIter)]
+pub enum Loadout {
+    /// No upgrades
+    Standard,
+    /// A shorter attack cooldown
+    Swordmaster,
+    /// Teleport unlocked
+    Teleporter,
+    /// Higher max health
+    Tank,
+}
+
+impl Loadout {
+    /// Gets the user-facing name of this loadout
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Loadout::Standard => "Standard",
+            Loadout::Swordmaster => "Swordmaster",
+            Loadout::Teleporter => "Teleporter",
+            Loadout::Tank => "Tank",
+        }
+    }
+
+    /// Gets the loadout that comes after this one when cycling through them
+    fn next(&self) -> Loadout {
+        match self {
+            Loadout::Standard => Loadout::Swordmaster,
+            Loadout::Swordmaster => Loadout::Teleporter,
+            Loadout::Teleporter => Loadout::Tank,
+            Loadout::Tank => Loadout::Standard,
+        }
     }
 }
 
+/// The loadout chosen for the next run
+#[derive(Resource)]
+pub struct SelectedLoadout(pub Loadout);
+
+/// How hard a run is
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    /// Gets the user-facing name of this difficulty
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+        }
+    }
+
+    /// Gets the difficulty that comes after this one when cycling through them
+    fn next(&self) -> Difficulty {
+        match self {
+            Difficulty::Easy => Difficulty::Normal,
+            Difficulty::Normal => Difficulty::Hard,
+            Difficulty::Hard => Difficulty::Easy,
+        }
+    }
+
+    /// Gets the multiplier to apply to the time between enemy spawns at the start of a run
+    pub fn get_spawn_interval_multiplier(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 1.5,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 0.6,
+        }
+    }
+
+    /// Determines whether the player gets knocked down instead of dying the first time they run out of health,
+    /// with a chance to get back up
+    pub fn allows_downed(&self) -> bool {
+        match self {
+            Difficulty::Easy => true,
+            Difficulty::Normal => false,
+            Difficulty::Hard => false,
+        }
+    }
+
+    /// Gets the multiplier to apply to the XP enemies give, to make up for the extra danger on harder difficulties
+    pub fn get_xp_multiplier(&self) -> u64 {
+        match self {
+            Difficulty::Easy => 1,
+            Difficulty::Normal => 1,
+            Difficulty::Hard => 2,
+        }
+    }
+}
+
+/// The difficulty chosen for the next run
+#[derive(Resource)]
+pub struct SelectedDifficulty(pub Difficulty);
+
+/// How enemies are sent at the player during a run
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum SpawnMode {
+    /// Enemies trickle in constantly, faster and faster over time
+    Continuous,
+    /// Enemies come in batches, with a bigger one coming once the last one is mostly cleared out
+    Waves,
+}
+
+impl SpawnMode {
+    /// Gets the user-facing name of this spawn mode
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            SpawnMode::Continuous => "Continuous",
+            SpawnMode::Waves => "Waves",
+        }
+    }
+
+    /// Gets the spawn mode that comes after this one when cycling through them
+    fn next(&self) -> SpawnMode {
+        match self {
+            SpawnMode::Continuous => SpawnMode::Waves,
+            SpawnMode::Waves => SpawnMode::Continuous,
+        }
+    }
+}
+
+/// The spawn mode chosen for the next run
+#[derive(Resource)]
+pub struct SelectedSpawnMode(pub SpawnMode);
+
 #[derive(Component)]
 struct MenuComponent;
 
 #[derive(Component)]
 struct StartButton;
 
-fn menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+#[derive(Component)]
+struct DifficultyButton;
+
+#[derive(Component)]
+struct SpawnModeButton;
+
+#[derive(Component)]
+struct LoadoutButton;
+
+#[derive(Component)]
+struct OptionsButton;
+
+#[derive(Component)]
+struct CodexButton;
+
+#[derive(Component)]
+struct ProfileButton;
+
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Component)]
+struct QuitButton;
+
+/// The build code being typed in to be imported
+#[derive(Resource)]
+struct BuildCodeInput(String);
+
+#[derive(Component)]
+struct BuildCodeText;
+
+#[derive(Component)]
+struct BuildCodeStatusText;
+
+#[derive(Component)]
+struct ImportBuildButton;
+
+/// Gets the user-facing label for the build code being typed in
+fn get_build_code_label(build_code_input: &BuildCodeInput) -> String {
+    format!("Build code: {}_", build_code_input.0)
+}
+
+/// Gets the user-facing text describing the imported build, if there is one
+fn get_imported_build_status(imported_build: &ImportedBuild) -> String {
+    match &imported_build.0 {
+        Some(build) => format!("Next run will use: {}", build.get_summary()),
+        None => String::new(),
+    }
+}
+
+/// Gets the user-facing label for the difficulty button, including the XP bonus if there is one
+fn get_difficulty_label(difficulty: Difficulty) -> String {
+    let xp_multiplier = difficulty.get_xp_multiplier();
+    if xp_multiplier > 1 {
+        format!(
+            "Difficulty: {} ({xp_multiplier}x XP)",
+            difficulty.get_name()
+        )
+    } else {
+        format!("Difficulty: {}", difficulty.get_name())
+    }
+}
+
+/// Gets the user-facing label for the spawn mode button
+fn get_spawn_mode_label(spawn_mode: SpawnMode) -> String {
+    format!("Spawning: {}", spawn_mode.get_name())
+}
+
+/// Gets the user-facing label for the loadout button
+fn get_loadout_label(loadout: Loadout) -> String {
+    format!("Loadout: {}", loadout.get_name())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn menu_setup(
+    mut commands: Commands,
+    selected_difficulty: Res<SelectedDifficulty>,
+    selected_spawn_mode: Res<SelectedSpawnMode>,
+    selected_loadout: Res<SelectedLoadout>,
+    current_profile: Res<CurrentProfile>,
+    build_code_input: Res<BuildCodeInput>,
+    imported_build: Res<ImportedBuild>,
+    cosmetics: Res<Cosmetics>,
+    theme: Res<Theme>,
+    asset_server: Res<AssetServer>,
+) {
     // title text
     commands
         .spawn(NodeBundle {
@@ -27,6 +269,7 @@ fn menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                 width: Val::Percent(100.0),
                 height: Val::Percent(50.0),
                 position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::Column,
                 justify_content: JustifyContent::Center,
                 align_items: AlignItems::Center,
                 ..default()
@@ -44,22 +287,35 @@ fn menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                         color: Color::rgb(0.9, 0.2, 0.2),
                     },
                 )
-                .with_text_alignment(TextAlignment::Center)
-                .with_style(Style {
-                    margin: UiRect::all(Val::Auto),
-                    ..default()
-                }),
+                .with_text_alignment(TextAlignment::Center),
             );
+
+            // best run, if there's been one
+            let best_level = cosmetics.lifetime_stats.best_level;
+            if best_level > 0 {
+                parent.spawn(
+                    TextBundle::from_section(
+                        format!("Best: level {best_level}"),
+                        TextStyle {
+                            font: asset_server.load(MAIN_FONT),
+                            font_size: 25.0,
+                            color: Color::GRAY,
+                        },
+                    )
+                    .with_text_alignment(TextAlignment::Center),
+                );
+            }
         });
 
-    // start button
+    // buttons
     commands
         .spawn(NodeBundle {
             style: Style {
-                // center button
+                // center buttons
                 width: Val::Percent(100.00),
                 position_type: PositionType::Absolute,
                 top: Val::Percent(50.0),
+                flex_direction: FlexDirection::Column,
                 justify_content: JustifyContent::Center,
                 align_items: AlignItems::Center,
                 ..default()
@@ -67,29 +323,148 @@ fn menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
             ..default()
         })
         .insert(MenuComponent)
+        .with_children(|parent| {
+            spawn_menu_button("Begin", StartButton, parent, &theme, &asset_server);
+            spawn_menu_button(
+                &get_difficulty_label(selected_difficulty.0),
+                DifficultyButton,
+                parent,
+                &theme,
+                &asset_server,
+            );
+            spawn_menu_button(
+                &get_spawn_mode_label(selected_spawn_mode.0),
+                SpawnModeButton,
+                parent,
+                &theme,
+                &asset_server,
+            );
+            spawn_menu_button(
+                &get_loadout_label(selected_loadout.0),
+                LoadoutButton,
+                parent,
+                &theme,
+                &asset_server,
+            );
+            spawn_menu_button("Options", OptionsButton, parent, &theme, &asset_server);
+            spawn_menu_button("Codex", CodexButton, parent, &theme, &asset_server);
+            spawn_menu_button(
+                &format!("Profile: {}", current_profile.0),
+                ProfileButton,
+                parent,
+                &theme,
+                &asset_server,
+            );
+            #[cfg(not(target_arch = "wasm32"))]
+            spawn_menu_button("Quit", QuitButton, parent, &theme, &asset_server);
+        });
+
+    // build import
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(10.0),
+                bottom: Val::Px(10.0),
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            ..default()
+        })
+        .insert(MenuComponent)
         .with_children(|parent| {
             parent
-                .spawn(ButtonBundle {
+                .spawn(NodeBundle {
                     style: Style {
-                        justify_content: JustifyContent::Center,
+                        flex_direction: FlexDirection::Row,
                         align_items: AlignItems::Center,
-                        padding: UiRect::all(Val::Px(10.0)),
                         ..default()
                     },
-                    background_color: NORMAL_BUTTON.into(),
                     ..default()
                 })
-                .insert(StartButton)
                 .with_children(|parent| {
-                    parent.spawn(TextBundle::from_section(
-                        "Begin",
-                        TextStyle {
-                            font: asset_server.load(MAIN_FONT),
-                            font_size: 40.0,
-                            color: NORMAL_BUTTON_TEXT_COLOR,
-                        },
-                    ));
+                    parent
+                        .spawn(
+                            TextBundle::from_section(
+                                get_build_code_label(&build_code_input),
+                                TextStyle {
+                                    font: asset_server.load(MONO_FONT),
+                                    font_size: 25.0,
+                                    color: Color::WHITE,
+                                },
+                            )
+                            .with_style(Style {
+                                margin: UiRect::right(Val::Px(10.0)),
+                                ..default()
+                            }),
+                        )
+                        .insert(BuildCodeText);
+                    parent
+                        .spawn(ButtonBundle {
+                            style: Style {
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                padding: UiRect::all(Val::Px(5.0)),
+                                ..default()
+                            },
+                            background_color: theme.normal_button.into(),
+                            ..default()
+                        })
+                        .insert(ImportBuildButton)
+                        .with_children(|parent| {
+                            parent.spawn(TextBundle::from_section(
+                                "Import",
+                                TextStyle {
+                                    font: asset_server.load(MAIN_FONT),
+                                    font_size: 25.0,
+                                    color: theme.button_text,
+                                },
+                            ));
+                        });
                 });
+            parent
+                .spawn(TextBundle::from_section(
+                    get_imported_build_status(&imported_build),
+                    TextStyle {
+                        font: asset_server.load(MAIN_FONT),
+                        font_size: 20.0,
+                        color: Color::GRAY,
+                    },
+                ))
+                .insert(BuildCodeStatusText);
+        });
+}
+
+/// Spawns a menu button with the provided text and marker component
+fn spawn_menu_button(
+    text: &str,
+    marker: impl Component,
+    parent: &mut ChildBuilder,
+    theme: &Theme,
+    asset_server: &Res<AssetServer>,
+) {
+    parent
+        .spawn(ButtonBundle {
+            style: Style {
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                padding: UiRect::all(Val::Px(10.0)),
+                margin: UiRect::bottom(Val::Px(10.0)),
+                ..default()
+            },
+            background_color: theme.normal_button.into(),
+            ..default()
+        })
+        .insert(marker)
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                text,
+                TextStyle {
+                    font: asset_server.load(MAIN_FONT),
+                    font_size: 40.0,
+                    color: theme.button_text,
+                },
+            ));
         });
 }
 
@@ -102,7 +477,224 @@ fn start_button_system(
 ) {
     for interaction in interaction_query.iter() {
         if *interaction == Interaction::Pressed {
-            next_state.set(GameState::GameLoading);
+            next_state.set(GameState::Mutators);
+        }
+    }
+}
+
+type InteractedDifficultyButtonTuple = (Changed<Interaction>, With<DifficultyButton>);
+
+/// Handles interactions with the difficulty button.
+fn difficulty_button_system(
+    mut selected_difficulty: ResMut<SelectedDifficulty>,
+    interaction_query: Query<(&Interaction, &Children), InteractedDifficultyButtonTuple>,
+    mut text_query: Query<&mut Text>,
+) {
+    for (interaction, children) in interaction_query.iter() {
+        if *interaction == Interaction::Pressed {
+            selected_difficulty.0 = selected_difficulty.0.next();
+
+            for child in children.iter() {
+                if let Ok(mut text) = text_query.get_mut(*child) {
+                    text.sections[0].value = get_difficulty_label(selected_difficulty.0);
+                }
+            }
+        }
+    }
+}
+
+type InteractedSpawnModeButtonTuple = (Changed<Interaction>, With<SpawnModeButton>);
+
+/// Handles interactions with the spawn mode button.
+fn spawn_mode_button_system(
+    mut selected_spawn_mode: ResMut<SelectedSpawnMode>,
+    interaction_query: Query<(&Interaction, &Children), InteractedSpawnModeButtonTuple>,
+    mut text_query: Query<&mut Text>,
+) {
+    for (interaction, children) in interaction_query.iter() {
+        if *interaction == Interaction::Pressed {
+            selected_spawn_mode.0 = selected_spawn_mode.0.next();
+
+            for child in children.iter() {
+                if let Ok(mut text) = text_query.get_mut(*child) {
+                    text.sections[0].value = get_spawn_mode_label(selected_spawn_mode.0);
+                }
+            }
+        }
+    }
+}
+
+type InteractedLoadoutButtonTuple = (Changed<Interaction>, With<LoadoutButton>);
+
+/// Handles interactions with the loadout button.
+fn loadout_button_system(
+    mut selected_loadout: ResMut<SelectedLoadout>,
+    interaction_query: Query<(&Interaction, &Children), InteractedLoadoutButtonTuple>,
+    mut text_query: Query<&mut Text>,
+) {
+    for (interaction, children) in interaction_query.iter() {
+        if *interaction == Interaction::Pressed {
+            selected_loadout.0 = selected_loadout.0.next();
+
+            for child in children.iter() {
+                if let Ok(mut text) = text_query.get_mut(*child) {
+                    text.sections[0].value = get_loadout_label(selected_loadout.0);
+                }
+            }
+        }
+    }
+}
+
+type InteractedOptionsButtonTuple = (Changed<Interaction>, With<OptionsButton>);
+
+/// Handles interactions with the options button.
+fn options_button_system(
+    mut next_state: ResMut<NextState<GameState>>,
+    interaction_query: Query<&Interaction, InteractedOptionsButtonTuple>,
+) {
+    for interaction in interaction_query.iter() {
+        if *interaction == Interaction::Pressed {
+            next_state.set(GameState::Settings);
+        }
+    }
+}
+
+type InteractedCodexButtonTuple = (Changed<Interaction>, With<CodexButton>);
+
+/// Handles interactions with the codex button.
+fn codex_button_system(
+    mut next_state: ResMut<NextState<GameState>>,
+    interaction_query: Query<&Interaction, InteractedCodexButtonTuple>,
+) {
+    for interaction in interaction_query.iter() {
+        if *interaction == Interaction::Pressed {
+            next_state.set(GameState::Codex);
+        }
+    }
+}
+
+type InteractedProfileButtonTuple = (Changed<Interaction>, With<ProfileButton>);
+
+/// Handles interactions with the profile button.
+fn profile_button_system(
+    mut next_state: ResMut<NextState<GameState>>,
+    interaction_query: Query<&Interaction, InteractedProfileButtonTuple>,
+) {
+    for interaction in interaction_query.iter() {
+        if *interaction == Interaction::Pressed {
+            next_state.set(GameState::Profiles);
+        }
+    }
+}
+
+/// Handles typing in a build code to import
+fn build_code_input_system(
+    mut received_characters: EventReader<ReceivedCharacter>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut build_code_input: ResMut<BuildCodeInput>,
+    mut build_code_text_query: Query<&mut Text, With<BuildCodeText>>,
+) {
+    let mut changed = false;
+    for event in received_characters.read() {
+        // build codes are only ever made of hex digits and separators
+        let allowed = event.char.is_ascii_hexdigit() || event.char == '-';
+        if allowed && build_code_input.0.len() < MAX_BUILD_CODE_LENGTH {
+            build_code_input.0.push(event.char.to_ascii_lowercase());
+            changed = true;
+        }
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Back) {
+        build_code_input.0.pop();
+        changed = true;
+    }
+
+    if changed {
+        for mut text in build_code_text_query.iter_mut() {
+            text.sections[0].value = get_build_code_label(&build_code_input);
+        }
+    }
+}
+
+type InteractedImportBuildButtonTuple = (Changed<Interaction>, With<ImportBuildButton>);
+
+/// Handles interactions with the button for importing a build code.
+#[allow(clippy::too_many_arguments)]
+fn import_build_button_system(
+    interaction_query: Query<&Interaction, InteractedImportBuildButtonTuple>,
+    build_code_input: Res<BuildCodeInput>,
+    mut imported_build: ResMut<ImportedBuild>,
+    mut selected_loadout: ResMut<SelectedLoadout>,
+    mut active_mutators: ResMut<ActiveMutators>,
+    loadout_button_query: Query<&Children, With<LoadoutButton>>,
+    status_text_query: Query<Entity, With<BuildCodeStatusText>>,
+    mut text_query: Query<&mut Text>,
+) {
+    for interaction in interaction_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        let status = match Build::from_code(&build_code_input.0) {
+            Some(build) => {
+                selected_loadout.0 = build.loadout;
+                active_mutators.0 = build.mutators.clone();
+                imported_build.0 = Some(build);
+
+                for children in loadout_button_query.iter() {
+                    for child in children.iter() {
+                        if let Ok(mut text) = text_query.get_mut(*child) {
+                            text.sections[0].value = get_loadout_label(selected_loadout.0);
+                        }
+                    }
+                }
+
+                get_imported_build_status(&imported_build)
+            }
+            None => "That's not a valid build code".to_string(),
+        };
+
+        for entity in status_text_query.iter() {
+            if let Ok(mut text) = text_query.get_mut(entity) {
+                text.sections[0].value = status.clone();
+            }
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+type InteractedQuitButtonTuple = (Changed<Interaction>, With<QuitButton>);
+
+/// Handles interactions with the quit button.
+#[cfg(not(target_arch = "wasm32"))]
+fn quit_button_system(
+    mut app_exit_events: EventWriter<AppExit>,
+    interaction_query: Query<&Interaction, InteractedQuitButtonTuple>,
+) {
+    for interaction in interaction_query.iter() {
+        if *interaction == Interaction::Pressed {
+            app_exit_events.send(AppExit);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xp_multiplier_for_each_difficulty() {
+        let base_xp_reward = 3;
+        for (difficulty, expected_xp_reward) in [
+            (Difficulty::Easy, 3),
+            (Difficulty::Normal, 3),
+            (Difficulty::Hard, 6),
+        ] {
+            assert_eq!(
+                expected_xp_reward,
+                base_xp_reward * difficulty.get_xp_multiplier(),
+                "wrong XP reward for {difficulty:?}"
+            );
         }
     }
 }
@@ -0,0 +1,302 @@
+use std::collections::HashSet;
+
+use strum::{EnumIter, IntoEnumIterator};
+
+use crate::*;
+
+pub const MAX_ACTIVE_MUTATORS: usize = 2;
+
+pub struct MutatorsPlugin;
+
+impl Plugin for MutatorsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ActiveMutators(HashSet::new()))
+            .add_systems(OnEnter(GameState::Mutators), mutators_setup)
+            .add_systems(
+                OnExit(GameState::Mutators),
+                despawn_components_system::<MutatorsComponent>,
+            )
+            .add_systems(
+                Update,
+                (
+                    mutator_button_system,
+                    mutators_start_button_system,
+                    mutators_back_button_system,
+                )
+                    .run_if(in_state(GameState::Mutators)),
+            );
+    }
+}
+
+/// A rule change that lasts for a whole run
+#[derive(PartialEq, Eq, Clone, Copy, Hash, Debug, EnumIter)]
+pub enum Mutator {
+    /// Enemies move faster, but give more XP
+    Frenzy,
+    /// No secondary actions, but a longer sword
+    Bladesworn,
+    /// Less health, but faster attacks
+    GlassCannon,
+    /// Leveling up doesn't give any perks
+    Purist,
+}
+
+impl Mutator {
+    /// Gets the user-facing name and description of this mutator
+    fn get_name_and_description(&self) -> (&'static str, &'static str) {
+        match self {
+            Mutator::Frenzy => ("Frenzy", "Enemies move 20% faster, but give double XP"),
+            Mutator::Bladesworn => (
+                "Bladesworn",
+                "No secondary actions, but you start with a 50% longer sword",
+            ),
+            Mutator::GlassCannon => (
+                "Glass Cannon",
+                "Half max health, but your attack cooldown is halved",
+            ),
+            Mutator::Purist => ("Purist", "Leveling up doesn't give you any perks"),
+        }
+    }
+}
+
+/// The mutators chosen for the current run
+#[derive(Resource)]
+pub struct ActiveMutators(pub HashSet<Mutator>);
+
+impl ActiveMutators {
+    /// Determines whether the provided mutator is active
+    pub fn has(&self, mutator: Mutator) -> bool {
+        self.0.contains(&mutator)
+    }
+}
+
+#[derive(Component)]
+struct MutatorsComponent;
+
+#[derive(Component)]
+struct MutatorButton(Mutator);
+
+#[derive(Component)]
+struct MutatorText(Mutator);
+
+#[derive(Component)]
+struct MutatorsStartButton;
+
+#[derive(Component)]
+struct MutatorsBackButton;
+
+/// Gets the user-facing label for a mutator button
+fn get_mutator_label(mutator: Mutator, active_mutators: &ActiveMutators) -> String {
+    let check = if active_mutators.has(mutator) {
+        "[x]"
+    } else {
+        "[ ]"
+    };
+    let (name, _) = mutator.get_name_and_description();
+    format!("{check} {name}")
+}
+
+fn mutators_setup(
+    mut commands: Commands,
+    active_mutators: Res<ActiveMutators>,
+    theme: Res<Theme>,
+    asset_server: Res<AssetServer>,
+) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .insert(MutatorsComponent)
+        .with_children(|parent| {
+            // title
+            parent.spawn(
+                TextBundle::from_section(
+                    "Mutators",
+                    TextStyle {
+                        font: asset_server.load(TITLE_FONT),
+                        font_size: 60.0,
+                        color: Color::WHITE,
+                    },
+                )
+                .with_text_alignment(TextAlignment::Center),
+            );
+
+            parent.spawn(
+                TextBundle::from_section(
+                    format!("Choose up to {MAX_ACTIVE_MUTATORS} rule changes for this run"),
+                    TextStyle {
+                        font: asset_server.load(MAIN_FONT),
+                        font_size: 25.0,
+                        color: Color::GRAY,
+                    },
+                )
+                .with_text_alignment(TextAlignment::Center)
+                .with_style(Style {
+                    margin: UiRect::bottom(Val::Px(15.0)),
+                    ..default()
+                }),
+            );
+
+            // mutator buttons
+            for mutator in Mutator::iter() {
+                let (_, description) = mutator.get_name_and_description();
+                parent
+                    .spawn(ButtonBundle {
+                        style: Style {
+                            width: Val::Percent(60.0),
+                            flex_direction: FlexDirection::Column,
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            padding: UiRect::all(Val::Px(8.0)),
+                            margin: UiRect::all(Val::Px(4.0)),
+                            ..default()
+                        },
+                        background_color: theme.normal_button.into(),
+                        ..default()
+                    })
+                    .insert(MutatorButton(mutator))
+                    .with_children(|parent| {
+                        parent
+                            .spawn(TextBundle::from_section(
+                                get_mutator_label(mutator, &active_mutators),
+                                TextStyle {
+                                    font: asset_server.load(MAIN_FONT),
+                                    font_size: 30.0,
+                                    color: theme.button_text,
+                                },
+                            ))
+                            .insert(MutatorText(mutator));
+                        parent.spawn(
+                            TextBundle::from_section(
+                                description,
+                                TextStyle {
+                                    font: asset_server.load(MAIN_FONT),
+                                    font_size: 20.0,
+                                    color: theme.button_text,
+                                },
+                            )
+                            .with_text_alignment(TextAlignment::Center),
+                        );
+                    });
+            }
+
+            // start and back buttons
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        margin: UiRect::top(Val::Px(15.0)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    spawn_mutators_screen_button(
+                        "Back",
+                        MutatorsBackButton,
+                        parent,
+                        &theme,
+                        &asset_server,
+                    );
+                    spawn_mutators_screen_button(
+                        "Start",
+                        MutatorsStartButton,
+                        parent,
+                        &theme,
+                        &asset_server,
+                    );
+                });
+        });
+}
+
+/// Spawns a button along the bottom of the mutators screen
+fn spawn_mutators_screen_button(
+    text: &str,
+    marker: impl Component,
+    parent: &mut ChildBuilder,
+    theme: &Theme,
+    asset_server: &Res<AssetServer>,
+) {
+    parent
+        .spawn(ButtonBundle {
+            style: Style {
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                padding: UiRect::all(Val::Px(10.0)),
+                margin: UiRect::horizontal(Val::Px(10.0)),
+                ..default()
+            },
+            background_color: theme.normal_button.into(),
+            ..default()
+        })
+        .insert(marker)
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                text,
+                TextStyle {
+                    font: asset_server.load(MAIN_FONT),
+                    font_size: 40.0,
+                    color: theme.button_text,
+                },
+            ));
+        });
+}
+
+/// Handles interactions with the buttons for individual mutators.
+fn mutator_button_system(
+    mut active_mutators: ResMut<ActiveMutators>,
+    interaction_query: Query<(&Interaction, &MutatorButton), Changed<Interaction>>,
+    mut mutator_text_query: Query<(&mut Text, &MutatorText)>,
+) {
+    for (interaction, button) in interaction_query.iter() {
+        if *interaction == Interaction::Pressed {
+            if active_mutators.has(button.0) {
+                active_mutators.0.remove(&button.0);
+            } else if active_mutators.0.len() < MAX_ACTIVE_MUTATORS {
+                active_mutators.0.insert(button.0);
+            }
+
+            for (mut text, mutator_text) in mutator_text_query.iter_mut() {
+                text.sections[0].value = get_mutator_label(mutator_text.0, &active_mutators);
+            }
+        }
+    }
+}
+
+type InteractedMutatorsStartButtonTuple = (Changed<Interaction>, With<MutatorsStartButton>);
+
+/// Handles interactions with the start button on the mutators screen.
+fn mutators_start_button_system(
+    mut next_state: ResMut<NextState<GameState>>,
+    interaction_query: Query<&Interaction, InteractedMutatorsStartButtonTuple>,
+    image_assets: Option<Res<ImageAssets>>,
+    audio_assets: Option<Res<AudioAssets>>,
+) {
+    for interaction in interaction_query.iter() {
+        if *interaction == Interaction::Pressed {
+            next_state.set(get_game_start_state(&image_assets, &audio_assets));
+        }
+    }
+}
+
+type InteractedMutatorsBackButtonTuple = (Changed<Interaction>, With<MutatorsBackButton>);
+
+/// Handles interactions with the back button on the mutators screen.
+fn mutators_back_button_system(
+    mut next_state: ResMut<NextState<GameState>>,
+    interaction_query: Query<&Interaction, InteractedMutatorsBackButtonTuple>,
+) {
+    for interaction in interaction_query.iter() {
+        if *interaction == Interaction::Pressed {
+            next_state.set(GameState::Menu);
+        }
+    }
+}
@@ -0,0 +1,290 @@
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::*;
+
+#[cfg(not(target_arch = "wasm32"))]
+const SAVE_DIRECTORY_NAME: &str = "last-stand";
+
+pub const DEFAULT_PROFILE: &str = "Default";
+const PROFILE_SEPARATOR: &str = "/";
+/// Logs have one JSON value per line, rather than being a single JSON value like saved data
+const LOG_EXTENSION: &str = "jsonl";
+
+/// The profile whose data is being loaded and saved
+#[derive(Resource)]
+pub struct CurrentProfile(pub String);
+
+impl CurrentProfile {
+    /// Gets the key data with the provided key is stored under for this profile.
+    /// The default profile's data isn't namespaced, so anything saved from before profiles existed still belongs to it.
+    fn get_profile_key(&self, key: &str) -> String {
+        if self.0 == DEFAULT_PROFILE {
+            key.to_string()
+        } else {
+            format!("{}{PROFILE_SEPARATOR}{key}", self.0)
+        }
+    }
+}
+
+/// Loads the value the current profile saved under the provided key, if there is one
+pub fn load_for_profile<T: DeserializeOwned>(profile: &CurrentProfile, key: &str) -> Option<T> {
+    load(&profile.get_profile_key(key))
+}
+
+/// Saves the provided value under the provided key for the current profile
+pub fn save_for_profile<T: Serialize>(profile: &CurrentProfile, key: &str, value: &T) {
+    save(&profile.get_profile_key(key), value)
+}
+
+/// Adds the provided value to the end of the log with the provided key for the current profile
+pub fn append_to_log_for_profile<T: Serialize>(profile: &CurrentProfile, key: &str, value: &T) {
+    append_to_log(&profile.get_profile_key(key), value)
+}
+
+/// Offers the log with the provided key for the current profile as a file to download, since browsers can't write files directly
+#[cfg(target_arch = "wasm32")]
+pub fn download_log_for_profile(profile: &CurrentProfile, key: &str) {
+    download_log(&profile.get_profile_key(key))
+}
+
+/// Loads the value saved under the provided key, if there is one
+pub fn load<T: DeserializeOwned>(key: &str) -> Option<T> {
+    let serialized = read(key)?;
+    match serde_json::from_str(&serialized) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            warn!("unable to parse saved data for {key}: {e}");
+            None
+        }
+    }
+}
+
+/// Saves the provided value under the provided key
+pub fn save<T: Serialize>(key: &str, value: &T) {
+    match serde_json::to_string(value) {
+        Ok(serialized) => write(key, &serialized),
+        Err(e) => warn!("unable to serialize data for {key}: {e}"),
+    }
+}
+
+/// Adds the provided value to the end of the log with the provided key, as a line of JSON.
+/// Logs are never read back by the game; they're only kept for the player to look at.
+pub fn append_to_log<T: Serialize>(key: &str, value: &T) {
+    match serde_json::to_string(value) {
+        Ok(serialized) => append_line(key, &serialized),
+        Err(e) => warn!("unable to serialize log entry for {key}: {e}"),
+    }
+}
+
+/// Gets the current time, in seconds since the Unix epoch
+#[cfg(not(target_arch = "wasm32"))]
+pub fn get_timestamp() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|since_epoch| since_epoch.as_secs())
+        .unwrap_or(0)
+}
+
+/// Gets the current time, in seconds since the Unix epoch
+#[cfg(target_arch = "wasm32")]
+pub fn get_timestamp() -> u64 {
+    // the system clock isn't available in the browser, so ask javascript instead
+    (js_sys::Date::now() / 1000.0) as u64
+}
+
+/// Gets the path of the directory all data is saved in
+#[cfg(not(target_arch = "wasm32"))]
+fn get_save_directory() -> Option<std::path::PathBuf> {
+    dirs::data_dir().map(|dir| dir.join(SAVE_DIRECTORY_NAME))
+}
+
+/// Gets the path of the file data with the provided key is saved in
+#[cfg(not(target_arch = "wasm32"))]
+fn get_save_path(key: &str) -> Option<std::path::PathBuf> {
+    get_save_directory().map(|dir| dir.join(format!("{key}.json")))
+}
+
+/// Gets the path of the file the log with the provided key is saved in
+#[cfg(not(target_arch = "wasm32"))]
+fn get_log_path(key: &str) -> Option<std::path::PathBuf> {
+    get_save_directory().map(|dir| dir.join(format!("{key}.{LOG_EXTENSION}")))
+}
+
+/// Makes sure the directory the provided file goes in exists, returning whether it does
+#[cfg(not(target_arch = "wasm32"))]
+fn create_parent_directory(path: &std::path::Path) -> bool {
+    if let Some(parent) = path.parent() {
+        if let Err(e) = std::fs::create_dir_all(parent) {
+            warn!("unable to create save directory {parent:?}: {e}");
+            return false;
+        }
+    }
+
+    true
+}
+
+/// Deletes all the data saved for the profile with the provided name
+#[cfg(not(target_arch = "wasm32"))]
+pub fn delete_profile(profile_name: &str) {
+    let Some(path) = get_save_directory().map(|dir| dir.join(profile_name)) else {
+        warn!("unable to determine where data for profile {profile_name} is saved");
+        return;
+    };
+
+    if path.exists() {
+        if let Err(e) = std::fs::remove_dir_all(&path) {
+            warn!("unable to delete saved data in {path:?}: {e}");
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read(key: &str) -> Option<String> {
+    std::fs::read_to_string(get_save_path(key)?).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write(key: &str, serialized: &str) {
+    let Some(path) = get_save_path(key) else {
+        warn!("unable to determine where to save data for {key}");
+        return;
+    };
+
+    if !create_parent_directory(&path) {
+        return;
+    }
+
+    if let Err(e) = std::fs::write(&path, serialized) {
+        warn!("unable to save data to {path:?}: {e}");
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn append_line(key: &str, line: &str) {
+    use std::io::Write;
+
+    let Some(path) = get_log_path(key) else {
+        warn!("unable to determine where to save log {key}");
+        return;
+    };
+
+    if !create_parent_directory(&path) {
+        return;
+    }
+
+    let result = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| writeln!(file, "{line}"));
+    if let Err(e) = result {
+        warn!("unable to add to log {path:?}: {e}");
+    }
+}
+
+/// Gets the browser's local storage
+#[cfg(target_arch = "wasm32")]
+fn get_local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+/// Deletes all the data saved for the profile with the provided name
+#[cfg(target_arch = "wasm32")]
+pub fn delete_profile(profile_name: &str) {
+    let Some(storage) = get_local_storage() else {
+        warn!("unable to access local storage to delete data for profile {profile_name}");
+        return;
+    };
+
+    let prefix = format!("{profile_name}{PROFILE_SEPARATOR}");
+    let num_keys = storage.length().unwrap_or(0);
+    let profile_keys = (0..num_keys)
+        .filter_map(|i| storage.key(i).ok().flatten())
+        .filter(|key| key.starts_with(&prefix))
+        .collect::<Vec<String>>();
+    for key in profile_keys {
+        if storage.remove_item(&key).is_err() {
+            warn!("unable to delete {key} from local storage");
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read(key: &str) -> Option<String> {
+    get_local_storage()?.get_item(key).ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write(key: &str, serialized: &str) {
+    let Some(storage) = get_local_storage() else {
+        warn!("unable to access local storage to save data for {key}");
+        return;
+    };
+
+    if storage.set_item(key, serialized).is_err() {
+        warn!("unable to save data for {key} to local storage");
+    }
+}
+
+/// Gets the key the log with the provided key is stored under in local storage, so it can't collide with saved data
+#[cfg(target_arch = "wasm32")]
+fn get_log_storage_key(key: &str) -> String {
+    format!("{key}.{LOG_EXTENSION}")
+}
+
+#[cfg(target_arch = "wasm32")]
+fn append_line(key: &str, line: &str) {
+    let Some(storage) = get_local_storage() else {
+        warn!("unable to access local storage to add to log {key}");
+        return;
+    };
+
+    let log_key = get_log_storage_key(key);
+    let mut log = storage
+        .get_item(&log_key)
+        .ok()
+        .flatten()
+        .unwrap_or_default();
+    log.push_str(line);
+    log.push('\n');
+    if storage.set_item(&log_key, &log).is_err() {
+        warn!("unable to save log {key} to local storage");
+    }
+}
+
+/// Offers the log with the provided key as a file to download
+#[cfg(target_arch = "wasm32")]
+fn download_log(key: &str) {
+    let log = get_local_storage()
+        .and_then(|storage| storage.get_item(&get_log_storage_key(key)).ok().flatten())
+        .unwrap_or_default();
+    // profile keys contain the separator, which isn't allowed in file names
+    let file_name = get_log_storage_key(&key.replace(PROFILE_SEPARATOR, "-"));
+    if let Err(e) = offer_download(&log, &file_name) {
+        warn!("unable to download log {key}: {e:?}");
+    }
+}
+
+/// Has the browser download a file with the provided contents, by building it as a blob and clicking a link to it
+#[cfg(target_arch = "wasm32")]
+fn offer_download(contents: &str, file_name: &str) -> Result<(), wasm_bindgen::JsValue> {
+    use wasm_bindgen::{JsCast, JsValue};
+
+    let parts = js_sys::Array::of1(&JsValue::from_str(contents));
+    let options = web_sys::BlobPropertyBag::new();
+    options.set_type("application/jsonl");
+    let blob = web_sys::Blob::new_with_str_sequence_and_options(&parts, &options)?;
+    let url = web_sys::Url::create_object_url_with_blob(&blob)?;
+
+    let document = web_sys::window()
+        .and_then(|window| window.document())
+        .ok_or_else(|| JsValue::from_str("no document"))?;
+    let link = document
+        .create_element("a")?
+        .dyn_into::<web_sys::HtmlAnchorElement>()?;
+    link.set_href(&url);
+    link.set_download(file_name);
+    link.click();
+
+    web_sys::Url::revoke_object_url(&url)
+}
@@ -0,0 +1,345 @@
+use bevy::window::ReceivedCharacter;
+use serde::{Deserialize, Serialize};
+
+use crate::persistence::DEFAULT_PROFILE;
+use crate::*;
+
+const PROFILES_KEY: &str = "profiles";
+const MAX_PROFILE_NAME_LENGTH: usize = 16;
+
+pub struct ProfilesPlugin;
+
+impl Plugin for ProfilesPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(
+            persistence::load::<Profiles>(PROFILES_KEY)
+                .unwrap_or_else(|| Profiles(vec![DEFAULT_PROFILE.to_string()])),
+        )
+        .insert_resource(CurrentProfile(DEFAULT_PROFILE.to_string()))
+        .insert_resource(NewProfileName(String::new()))
+        .add_systems(OnEnter(GameState::Profiles), profiles_setup)
+        .add_systems(
+            OnExit(GameState::Profiles),
+            despawn_components_system::<ProfilesComponent>,
+        )
+        .add_systems(
+            Update,
+            (
+                rebuild_profile_list.run_if(resource_changed::<Profiles>()),
+                select_profile_button_system,
+                delete_profile_button_system,
+                profile_name_input_system,
+                create_profile_button_system,
+            )
+                .run_if(in_state(GameState::Profiles)),
+        );
+    }
+}
+
+/// The names of all the profiles that have been created
+#[derive(Resource, Serialize, Deserialize)]
+struct Profiles(Vec<String>);
+
+/// The name being typed in for a new profile
+#[derive(Resource)]
+struct NewProfileName(String);
+
+#[derive(Component)]
+struct ProfilesComponent;
+
+#[derive(Component)]
+struct ProfileList;
+
+#[derive(Component)]
+struct SelectProfileButton(String);
+
+#[derive(Component)]
+struct DeleteProfileButton(String);
+
+#[derive(Component)]
+struct NewProfileNameText;
+
+#[derive(Component)]
+struct CreateProfileButton;
+
+/// Gets the user-facing label for the name of the profile being created
+fn get_new_profile_name_label(new_profile_name: &NewProfileName) -> String {
+    format!("New profile: {}_", new_profile_name.0)
+}
+
+fn profiles_setup(
+    mut commands: Commands,
+    profiles: Res<Profiles>,
+    new_profile_name: Res<NewProfileName>,
+    theme: Res<Theme>,
+    asset_server: Res<AssetServer>,
+) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .insert(ProfilesComponent)
+        .with_children(|parent| {
+            // title
+            parent.spawn(
+                TextBundle::from_section(
+                    "Profiles",
+                    TextStyle {
+                        font: asset_server.load(TITLE_FONT),
+                        font_size: 60.0,
+                        color: Color::WHITE,
+                    },
+                )
+                .with_text_alignment(TextAlignment::Center)
+                .with_style(Style {
+                    margin: UiRect::bottom(Val::Px(15.0)),
+                    ..default()
+                }),
+            );
+
+            // existing profiles
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .insert(ProfileList)
+                .with_children(|parent| {
+                    spawn_profile_rows(&profiles, parent, &theme, &asset_server)
+                });
+
+            // new profile
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        align_items: AlignItems::Center,
+                        margin: UiRect::top(Val::Px(15.0)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn(TextBundle::from_section(
+                            get_new_profile_name_label(&new_profile_name),
+                            TextStyle {
+                                font: asset_server.load(MAIN_FONT),
+                                font_size: 30.0,
+                                color: Color::WHITE,
+                            },
+                        ))
+                        .insert(NewProfileNameText);
+                    spawn_profiles_screen_button(
+                        "Create",
+                        CreateProfileButton,
+                        parent,
+                        &theme,
+                        &asset_server,
+                    );
+                });
+        });
+}
+
+/// Spawns a row for each profile, with buttons to select and delete it
+fn spawn_profile_rows(
+    profiles: &Profiles,
+    parent: &mut ChildBuilder,
+    theme: &Theme,
+    asset_server: &Res<AssetServer>,
+) {
+    for profile_name in profiles.0.iter() {
+        parent
+            .spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
+                    margin: UiRect::bottom(Val::Px(10.0)),
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|parent| {
+                spawn_profiles_screen_button(
+                    profile_name,
+                    SelectProfileButton(profile_name.clone()),
+                    parent,
+                    theme,
+                    asset_server,
+                );
+
+                // the default profile always exists, so there's somewhere to fall back to
+                if profile_name != DEFAULT_PROFILE {
+                    spawn_profiles_screen_button(
+                        "Delete",
+                        DeleteProfileButton(profile_name.clone()),
+                        parent,
+                        theme,
+                        asset_server,
+                    );
+                }
+            });
+    }
+}
+
+/// Spawns a button on the profiles screen
+fn spawn_profiles_screen_button(
+    text: &str,
+    marker: impl Component,
+    parent: &mut ChildBuilder,
+    theme: &Theme,
+    asset_server: &Res<AssetServer>,
+) {
+    parent
+        .spawn(ButtonBundle {
+            style: Style {
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                padding: UiRect::all(Val::Px(10.0)),
+                margin: UiRect::horizontal(Val::Px(10.0)),
+                ..default()
+            },
+            background_color: theme.normal_button.into(),
+            ..default()
+        })
+        .insert(marker)
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                text,
+                TextStyle {
+                    font: asset_server.load(MAIN_FONT),
+                    font_size: 30.0,
+                    color: theme.button_text,
+                },
+            ));
+        });
+}
+
+/// Keeps the list of profiles up to date when profiles are created or deleted
+fn rebuild_profile_list(
+    mut commands: Commands,
+    profiles: Res<Profiles>,
+    profile_list_query: Query<Entity, With<ProfileList>>,
+    theme: Res<Theme>,
+    asset_server: Res<AssetServer>,
+) {
+    for entity in profile_list_query.iter() {
+        commands
+            .entity(entity)
+            .despawn_descendants()
+            .with_children(|parent| spawn_profile_rows(&profiles, parent, &theme, &asset_server));
+    }
+}
+
+/// Handles interactions with the buttons for selecting a profile.
+fn select_profile_button_system(
+    mut commands: Commands,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut current_profile: ResMut<CurrentProfile>,
+    interaction_query: Query<(&Interaction, &SelectProfileButton), Changed<Interaction>>,
+) {
+    for (interaction, button) in interaction_query.iter() {
+        if *interaction == Interaction::Pressed {
+            current_profile.0 = button.0.clone();
+            load_profile_settings(&current_profile, &mut commands);
+            load_profile_key_bindings(&current_profile, &mut commands);
+            load_profile_cosmetics(&current_profile, &mut commands);
+            next_state.set(GameState::Menu);
+        }
+    }
+}
+
+/// Handles interactions with the buttons for deleting a profile.
+fn delete_profile_button_system(
+    mut commands: Commands,
+    mut profiles: ResMut<Profiles>,
+    mut current_profile: ResMut<CurrentProfile>,
+    interaction_query: Query<(&Interaction, &DeleteProfileButton), Changed<Interaction>>,
+) {
+    for (interaction, button) in interaction_query.iter() {
+        if *interaction == Interaction::Pressed {
+            profiles.0.retain(|profile_name| *profile_name != button.0);
+            persistence::save(PROFILES_KEY, &*profiles);
+            persistence::delete_profile(&button.0);
+
+            if current_profile.0 == button.0 {
+                current_profile.0 = DEFAULT_PROFILE.to_string();
+                load_profile_settings(&current_profile, &mut commands);
+                load_profile_key_bindings(&current_profile, &mut commands);
+                load_profile_cosmetics(&current_profile, &mut commands);
+            }
+        }
+    }
+}
+
+/// Handles typing in the name for a new profile
+fn profile_name_input_system(
+    mut received_characters: EventReader<ReceivedCharacter>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut new_profile_name: ResMut<NewProfileName>,
+    mut new_profile_name_text_query: Query<&mut Text, With<NewProfileNameText>>,
+) {
+    let mut changed = false;
+    for event in received_characters.read() {
+        // profile names end up in file paths, so stick to characters that are safe there
+        let allowed = event.char.is_ascii_alphanumeric() || event.char == ' ';
+        if allowed && new_profile_name.0.len() < MAX_PROFILE_NAME_LENGTH {
+            new_profile_name.0.push(event.char);
+            changed = true;
+        }
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Back) {
+        new_profile_name.0.pop();
+        changed = true;
+    }
+
+    if changed {
+        for mut text in new_profile_name_text_query.iter_mut() {
+            text.sections[0].value = get_new_profile_name_label(&new_profile_name);
+        }
+    }
+}
+
+type InteractedCreateProfileButtonTuple = (Changed<Interaction>, With<CreateProfileButton>);
+
+/// Handles interactions with the button for creating a new profile.
+fn create_profile_button_system(
+    mut profiles: ResMut<Profiles>,
+    mut new_profile_name: ResMut<NewProfileName>,
+    interaction_query: Query<&Interaction, InteractedCreateProfileButtonTuple>,
+    mut new_profile_name_text_query: Query<&mut Text, With<NewProfileNameText>>,
+) {
+    for interaction in interaction_query.iter() {
+        if *interaction == Interaction::Pressed {
+            let name = new_profile_name.0.trim().to_string();
+            // profile names become directory names, which aren't case sensitive on every platform
+            let name_taken = profiles
+                .0
+                .iter()
+                .any(|existing_name| existing_name.to_lowercase() == name.to_lowercase());
+            if name.is_empty() || name_taken {
+                continue;
+            }
+
+            profiles.0.push(name);
+            persistence::save(PROFILES_KEY, &*profiles);
+
+            new_profile_name.0.clear();
+            for mut text in new_profile_name_text_query.iter_mut() {
+                text.sections[0].value = get_new_profile_name_label(&new_profile_name);
+            }
+        }
+    }
+}
@@ -0,0 +1,1052 @@
+use serde::{Deserialize, Serialize};
+use strum::{EnumIter, IntoEnumIterator};
+
+use crate::*;
+
+const SETTINGS_KEY: &str = "settings";
+const VOLUME_SETTINGS_KEY: &str = "volume";
+const VOLUME_STEP: f32 = 0.1;
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        let settings = persistence::load::<Settings>(SETTINGS_KEY).unwrap_or_default();
+        app.insert_resource(Theme::from_settings(&settings))
+            .insert_resource(settings)
+            .insert_resource(
+                persistence::load::<VolumeSettings>(VOLUME_SETTINGS_KEY).unwrap_or_default(),
+            )
+            .insert_resource(SelectedSettingsPage(SettingsPage::Gameplay))
+            .add_systems(OnEnter(GameState::Settings), settings_setup)
+            .add_systems(
+                OnExit(GameState::Settings),
+                despawn_components_system::<SettingsComponent>,
+            )
+            .add_systems(
+                Update,
+                (
+                    settings_page_button_system,
+                    show_selected_settings_page
+                        .after(settings_page_button_system)
+                        .run_if(
+                            resource_changed::<SelectedSettingsPage>()
+                                .or_else(resource_changed::<Theme>()),
+                        ),
+                    setting_button_system,
+                    volume_button_system,
+                    key_bindings_button_system,
+                    settings_back_button_system,
+                )
+                    .run_if(in_state(GameState::Settings)),
+            );
+    }
+}
+
+/// Replaces the current settings with the ones saved for the provided profile
+pub fn load_profile_settings(profile: &CurrentProfile, commands: &mut Commands) {
+    commands.insert_resource(
+        persistence::load_for_profile::<Settings>(profile, SETTINGS_KEY).unwrap_or_default(),
+    );
+    commands.insert_resource(
+        persistence::load_for_profile::<VolumeSettings>(profile, VOLUME_SETTINGS_KEY)
+            .unwrap_or_default(),
+    );
+}
+
+/// Options the player can change from the settings screen
+#[derive(Resource, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Whether enemies are drawn with a ring indicating how dangerous they are
+    pub show_threat_rings: bool,
+    /// Whether effects that shake, flash, or pulse should be replaced with steadier alternatives.
+    /// Anything that moves the screen around or flashes should check this.
+    pub reduce_motion: bool,
+    /// Whether a perk should be chosen automatically if the player is idle on the perk chooser for a long time
+    pub auto_choose_perk: bool,
+    /// Whether the player turns towards the cursor gradually rather than instantly
+    pub smooth_aim: bool,
+    /// Whether enemies should spawn faster or slower depending on how well the player is doing
+    pub dynamic_difficulty: bool,
+    /// Whether the camera should zoom in and out to keep enemies in view, instead of zooming out each level
+    pub auto_zoom: bool,
+    /// How the player controls their movement
+    pub movement_scheme: MovementScheme,
+    /// Whether the game should always be shown at its base aspect ratio, with black bars filling the rest of the window.
+    /// This keeps the amount of the arena that's visible the same no matter what shape the window is.
+    pub lock_aspect_ratio: bool,
+    /// Whether buttons should be drawn with more contrasting colors, to make them easier to read
+    pub high_contrast: bool,
+    /// Whether a summary of each finished run should be added to a log kept on this device, for players who want to analyze their play
+    pub log_runs: bool,
+    /// How strongly aiming with a gamepad should be pulled towards nearby enemies. Doesn't affect aiming with the mouse.
+    pub aim_assist: AimAssist,
+    /// Whether getting hit should only set the player's attack combo back partway, rather than dropping it entirely
+    pub lenient_combos: bool,
+    /// Which side of the screen the sidebar with the player's level, XP, and other stats is on
+    pub sidebar_side: SidebarSide,
+    /// How the perk chooser is laid out when the player levels up
+    pub perk_chooser_layout: PerkChooserLayout,
+    /// How hard the player gets shoved when an enemy hits them. Doesn't affect how much damage they take.
+    pub knockback: Knockback,
+    /// Where the clock showing how long the current run has lasted is shown, if anywhere
+    pub run_clock: RunClockPosition,
+    /// How many faded copies of the sword trail behind it when it's swung
+    pub sword_shadows: SwordShadows,
+    /// Whether holding the secondary action button slows time down to aim it, with the action happening once the button is let go
+    pub hold_to_aim: bool,
+    /// Whether enemies should spawn a bit slower while the game is running slowly, so the frame rate can recover
+    pub spawn_budget: bool,
+    /// How big enemies are, to make small ones easier to see. Their hitboxes grow along with them.
+    pub enemy_size: EnemySize,
+}
+
+/// A way of controlling the player's movement
+#[derive(PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum MovementScheme {
+    /// Move with the WASD keys
+    Keyboard,
+    /// Continuously move towards the cursor
+    MouseFollow,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            show_threat_rings: true,
+            reduce_motion: false,
+            auto_choose_perk: false,
+            smooth_aim: false,
+            dynamic_difficulty: false,
+            auto_zoom: false,
+            movement_scheme: MovementScheme::Keyboard,
+            lock_aspect_ratio: false,
+            high_contrast: false,
+            log_runs: false,
+            aim_assist: AimAssist::Medium,
+            lenient_combos: false,
+            sidebar_side: SidebarSide::Right,
+            perk_chooser_layout: PerkChooserLayout::Fullscreen,
+            knockback: Knockback::Normal,
+            run_clock: RunClockPosition::Top,
+            sword_shadows: SwordShadows::Full,
+            hold_to_aim: false,
+            spawn_budget: true,
+            enemy_size: EnemySize::Normal,
+        }
+    }
+}
+
+/// A way of laying out the perk chooser
+#[derive(PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum PerkChooserLayout {
+    /// A large panel covering most of the screen
+    Fullscreen,
+    /// A bar along the bottom of the screen that leaves the arena visible
+    Compact,
+}
+
+/// A side of the screen
+#[derive(PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum SidebarSide {
+    Left,
+    Right,
+}
+
+/// Where the run clock is shown. It always goes on the opposite side of the screen from the sidebar.
+#[derive(PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum RunClockPosition {
+    Hidden,
+    Top,
+    Bottom,
+}
+
+/// How many shadows trail behind the sword when it's swung
+#[derive(PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum SwordShadows {
+    Off,
+    Few,
+    Full,
+}
+
+impl SwordShadows {
+    /// Gets how many of the possible sword shadows to skip between each one that's kept, or `None` if none should be kept
+    pub fn get_step(&self) -> Option<usize> {
+        match self {
+            SwordShadows::Off => None,
+            SwordShadows::Few => Some(3),
+            SwordShadows::Full => Some(1),
+        }
+    }
+}
+
+/// How big enemies are drawn, and how big their hitboxes are
+#[derive(PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum EnemySize {
+    Normal,
+    Large,
+    Huge,
+}
+
+impl EnemySize {
+    /// Gets the multiplier to apply to the size of enemies
+    pub fn get_multiplier(&self) -> f32 {
+        match self {
+            EnemySize::Normal => 1.0,
+            EnemySize::Large => 1.25,
+            EnemySize::Huge => 1.5,
+        }
+    }
+}
+
+/// How hard the player gets shoved when they're hit
+#[derive(PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum Knockback {
+    Off,
+    Weak,
+    Normal,
+    Strong,
+}
+
+impl Knockback {
+    /// Gets the multiplier to apply to the force the player gets shoved with
+    pub fn get_multiplier(&self) -> f32 {
+        match self {
+            Knockback::Off => 0.0,
+            Knockback::Weak => 0.5,
+            Knockback::Normal => 1.0,
+            Knockback::Strong => 1.5,
+        }
+    }
+}
+
+/// How much help the player gets aiming with a gamepad
+#[derive(PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum AimAssist {
+    Off,
+    Low,
+    Medium,
+    High,
+}
+
+impl AimAssist {
+    /// Gets how much of the way towards a nearby enemy aim should be pulled, from 0 to 1
+    pub fn get_strength(&self) -> f32 {
+        match self {
+            AimAssist::Off => 0.0,
+            AimAssist::Low => 0.25,
+            AimAssist::Medium => 0.5,
+            AimAssist::High => 0.8,
+        }
+    }
+}
+
+/// How loud different kinds of sounds should be, from 0 to 1
+#[derive(Resource, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VolumeSettings {
+    /// Applies to every sound, on top of its own channel's volume
+    pub master: f32,
+    pub music: f32,
+    pub sfx: f32,
+}
+
+impl Default for VolumeSettings {
+    fn default() -> Self {
+        VolumeSettings {
+            master: 1.0,
+            music: 1.0,
+            sfx: 1.0,
+        }
+    }
+}
+
+impl VolumeSettings {
+    /// Gets how loud music should actually be played, taking the master volume into account
+    pub fn get_music_volume(&self) -> f32 {
+        self.master * self.music
+    }
+
+    /// Gets how loud sound effects should actually be played, taking the master volume into account
+    pub fn get_sfx_volume(&self) -> f32 {
+        self.master * self.sfx
+    }
+}
+
+/// A kind of sound with its own volume
+#[derive(Clone, Copy, EnumIter)]
+enum VolumeChannel {
+    Master,
+    Music,
+    Sfx,
+}
+
+impl VolumeChannel {
+    /// Gets the user-facing text describing the current volume of this channel
+    fn get_label(&self, volume_settings: &VolumeSettings) -> String {
+        let (name, volume) = match self {
+            VolumeChannel::Master => ("Master", volume_settings.master),
+            VolumeChannel::Music => ("Music", volume_settings.music),
+            VolumeChannel::Sfx => ("Sound effects", volume_settings.sfx),
+        };
+        format!("{name}: {:.0}%", volume * 100.0)
+    }
+
+    /// Changes the volume of this channel by the provided amount
+    fn adjust(&self, volume_settings: &mut VolumeSettings, change: f32) {
+        let volume = match self {
+            VolumeChannel::Master => &mut volume_settings.master,
+            VolumeChannel::Music => &mut volume_settings.music,
+            VolumeChannel::Sfx => &mut volume_settings.sfx,
+        };
+        // round to the nearest step so repeated adjustments don't accumulate floating point error
+        let steps = ((*volume + change) / VOLUME_STEP).round();
+        *volume = (steps * VOLUME_STEP).clamp(0.0, 1.0);
+    }
+}
+
+/// A group of entries on the settings screen. Only one is shown at a time, so they all fit on the screen.
+#[derive(Clone, Copy, PartialEq, Eq, EnumIter)]
+enum SettingsPage {
+    Gameplay,
+    Display,
+    AudioAndControls,
+}
+
+impl SettingsPage {
+    /// Gets the user-facing name of this page
+    fn get_name(&self) -> &'static str {
+        match self {
+            SettingsPage::Gameplay => "Gameplay",
+            SettingsPage::Display => "Display",
+            SettingsPage::AudioAndControls => "Audio & controls",
+        }
+    }
+
+    /// Gets how the contents of this page should be displayed when the provided page is selected
+    fn get_display(&self, selected_page: SettingsPage) -> Display {
+        if *self == selected_page {
+            Display::Flex
+        } else {
+            Display::None
+        }
+    }
+}
+
+/// The page currently shown on the settings screen
+#[derive(Resource)]
+struct SelectedSettingsPage(SettingsPage);
+
+/// A single entry on the settings screen
+#[derive(Clone, Copy, EnumIter)]
+enum Setting {
+    ThreatRings,
+    ReduceMotion,
+    AutoChoosePerk,
+    SmoothAim,
+    DynamicDifficulty,
+    AutoZoom,
+    MovementScheme,
+    LockAspectRatio,
+    HighContrast,
+    LogRuns,
+    AimAssist,
+    LenientCombos,
+    SidebarSide,
+    PerkChooserLayout,
+    Knockback,
+    RunClock,
+    SwordShadows,
+    HoldToAim,
+    SpawnBudget,
+    EnemySize,
+}
+
+impl Setting {
+    /// Gets the page of the settings screen this setting is shown on
+    fn get_page(&self) -> SettingsPage {
+        match self {
+            Setting::AutoChoosePerk
+            | Setting::SmoothAim
+            | Setting::DynamicDifficulty
+            | Setting::MovementScheme
+            | Setting::LogRuns
+            | Setting::AimAssist
+            | Setting::LenientCombos
+            | Setting::Knockback
+            | Setting::HoldToAim
+            | Setting::SpawnBudget
+            | Setting::EnemySize => SettingsPage::Gameplay,
+            Setting::ThreatRings
+            | Setting::ReduceMotion
+            | Setting::AutoZoom
+            | Setting::LockAspectRatio
+            | Setting::HighContrast
+            | Setting::SidebarSide
+            | Setting::PerkChooserLayout
+            | Setting::RunClock
+            | Setting::SwordShadows => SettingsPage::Display,
+        }
+    }
+
+    /// Gets the user-facing text describing the current value of this setting
+    fn get_label(&self, settings: &Settings) -> String {
+        match self {
+            Setting::ThreatRings => {
+                format!("Threat rings: {}", on_off(settings.show_threat_rings))
+            }
+            Setting::ReduceMotion => {
+                format!("Reduce motion: {}", on_off(settings.reduce_motion))
+            }
+            Setting::AutoChoosePerk => {
+                format!(
+                    "Auto-choose perk when idle: {}",
+                    on_off(settings.auto_choose_perk)
+                )
+            }
+            Setting::SmoothAim => format!("Smooth aim: {}", on_off(settings.smooth_aim)),
+            Setting::DynamicDifficulty => format!(
+                "Dynamic difficulty: {}",
+                on_off(settings.dynamic_difficulty)
+            ),
+            Setting::AutoZoom => format!("Auto zoom: {}", on_off(settings.auto_zoom)),
+            Setting::MovementScheme => {
+                let scheme = match settings.movement_scheme {
+                    MovementScheme::Keyboard => "Keyboard",
+                    MovementScheme::MouseFollow => "Follow mouse",
+                };
+                format!("Movement: {scheme}")
+            }
+            Setting::LockAspectRatio => {
+                format!("Lock aspect ratio: {}", on_off(settings.lock_aspect_ratio))
+            }
+            Setting::HighContrast => {
+                format!("High contrast: {}", on_off(settings.high_contrast))
+            }
+            Setting::LogRuns => format!("Log runs: {}", on_off(settings.log_runs)),
+            Setting::AimAssist => {
+                let strength = match settings.aim_assist {
+                    AimAssist::Off => "Off",
+                    AimAssist::Low => "Low",
+                    AimAssist::Medium => "Medium",
+                    AimAssist::High => "High",
+                };
+                format!("Gamepad aim assist: {strength}")
+            }
+            Setting::LenientCombos => {
+                format!("Lenient combos: {}", on_off(settings.lenient_combos))
+            }
+            Setting::SidebarSide => {
+                let side = match settings.sidebar_side {
+                    SidebarSide::Left => "Left",
+                    SidebarSide::Right => "Right",
+                };
+                format!("Sidebar: {side}")
+            }
+            Setting::PerkChooserLayout => {
+                let layout = match settings.perk_chooser_layout {
+                    PerkChooserLayout::Fullscreen => "Fullscreen",
+                    PerkChooserLayout::Compact => "Compact",
+                };
+                format!("Perk chooser: {layout}")
+            }
+            Setting::Knockback => {
+                let strength = match settings.knockback {
+                    Knockback::Off => "Off",
+                    Knockback::Weak => "Weak",
+                    Knockback::Normal => "Normal",
+                    Knockback::Strong => "Strong",
+                };
+                format!("Knockback: {strength}")
+            }
+            Setting::RunClock => {
+                let position = match settings.run_clock {
+                    RunClockPosition::Hidden => "Off",
+                    RunClockPosition::Top => "Top",
+                    RunClockPosition::Bottom => "Bottom",
+                };
+                format!("Run clock: {position}")
+            }
+            Setting::SwordShadows => {
+                let amount = match settings.sword_shadows {
+                    SwordShadows::Off => "Off",
+                    SwordShadows::Few => "Few",
+                    SwordShadows::Full => "Full",
+                };
+                format!("Sword shadows: {amount}")
+            }
+            Setting::HoldToAim => {
+                format!(
+                    "Hold to aim secondary action: {}",
+                    on_off(settings.hold_to_aim)
+                )
+            }
+            Setting::SpawnBudget => {
+                format!(
+                    "Reduce spawns when lagging: {}",
+                    on_off(settings.spawn_budget)
+                )
+            }
+            Setting::EnemySize => {
+                let size = match settings.enemy_size {
+                    EnemySize::Normal => "Normal",
+                    EnemySize::Large => "Large",
+                    EnemySize::Huge => "Huge",
+                };
+                format!("Enemy size: {size}")
+            }
+        }
+    }
+
+    /// Changes this setting to its next value
+    fn cycle(&self, settings: &mut Settings) {
+        match self {
+            Setting::ThreatRings => settings.show_threat_rings = !settings.show_threat_rings,
+            Setting::ReduceMotion => settings.reduce_motion = !settings.reduce_motion,
+            Setting::AutoChoosePerk => settings.auto_choose_perk = !settings.auto_choose_perk,
+            Setting::SmoothAim => settings.smooth_aim = !settings.smooth_aim,
+            Setting::DynamicDifficulty => {
+                settings.dynamic_difficulty = !settings.dynamic_difficulty
+            }
+            Setting::AutoZoom => settings.auto_zoom = !settings.auto_zoom,
+            Setting::MovementScheme => {
+                settings.movement_scheme = match settings.movement_scheme {
+                    MovementScheme::Keyboard => MovementScheme::MouseFollow,
+                    MovementScheme::MouseFollow => MovementScheme::Keyboard,
+                }
+            }
+            Setting::LockAspectRatio => settings.lock_aspect_ratio = !settings.lock_aspect_ratio,
+            Setting::HighContrast => settings.high_contrast = !settings.high_contrast,
+            Setting::LogRuns => settings.log_runs = !settings.log_runs,
+            Setting::AimAssist => {
+                settings.aim_assist = match settings.aim_assist {
+                    AimAssist::Off => AimAssist::Low,
+                    AimAssist::Low => AimAssist::Medium,
+                    AimAssist::Medium => AimAssist::High,
+                    AimAssist::High => AimAssist::Off,
+                }
+            }
+            Setting::LenientCombos => settings.lenient_combos = !settings.lenient_combos,
+            Setting::SidebarSide => {
+                settings.sidebar_side = match settings.sidebar_side {
+                    SidebarSide::Left => SidebarSide::Right,
+                    SidebarSide::Right => SidebarSide::Left,
+                }
+            }
+            Setting::PerkChooserLayout => {
+                settings.perk_chooser_layout = match settings.perk_chooser_layout {
+                    PerkChooserLayout::Fullscreen => PerkChooserLayout::Compact,
+                    PerkChooserLayout::Compact => PerkChooserLayout::Fullscreen,
+                }
+            }
+            Setting::Knockback => {
+                settings.knockback = match settings.knockback {
+                    Knockback::Off => Knockback::Weak,
+                    Knockback::Weak => Knockback::Normal,
+                    Knockback::Normal => Knockback::Strong,
+                    Knockback::Strong => Knockback::Off,
+                }
+            }
+            Setting::RunClock => {
+                settings.run_clock = match settings.run_clock {
+                    RunClockPosition::Hidden => RunClockPosition::Top,
+                    RunClockPosition::Top => RunClockPosition::Bottom,
+                    RunClockPosition::Bottom => RunClockPosition::Hidden,
+                }
+            }
+            Setting::SwordShadows => {
+                settings.sword_shadows = match settings.sword_shadows {
+                    SwordShadows::Off => SwordShadows::Few,
+                    SwordShadows::Few => SwordShadows::Full,
+                    SwordShadows::Full => SwordShadows::Off,
+                }
+            }
+            Setting::HoldToAim => settings.hold_to_aim = !settings.hold_to_aim,
+            Setting::SpawnBudget => settings.spawn_budget = !settings.spawn_budget,
+            Setting::EnemySize => {
+                settings.enemy_size = match settings.enemy_size {
+                    EnemySize::Normal => EnemySize::Large,
+                    EnemySize::Large => EnemySize::Huge,
+                    EnemySize::Huge => EnemySize::Normal,
+                }
+            }
+        }
+    }
+}
+
+/// Gets the user-facing name of a boolean setting value
+fn on_off(value: bool) -> &'static str {
+    if value {
+        "On"
+    } else {
+        "Off"
+    }
+}
+
+#[derive(Component)]
+struct SettingsComponent;
+
+#[derive(Component)]
+struct SettingsPageButton(SettingsPage);
+
+/// Holds everything shown on one page of the settings screen
+#[derive(Component)]
+struct SettingsPageContent(SettingsPage);
+
+#[derive(Component)]
+struct SettingButton(Setting);
+
+#[derive(Component)]
+struct SettingText(Setting);
+
+#[derive(Component)]
+struct VolumeButton {
+    channel: VolumeChannel,
+    change: f32,
+}
+
+#[derive(Component)]
+struct VolumeText(VolumeChannel);
+
+#[derive(Component)]
+struct SettingsBackButton;
+
+#[derive(Component)]
+struct KeyBindingsButton;
+
+fn settings_setup(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    volume_settings: Res<VolumeSettings>,
+    selected_page: Res<SelectedSettingsPage>,
+    theme: Res<Theme>,
+    asset_server: Res<AssetServer>,
+) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .insert(SettingsComponent)
+        .with_children(|parent| {
+            // title
+            parent.spawn(
+                TextBundle::from_section(
+                    "Options",
+                    TextStyle {
+                        font: asset_server.load(TITLE_FONT),
+                        font_size: 60.0,
+                        color: Color::WHITE,
+                    },
+                )
+                .with_text_alignment(TextAlignment::Center)
+                .with_style(Style {
+                    margin: UiRect::bottom(Val::Px(15.0)),
+                    ..default()
+                }),
+            );
+
+            // page buttons
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        margin: UiRect::bottom(Val::Px(10.0)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for page in SettingsPage::iter() {
+                        let mut button = parent.spawn(ButtonBundle {
+                            style: Style {
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                padding: UiRect::all(Val::Px(6.0)),
+                                margin: UiRect::horizontal(Val::Px(4.0)),
+                                ..default()
+                            },
+                            background_color: get_settings_page_button_color(
+                                page,
+                                selected_page.0,
+                                &theme,
+                            )
+                            .into(),
+                            ..default()
+                        });
+                        button
+                            .insert(SettingsPageButton(page))
+                            .with_children(|parent| {
+                                parent.spawn(TextBundle::from_section(
+                                    page.get_name(),
+                                    TextStyle {
+                                        font: asset_server.load(MAIN_FONT),
+                                        font_size: 30.0,
+                                        color: theme.button_text,
+                                    },
+                                ));
+                            });
+                        // the selected page's button can't be pressed again, so it stays highlighted
+                        if page == selected_page.0 {
+                            button.insert(DisabledButton);
+                        }
+                    }
+                });
+
+            // setting buttons
+            for page in [SettingsPage::Gameplay, SettingsPage::Display] {
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            width: Val::Percent(90.0),
+                            flex_direction: FlexDirection::Row,
+                            flex_wrap: FlexWrap::Wrap,
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            display: page.get_display(selected_page.0),
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .insert(SettingsPageContent(page))
+                    .with_children(|parent| {
+                        for setting in Setting::iter().filter(|setting| setting.get_page() == page)
+                        {
+                            parent
+                                .spawn(ButtonBundle {
+                                    style: Style {
+                                        width: Val::Percent(45.0),
+                                        justify_content: JustifyContent::Center,
+                                        align_items: AlignItems::Center,
+                                        padding: UiRect::all(Val::Px(8.0)),
+                                        margin: UiRect::all(Val::Px(4.0)),
+                                        ..default()
+                                    },
+                                    background_color: theme.normal_button.into(),
+                                    ..default()
+                                })
+                                .insert(SettingButton(setting))
+                                .with_children(|parent| {
+                                    parent
+                                        .spawn(TextBundle::from_section(
+                                            setting.get_label(&settings),
+                                            TextStyle {
+                                                font: asset_server.load(MAIN_FONT),
+                                                font_size: 25.0,
+                                                color: theme.button_text,
+                                            },
+                                        ))
+                                        .insert(SettingText(setting));
+                                });
+                        }
+                    });
+            }
+
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        display: SettingsPage::AudioAndControls.get_display(selected_page.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .insert(SettingsPageContent(SettingsPage::AudioAndControls))
+                .with_children(|parent| {
+                    // volume controls
+                    for channel in VolumeChannel::iter() {
+                        parent
+                            .spawn(NodeBundle {
+                                style: Style {
+                                    flex_direction: FlexDirection::Row,
+                                    justify_content: JustifyContent::Center,
+                                    align_items: AlignItems::Center,
+                                    margin: UiRect::top(Val::Px(4.0)),
+                                    ..default()
+                                },
+                                ..default()
+                            })
+                            .with_children(|parent| {
+                                spawn_volume_button(
+                                    "-",
+                                    channel,
+                                    -VOLUME_STEP,
+                                    parent,
+                                    &theme,
+                                    &asset_server,
+                                );
+                                parent
+                                    .spawn(
+                                        TextBundle::from_section(
+                                            channel.get_label(&volume_settings),
+                                            TextStyle {
+                                                font: asset_server.load(MAIN_FONT),
+                                                font_size: 25.0,
+                                                color: theme.button_text,
+                                            },
+                                        )
+                                        .with_text_alignment(TextAlignment::Center)
+                                        .with_style(
+                                            Style {
+                                                width: Val::Px(300.0),
+                                                ..default()
+                                            },
+                                        ),
+                                    )
+                                    .insert(VolumeText(channel));
+                                spawn_volume_button(
+                                    "+",
+                                    channel,
+                                    VOLUME_STEP,
+                                    parent,
+                                    &theme,
+                                    &asset_server,
+                                );
+                            });
+                    }
+
+                    // key bindings button
+                    parent
+                        .spawn(ButtonBundle {
+                            style: Style {
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                padding: UiRect::all(Val::Px(6.0)),
+                                margin: UiRect::top(Val::Px(10.0)),
+                                ..default()
+                            },
+                            background_color: theme.normal_button.into(),
+                            ..default()
+                        })
+                        .insert(KeyBindingsButton)
+                        .with_children(|parent| {
+                            parent.spawn(TextBundle::from_section(
+                                "Key bindings",
+                                TextStyle {
+                                    font: asset_server.load(MAIN_FONT),
+                                    font_size: 25.0,
+                                    color: theme.button_text,
+                                },
+                            ));
+                        });
+                });
+
+            // back button
+            parent
+                .spawn(ButtonBundle {
+                    style: Style {
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        padding: UiRect::all(Val::Px(10.0)),
+                        margin: UiRect::top(Val::Px(15.0)),
+                        ..default()
+                    },
+                    background_color: theme.normal_button.into(),
+                    ..default()
+                })
+                .insert(SettingsBackButton)
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "Back",
+                        TextStyle {
+                            font: asset_server.load(MAIN_FONT),
+                            font_size: 40.0,
+                            color: theme.button_text,
+                        },
+                    ));
+                });
+        });
+}
+
+/// Gets the background color of the button for the provided page of the settings screen
+fn get_settings_page_button_color(
+    page: SettingsPage,
+    selected_page: SettingsPage,
+    theme: &Theme,
+) -> Color {
+    if page == selected_page {
+        theme.pressed_button
+    } else {
+        theme.normal_button
+    }
+}
+
+/// Spawns a button that changes the volume of the provided channel
+fn spawn_volume_button(
+    text: &str,
+    channel: VolumeChannel,
+    change: f32,
+    parent: &mut ChildBuilder,
+    theme: &Theme,
+    asset_server: &Res<AssetServer>,
+) {
+    parent
+        .spawn(ButtonBundle {
+            style: Style {
+                width: Val::Px(40.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                padding: UiRect::all(Val::Px(4.0)),
+                margin: UiRect::horizontal(Val::Px(4.0)),
+                ..default()
+            },
+            background_color: theme.normal_button.into(),
+            ..default()
+        })
+        .insert(VolumeButton { channel, change })
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                text,
+                TextStyle {
+                    font: asset_server.load(MAIN_FONT),
+                    font_size: 25.0,
+                    color: theme.button_text,
+                },
+            ));
+        });
+}
+
+type InteractedSettingsPageButtonTuple = (Changed<Interaction>, Without<DisabledButton>);
+
+/// Handles interactions with the buttons for switching between pages of the settings screen.
+fn settings_page_button_system(
+    mut selected_page: ResMut<SelectedSettingsPage>,
+    interaction_query: Query<
+        (&Interaction, &SettingsPageButton),
+        InteractedSettingsPageButtonTuple,
+    >,
+) {
+    for (interaction, button) in interaction_query.iter() {
+        if *interaction == Interaction::Pressed {
+            selected_page.0 = button.0;
+        }
+    }
+}
+
+/// Shows the contents of the selected page of the settings screen, and hides the rest
+fn show_selected_settings_page(
+    mut commands: Commands,
+    selected_page: Res<SelectedSettingsPage>,
+    theme: Res<Theme>,
+    mut page_content_query: Query<(&mut Style, &SettingsPageContent)>,
+    mut page_button_query: Query<(Entity, &mut BackgroundColor, &SettingsPageButton)>,
+) {
+    for (mut style, content) in page_content_query.iter_mut() {
+        style.display = content.0.get_display(selected_page.0);
+    }
+
+    for (entity, mut color, button) in page_button_query.iter_mut() {
+        *color = get_settings_page_button_color(button.0, selected_page.0, &theme).into();
+        if button.0 == selected_page.0 {
+            commands.entity(entity).insert(DisabledButton);
+        } else {
+            commands.entity(entity).remove::<DisabledButton>();
+        }
+    }
+}
+
+/// Handles interactions with the buttons for individual settings.
+fn setting_button_system(
+    mut settings: ResMut<Settings>,
+    interaction_query: Query<(&Interaction, &SettingButton), Changed<Interaction>>,
+    mut setting_text_query: Query<(&mut Text, &SettingText)>,
+    current_profile: Res<CurrentProfile>,
+) {
+    for (interaction, button) in interaction_query.iter() {
+        if *interaction == Interaction::Pressed {
+            button.0.cycle(&mut settings);
+            persistence::save_for_profile(&current_profile, SETTINGS_KEY, &*settings);
+
+            for (mut text, setting_text) in setting_text_query.iter_mut() {
+                text.sections[0].value = setting_text.0.get_label(&settings);
+            }
+        }
+    }
+}
+
+/// Handles interactions with the volume buttons.
+fn volume_button_system(
+    mut volume_settings: ResMut<VolumeSettings>,
+    interaction_query: Query<(&Interaction, &VolumeButton), Changed<Interaction>>,
+    mut volume_text_query: Query<(&mut Text, &VolumeText)>,
+    current_profile: Res<CurrentProfile>,
+) {
+    for (interaction, button) in interaction_query.iter() {
+        if *interaction == Interaction::Pressed {
+            button.channel.adjust(&mut volume_settings, button.change);
+            persistence::save_for_profile(&current_profile, VOLUME_SETTINGS_KEY, &*volume_settings);
+
+            for (mut text, volume_text) in volume_text_query.iter_mut() {
+                text.sections[0].value = volume_text.0.get_label(&volume_settings);
+            }
+        }
+    }
+}
+
+type InteractedKeyBindingsButtonTuple = (Changed<Interaction>, With<KeyBindingsButton>);
+
+/// Handles interactions with the button for opening the key bindings screen.
+fn key_bindings_button_system(
+    mut next_state: ResMut<NextState<GameState>>,
+    interaction_query: Query<&Interaction, InteractedKeyBindingsButtonTuple>,
+) {
+    for interaction in interaction_query.iter() {
+        if *interaction == Interaction::Pressed {
+            next_state.set(GameState::KeyBindings);
+        }
+    }
+}
+
+type InteractedSettingsBackButtonTuple = (Changed<Interaction>, With<SettingsBackButton>);
+
+/// Handles interactions with the back button on the settings screen.
+fn settings_back_button_system(
+    mut next_state: ResMut<NextState<GameState>>,
+    interaction_query: Query<&Interaction, InteractedSettingsBackButtonTuple>,
+) {
+    for interaction in interaction_query.iter() {
+        if *interaction == Interaction::Pressed {
+            next_state.set(GameState::Menu);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sword_shadow_steps() {
+        assert_eq!(None, SwordShadows::Off.get_step());
+        assert_eq!(Some(1), SwordShadows::Full.get_step());
+
+        // a few shadows should be fewer than the full set, but still more than none
+        let few_step = SwordShadows::Few.get_step().unwrap();
+        assert!(few_step > 1);
+    }
+
+    #[test]
+    fn every_settings_page_fits_on_screen() {
+        // any more than this and the back button gets pushed off the bottom of the screen
+        const MAX_SETTINGS_PER_PAGE: usize = 12;
+
+        for page in SettingsPage::iter() {
+            let num_settings = Setting::iter()
+                .filter(|setting| setting.get_page() == page)
+                .count();
+            assert!(num_settings <= MAX_SETTINGS_PER_PAGE);
+        }
+    }
+}